@@ -1,4 +1,31 @@
 pub const INITIAL_PRICE_DIVIDER: u64 = 800_000;       // lamports per one token (without decimal)
 pub const INITIAL_LAMPORTS_FOR_POOL: u64 = 10_000_000;   // 0.01SOL
 pub const TOKEN_SELL_LIMIT_PERCENT: u64 = 8000;     //  80%
+pub const PRICE_SCALE: u64 = 1_000_000_000;    //  curve prices are SOL amounts scaled by PRICE_SCALE ( lamports ), a price of 0.0001 SOL is 100_000
+pub const MAX_PRICE: u64 = 1_000_000_000_000_000_000;  //  highest spot price the curve may reach, in the spot_price unit of lamports per million whole tokens ( 1e9 SOL per million tokens ), far under u64 overflow
 pub const PROPORTION: u64 = 1280;      //  800M token is sold on 500SOL ===> (500 * 2 / 800) = 1.25 ===> 800 : 1.25 = 640 ====> 640 * 2 = 1280
+pub const FEE_BPS_DENOMINATOR: u64 = 10_000;   //  100% in basis points
+pub const SWAP_DIRECTION_BUY: u8 = 0;       //  SOL -> token
+pub const SWAP_DIRECTION_SELL: u8 = 1;      //  token -> SOL
+pub const SECONDS_PER_DAY: i64 = 86_400;
+pub const SECONDS_PER_YEAR: i64 = 365 * SECONDS_PER_DAY;
+pub const FEE_CAP_TIMELOCK_SECS: i64 = SECONDS_PER_DAY;   //  wait before a looser fee change cap applies
+pub const VOLATILITY_FULL_FEE_BPS: u64 = 1_000;   //  recent price moves adding up to 10% charge the maximum dynamic fee
+pub const LP_APR_WINDOW_SECS: i64 = 7 * SECONDS_PER_DAY;   //  span of the fee window the LP APR quote annualizes, restarted once exceeded
+pub const LP_APR_MIN_WINDOW_SECS: i64 = 60 * 60;   //  shortest span the LP APR quote annualizes over, a few seconds of fees would read as a huge APR
+pub const MIGRATION_READY: u8 = 0;          //  can_migrate: every precondition holds
+pub const MIGRATION_NO_RESERVES: u8 = 1;    //  can_migrate: the pool holds nothing to migrate
+pub const MIGRATION_NOT_COMPLETED: u8 = 2;  //  can_migrate: the curve has not graduated
+pub const MIGRATION_NO_DESTINATION: u8 = 3; //  can_migrate: no migration destination configured
+pub const MIGRATION_LOCKED: u8 = 4;         //  can_migrate: the curve graduated less than MIGRATION_TIMELOCK_SECS ago
+pub const MIGRATION_AUCTION_PENDING: u8 = 5;   //  can_migrate: the launch auction has not settled
+pub const MIGRATION_AUCTION_UNCLAIMED: u8 = 6; //  can_migrate: settled auction commitments are still to be claimed or refunded
+pub const MIGRATION_TIMELOCK_SECS: i64 = SECONDS_PER_DAY;  //  wait between graduation and the reserves being migrated
+pub const PAUSE_REASON_NONE: u8 = 0;        //  set_paused: not paused
+pub const PAUSE_REASON_MAINTENANCE: u8 = 1; //  set_paused: planned maintenance or an upgrade
+pub const PAUSE_REASON_SECURITY: u8 = 2;    //  set_paused: an incident or vulnerability is being handled
+pub const PAUSE_REASON_LEGAL: u8 = 3;       //  set_paused: a legal or compliance requirement
+pub const PAUSE_REASON_OTHER: u8 = 4;       //  set_paused: anything else, explained off-chain
+pub const SHARE_DECIMALS: u8 = 9;           //  liquidity shares count whole tokens with 9 decimals, whatever the mint decimals
+pub const MAX_POOLS_PER_BATCH: usize = 4;   //  pools create_pools_batch creates at most, bounded by the compute budget
+pub const ACC_FEE_PRECISION: u128 = 1_000_000_000_000;     //  scale of the per share fee accumulator
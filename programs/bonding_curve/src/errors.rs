@@ -49,4 +49,178 @@ pub enum CustomError {
    
     #[msg("Amount is negative")]
     NegativeNumber,
+
+    #[msg("Not the configuration authority")]
+    NotAuthority,
+
+    #[msg("Maximum number of liquidity providers reached")]
+    MaxLpsReached,
+
+    #[msg("Invalid swap path")]
+    InvalidSwapPath,
+
+    #[msg("Minimum hold time before selling is not met")]
+    MinHoldTimeNotMet,
+
+    #[msg("Curve is complete, trading is closed")]
+    CurveComplete,
+
+    #[msg("Mint does not match the pool")]
+    InvalidMint,
+
+    #[msg("Fee schedule must be sorted, within limits and not too long")]
+    InvalidFeeSchedule,
+
+    #[msg("Amount exceeds the accrued fees")]
+    ExceedsAccruedFees,
+
+    #[msg("Price impact exceeds the configured maximum slippage")]
+    SlippageExceeded,
+
+    #[msg("Pool already holds reserves")]
+    ReservesNotEmpty,
+
+    #[msg("Fee exceeds the swap input")]
+    FeeExceedsInput,
+
+    #[msg("Swap amount is below the minimum")]
+    AmountBelowMinimum,
+
+    #[msg("Selling in the same slot as a buy is blocked")]
+    SameSlotTradeBlocked,
+
+    #[msg("Insufficient pool reserves for this sell")]
+    InsufficientReserves,
+
+    #[msg("Invalid vesting schedule")]
+    InvalidVestingSchedule,
+
+    #[msg("Trading is closed outside the trading window")]
+    OutsideTradingWindow,
+
+    #[msg("Trading window bounds must be seconds within a day")]
+    InvalidTradingWindow,
+
+    #[msg("Swaps are locked while the post-swap hook runs")]
+    ReentrancyLocked,
+
+    #[msg("Post-swap hook program does not match the pool")]
+    InvalidPostSwapProgram,
+
+    #[msg("Swap limit for this slot reached")]
+    SlotSwapLimitReached,
+
+    #[msg("Swaps are paused")]
+    ProgramPaused,
+
+    #[msg("No unpause is ready to apply")]
+    UnpauseNotReady,
+
+    #[msg("A trader cannot refer their own swap")]
+    InvalidReferrer,
+
+    #[msg("Metadata URI is too long")]
+    MetadataUriTooLong,
+
+    #[msg("Fee recipients do not match the configuration")]
+    InvalidFeeRecipients,
+
+    #[msg("Instruction is only available in testing builds")]
+    TestingOnly,
+
+    #[msg("Creator has reached the maximum number of pools")]
+    CreatorPoolLimitReached,
+
+    #[msg("Target price is not above the current spot price")]
+    InvalidTargetPrice,
+
+    #[msg("No airdrop is available for this pool")]
+    AirdropNotAvailable,
+
+    #[msg("The curve has already traded")]
+    CurveAlreadyActive,
+
+    #[msg("Swap fee change is too large or too soon after the last one")]
+    FeeChangeTooFast,
+
+    #[msg("Too many pools in one batch")]
+    TooManyPools,
+
+    #[msg("Only whitelisted users can buy during the whitelist phase")]
+    NotWhitelisted,
+
+    #[msg("Trade comes too few slots after the pool's previous trade")]
+    TradeTooSoon,
+
+    #[msg("Trading opens once the launch auction is settled")]
+    AuctionActive,
+
+    #[msg("No launch auction is open on this pool")]
+    AuctionClosed,
+
+    #[msg("Launch auction commitments exceed what the curve can sell")]
+    AuctionFull,
+
+    #[msg("Launch auction is still taking commitments")]
+    AuctionNotEnded,
+
+    #[msg("Launch auction is not settled")]
+    AuctionNotSettled,
+
+    #[msg("Buy would push the price past the price cap")]
+    PriceCapReached,
+
+    #[msg("Swap or removal would take a reserve below its floor")]
+    ReserveFloorBreached,
+
+    #[msg("Shares cannot be split into the provider's own account")]
+    SplitToSelf,
+
+    #[msg("Liquidity removal moves the spot price past the tolerance")]
+    LpPriceImpactTooHigh,
+
+    #[msg("Pool has no liquidity yet")]
+    PoolNotSeeded,
+
+    #[msg("Trading after graduation reached its cap")]
+    PostGraduationCapReached,
+
+    #[msg("Unknown pause reason")]
+    InvalidPauseReason,
+
+    #[msg("Input and output token accounts are the same")]
+    DuplicateTokenAccount,
+
+    #[msg("Protocol seed of a funded pool only leaves with the creator's liquidity")]
+    ProtocolLiquidityLocked,
+
+    #[msg("Launch auction did not fill, commitments are refunded instead")]
+    AuctionNotFilled,
+
+    #[msg("Launch auction filled, commitments are claimed instead")]
+    AuctionFilled,
+
+    #[msg("Launch auction commitments are still unclaimed")]
+    AuctionUnclaimed,
+
+    #[msg("Protocol treasury cannot fund the amount")]
+    InsufficientTreasury,
+
+    #[msg("Pool needs more holders before it can graduate")]
+    InsufficientHolders,
+
+    #[msg("Other liquidity providers still hold shares in the pool")]
+    LiquidityProvidersRemaining,
+
+    #[msg("Buying for a recipient needs the recipient's user state")]
+    RecipientStateMissing,
+
+    #[msg("Post-swap hook can only change before trading opens")]
+    PostSwapProgramLocked,
+
+    #[msg("Airdrop mint of the pool is already set")]
+    AirdropMintSet,
+
+    #[msg("Reaching the target price costs more than the maximum input")]
+    MaxSolInExceeded,
 }
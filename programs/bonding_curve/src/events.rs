@@ -0,0 +1,359 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{FeeRecipient, FeeTier, ReserveFloor};
+
+#[event]
+pub struct PoolCreated {
+    pub pool: Pubkey,
+    pub creator: Pubkey,
+    pub token: Pubkey,
+    pub bump: u8,
+    pub launch_sell_fee_bps: u16,
+    pub launch_fee_window_secs: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SwapPathQuote {
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub hops: u8,
+}
+
+// `error_code` is the error the swap would fail with, 0 when it would go through
+#[event]
+pub struct SwapSimulation {
+    pub pool: Pubkey,
+    pub direction: u8,
+    pub amount: u64,
+    pub amount_out: u64,
+    pub fee: u64,
+    pub would_succeed: bool,
+    pub error_code: u32,
+}
+
+#[event]
+pub struct PoolCompleted {
+    pub pool: Pubkey,
+    pub creator: Pubkey,
+    pub creator_payout: u64,
+    pub reserve_token: u64,
+    pub reserve_sol: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CurveParamsUpdated {
+    pub pool: Pubkey,
+    pub virtual_sol_reserve_before: u64,
+    pub virtual_sol_reserve: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SpreadQuote {
+    pub pool: Pubkey,
+    pub amount: u64,
+    pub buy_price: u64,
+    pub sell_price: u64,
+}
+
+#[event]
+pub struct FairPriceQuote {
+    pub pool: Pubkey,
+    pub fair_price: u128,
+    pub spot_price: u64,
+}
+
+#[event]
+pub struct LpAprQuote {
+    pub pool: Pubkey,
+    pub fees_accrued_window: u64,
+    pub window_secs: i64,
+    pub reserve_sol: u64,
+    pub apr_bps: u64,
+}
+
+#[event]
+pub struct MarketCapQuote {
+    pub pool: Pubkey,
+    pub spot_price: u64,
+    pub tokens_sold: u64,
+    pub market_cap: u64,
+}
+
+#[event]
+pub struct GraduationQuote {
+    pub pool: Pubkey,
+    pub sol_remaining: u64,
+    pub token_remaining: u64,
+}
+
+#[event]
+pub struct GraduationSimulation {
+    pub pool: Pubkey,
+    pub completed: bool,
+    pub creator_payout: u64,
+    pub reserve_sol: u64,
+    pub reserve_token: u64,
+}
+
+#[event]
+pub struct MigrationCheck {
+    pub pool: Pubkey,
+    pub ready: bool,
+    pub reason: u8,
+}
+
+#[event]
+pub struct ConfigEvent {
+    pub fees: f64,
+    pub authority: Pubkey,
+    pub max_lps: u32,
+    pub withdraw_fee_bps: u16,
+    pub min_hold_secs: u32,
+    pub graduation_price: u64,
+    pub fee_schedule: Vec<FeeTier>,
+    pub max_slippage_bps: u16,
+    pub large_trade_threshold: u64,
+    pub large_trade_fee_bps: u16,
+    pub min_swap_amount: u64,
+    pub creator_graduation_payout: u64,
+    pub migration_reserve_bps: u16,
+    pub trading_window_start: u32,
+    pub trading_window_end: u32,
+    pub dump_tax_max_bps: u16,
+    pub migration_destination: Pubkey,
+    pub reward_mint: Pubkey,
+    pub reward_per_swap: u64,
+    pub max_swaps_per_slot: u16,
+    pub paused: bool,
+    pub unpause_delay_secs: u32,
+    pub unpause_ready_ts: i64,
+    pub creator_fee_share_bps: u16,
+    pub referral_fee_share_bps: u16,
+    pub buy_burn_bps: u16,
+    pub fee_recipients: Vec<FeeRecipient>,
+    pub max_pools_per_creator: u32,
+    pub auto_compound: bool,
+    pub max_fee_change_bps: u16,
+    pub fee_change_period_secs: u32,
+    pub last_fee_change_ts: i64,
+    pub min_trade_slot_spacing: u16,
+    pub launch_auction_secs: u32,
+    pub graduation_partial_fill: bool,
+    pub max_price: u64,
+    pub pool_count: u64,
+    pub min_reserve_floor: ReserveFloor,
+    pub max_lp_price_impact_bps: u16,
+    pub dynamic_fee_min_bps: u16,
+    pub dynamic_fee_max_bps: u16,
+    pub volatility_window_secs: u32,
+    pub post_graduation_trade_cap: u64,
+    pub graduation_sol_target: u64,
+    pub pause_reason: u8,
+    pub protocol_seed_amount: u64,
+    pub min_graduation_holders: u32,
+    pub protocol_seed_budget: u64,
+    pub pending_max_fee_change_bps: u16,
+    pub pending_fee_change_period_secs: u32,
+    pub fee_cap_ready_ts: i64,
+    pub pending_unpause_delay_secs: u32,
+    pub unpause_delay_ready_ts: i64,
+}
+
+#[event]
+pub struct SwapEvent {
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub recipient: Pubkey,
+    pub direction: u8,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub fee: u64,
+    pub reserve_token_before: u64,
+    pub reserve_sol_before: u64,
+    pub reserve_token_after: u64,
+    pub reserve_sol_after: u64,
+    pub total_supply: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AdminSwapEvent {
+    pub pool: Pubkey,
+    pub authority: Pubkey,
+    pub direction: u8,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub reserve_token_after: u64,
+    pub reserve_sol_after: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BuybackEvent {
+    pub pool: Pubkey,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub burned: bool,
+    pub spot_price_after: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AuctionCommitted {
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub amount: u64,
+    pub committed: u64, // Lamports the user committed so far
+    pub auction_sol: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AuctionSettled {
+    pub pool: Pubkey,
+    pub auction_sol: u64,
+    pub fee: u64,
+    pub tokens: u64,
+    pub clearing_price: u64, // Price every committer paid, fee excluded, in the unit of the spot price
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AuctionClaimed {
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub committed: u64,
+    pub tokens: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AirdropClaimed {
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub amount: u64,
+    pub claimed: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VestedClaimed {
+    pub pool: Pubkey,
+    pub beneficiary: Pubkey,
+    pub amount: u64,
+    pub claimed: u64,
+    pub total: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LiquidityAdded {
+    pub pool: Pubkey,
+    pub provider: Pubkey,
+    pub reserve_token_before: u64,
+    pub reserve_sol_before: u64,
+    pub reserve_token_after: u64,
+    pub reserve_sol_after: u64,
+    pub total_supply_before: u64,
+    pub total_supply_after: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LpFeesClaimed {
+    pub pool: Pubkey,
+    pub provider: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ProviderExited {
+    pub pool: Pubkey,
+    pub provider: Pubkey,
+    pub shares: u64,
+    pub fees: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ProviderSplit {
+    pub pool: Pubkey,
+    pub provider: Pubkey,
+    pub recipient: Pubkey,
+    pub shares: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CreatorFeesClaimed {
+    pub pool: Pubkey,
+    pub creator: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StuckSolRecovered {
+    pub pool: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ProtocolTokensWithdrawn {
+    pub pool: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LiquidityRemoved {
+    pub pool: Pubkey,
+    pub provider: Pubkey,
+    pub reserve_token_before: u64,
+    pub reserve_sol_before: u64,
+    pub reserve_token_after: u64,
+    pub reserve_sol_after: u64,
+    pub total_supply_before: u64,
+    pub total_supply_after: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PauseUpdated {
+    pub paused: bool,
+    pub pause_reason: u8,
+    pub unpause_ready_ts: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ProtocolLiquiditySeeded {
+    pub pool: Pubkey,
+    pub provider: Pubkey,
+    pub amount: u64,
+    pub shares: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AuctionRefunded {
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ProtocolSeedReturned {
+    pub pool: Pubkey,
+    pub seed: u64,
+    pub fees: u64,
+    pub timestamp: i64,
+}
@@ -1,13 +1,49 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
-    associated_token::AssociatedToken,
-    token::{Mint, Token, TokenAccount},
+    associated_token::{get_associated_token_address_with_program_id, AssociatedToken},
+    token_interface::{Mint, TokenAccount, TokenInterface},
 };
 
-use crate::state::{LiquidityPool, LiquidityPoolAccount};
+use crate::{
+    consts::FEE_BPS_DENOMINATOR,
+    errors::CustomError,
+    events::LiquidityAdded,
+    state::{CurveConfiguration, LiquidityPool, LiquidityPoolAccount, LiquidityProvider},
+};
 
 pub fn add_liquidity(ctx: Context<AddLiquidity>) -> Result<()> {
     let pool = &mut ctx.accounts.pool;
+    let liquidity_provider_account = &mut ctx.accounts.liquidity_provider_account;
+
+    // A graduated curve is frozen
+    if pool.completed {
+        return err!(CustomError::CurveComplete);
+    }
+
+    let deposited = ctx.accounts.token_mint.supply;
+
+    // A provider is counted once, the first time it funds the pool
+    if liquidity_provider_account.shares == 0 {
+        let max_lps = ctx.accounts.dex_configuration_account.max_lps;
+        if max_lps != 0 && pool.lp_count >= max_lps {
+            return err!(CustomError::MaxLpsReached);
+        }
+        pool.lp_count = pool
+            .lp_count
+            .checked_add(1)
+            .ok_or(CustomError::OverflowOrUnderflowOccurred)?;
+    }
+
+    let reserve_token_before = pool.reserve_token;
+    let reserve_sol_before = pool.reserve_sol;
+    let total_supply_before = pool.total_supply;
+
+    // Every deposit, the first into a pool the protocol only seeded included, moves the whole deposit.
+    // A provider holding less would fail inside the transfer or mint no shares, reject it before any
+    // account is touched
+    if deposited == 0 || ctx.accounts.user_token_account.amount < deposited {
+        return err!(CustomError::InvalidAmount);
+    }
 
     let token_accounts = (
         &mut *ctx.accounts.token_mint,
@@ -15,18 +51,64 @@ pub fn add_liquidity(ctx: Context<AddLiquidity>) -> Result<()> {
         &mut *ctx.accounts.user_token_account,
     );
 
-    pool.add_liquidity(
+    let received = pool.add_liquidity(
         token_accounts,
         &mut ctx.accounts.pool_sol_vault,
         &ctx.accounts.user,
         &ctx.accounts.token_program,
         &ctx.accounts.system_program,
     )?;
+
+    // Tokens held back for migration are never sold by the curve
+    pool.reserved_for_migration = (pool.reserve_token as u128
+        * ctx.accounts.dex_configuration_account.migration_reserve_bps as u128
+        / FEE_BPS_DENOMINATOR as u128) as u64;
+
+    // The first funding opens the launch auction, the curve trades once it is settled
+    let launch_auction_secs = ctx.accounts.dex_configuration_account.launch_auction_secs;
+    if pool.auction_end_ts == 0 && launch_auction_secs != 0 {
+        pool.auction_end_ts = pool.trade_start_ts + launch_auction_secs as i64;
+    }
+
+    emit!(LiquidityAdded {
+        pool: pool.key(),
+        provider: ctx.accounts.user.key(),
+        reserve_token_before,
+        reserve_sol_before,
+        reserve_token_after: pool.reserve_token,
+        reserve_sol_after: pool.reserve_sol,
+        total_supply_before,
+        total_supply_after: pool.total_supply,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    let shares = pool.shares_for(received)?;
+    liquidity_provider_account.accrue_shares_seconds(Clock::get()?.unix_timestamp)?;
+
+    // New shares start at the current accumulator so they only earn fees from now on
+    liquidity_provider_account.fee_debt = liquidity_provider_account
+        .fee_debt
+        .checked_add(pool.fee_debt_for(shares)?)
+        .ok_or(CustomError::FailedToAllocateShares)?;
+    liquidity_provider_account.shares = liquidity_provider_account
+        .shares
+        .checked_add(shares)
+        .ok_or(CustomError::FailedToAllocateShares)?;
+    pool.total_shares = pool
+        .total_shares
+        .checked_add(shares)
+        .ok_or(CustomError::FailedToAllocateShares)?;
     Ok(())
 }
 
 #[derive(Accounts)]
 pub struct AddLiquidity<'info> {
+    #[account(
+        seeds = [CurveConfiguration::SEED.as_bytes()],
+        bump = dex_configuration_account.bump,
+    )]
+    pub dex_configuration_account: Box<Account<'info, CurveConfiguration>>,
+
     #[account(
         mut,
         seeds = [LiquidityPool::POOL_SEED_PREFIX.as_bytes(), token_mint.key().as_ref()],
@@ -34,22 +116,34 @@ pub struct AddLiquidity<'info> {
     )]
     pub pool: Account<'info, LiquidityPool>,
 
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = LiquidityProvider::ACCOUNT_SIZE,
+        seeds = [LiquidityProvider::SEED_PREFIX.as_bytes(), pool.key().as_ref(), user.key().as_ref()],
+        bump,
+    )]
+    pub liquidity_provider_account: Box<Account<'info, LiquidityProvider>>,
+
     #[account(mut)]
-    pub token_mint: Box<Account<'info, Mint>>,
+    pub token_mint: Box<InterfaceAccount<'info, Mint>>,
 
     #[account(
         mut,
         associated_token::mint = token_mint,
-        associated_token::authority = pool
+        associated_token::authority = pool,
+        associated_token::token_program = token_program,
     )]
-    pub pool_token_account: Box<Account<'info, TokenAccount>>,
+    pub pool_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
 
     #[account(
         mut,
-        associated_token::mint = token_mint,
-        associated_token::authority = user,
+        address = get_associated_token_address_with_program_id(&user.key(), &token_mint.key(), &token_program.key())
+            @ anchor_lang::error::ErrorCode::AccountNotAssociatedTokenAccount,
+        token::mint = token_mint,
+        token::authority = user,
     )]
-    pub user_token_account: Box<Account<'info, TokenAccount>>,
+    pub user_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
 
     /// CHECK:
     #[account(
@@ -63,6 +157,6 @@ pub struct AddLiquidity<'info> {
     pub user: Signer<'info>,
     pub rent: Sysvar<'info, Rent>,
     pub system_program: Program<'info, System>,
-    pub token_program: Program<'info, Token>,
+    pub token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
 }
@@ -0,0 +1,40 @@
+use crate::{
+    errors::CustomError,
+    state::{CurveConfiguration, LiquidityPool, WhitelistEntry},
+};
+use anchor_lang::prelude::*;
+
+// Whitelists `user` to buy from the pool during its whitelist phase
+pub fn add_to_whitelist(_ctx: Context<AddToWhitelist>, _user: Pubkey) -> Result<()> {
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(user: Pubkey)]
+pub struct AddToWhitelist<'info> {
+    #[account(
+        seeds = [CurveConfiguration::SEED.as_bytes()],
+        bump = dex_configuration_account.bump,
+        has_one = authority @ CustomError::NotAuthority,
+    )]
+    pub dex_configuration_account: Box<Account<'info, CurveConfiguration>>,
+
+    #[account(
+        seeds = [LiquidityPool::POOL_SEED_PREFIX.as_bytes(), pool.token.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Box<Account<'info, LiquidityPool>>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = WhitelistEntry::ACCOUNT_SIZE,
+        seeds = [WhitelistEntry::SEED_PREFIX.as_bytes(), pool.key().as_ref(), user.as_ref()],
+        bump,
+    )]
+    pub whitelist_entry: Box<Account<'info, WhitelistEntry>>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
@@ -0,0 +1,150 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::{
+    consts::{SWAP_DIRECTION_BUY, SWAP_DIRECTION_SELL},
+    errors::CustomError,
+    events::AdminSwapEvent,
+    state::{CurveConfiguration, LiquidityPool, LiquidityPoolAccount},
+};
+
+// Recovery swap for the configuration authority. It trades on the curve without fees, hold times,
+// size or slippage limits, keeping only the reserve checks so the pool accounting stays whole
+pub fn admin_swap(ctx: Context<AdminSwap>, amount: u64, direction: u8, bump: u8) -> Result<()> {
+    if amount == 0 {
+        return err!(CustomError::InvalidAmount);
+    }
+
+    let pool = &mut ctx.accounts.pool;
+
+    let amount_out = match direction {
+        SWAP_DIRECTION_BUY => {
+            let amount_out = pool.buy_amount_out(amount)?;
+            if amount_out == 0 || amount_out > pool.available_token() {
+                return err!(CustomError::NotEnoughTokenInVault);
+            }
+
+            pool.reserve_sol = pool
+                .reserve_sol
+                .checked_add(amount)
+                .ok_or(CustomError::OverflowOrUnderflowOccurred)?;
+            pool.reserve_token -= amount_out;
+            pool.sol_raised = pool
+                .sol_raised
+                .checked_add(amount)
+                .ok_or(CustomError::OverflowOrUnderflowOccurred)?;
+
+            pool.transfer_sol_to_pool(
+                &ctx.accounts.authority,
+                &mut ctx.accounts.pool_sol_vault,
+                amount,
+                &ctx.accounts.system_program,
+            )?;
+            pool.transfer_token_from_pool(
+                &ctx.accounts.token_mint,
+                &ctx.accounts.pool_token_account,
+                &ctx.accounts.authority_token_account,
+                amount_out,
+                &ctx.accounts.token_program,
+            )?;
+            amount_out
+        }
+        SWAP_DIRECTION_SELL => {
+            let amount_out = pool.sell_amount_out(amount)?;
+            if pool.reserve_sol < amount_out {
+                return err!(CustomError::NotEnoughSolInVault);
+            }
+
+            pool.reserve_token = pool
+                .reserve_token
+                .checked_add(amount)
+                .ok_or(CustomError::OverflowOrUnderflowOccurred)?;
+            pool.reserve_sol -= amount_out;
+            pool.sol_raised = pool.sol_raised.saturating_sub(amount_out);
+
+            pool.transfer_token_to_pool(
+                &ctx.accounts.token_mint,
+                &ctx.accounts.authority_token_account,
+                &ctx.accounts.pool_token_account,
+                amount,
+                &ctx.accounts.authority,
+                &ctx.accounts.token_program,
+            )?;
+            pool.transfer_sol_from_pool(
+                &mut ctx.accounts.pool_sol_vault,
+                &ctx.accounts.authority,
+                amount_out,
+                bump,
+                &ctx.accounts.system_program,
+            )?;
+            amount_out
+        }
+        _ => return err!(CustomError::InvalidSwapPath),
+    };
+
+    emit!(AdminSwapEvent {
+        pool: pool.key(),
+        authority: ctx.accounts.authority.key(),
+        direction,
+        amount_in: amount,
+        amount_out,
+        reserve_token_after: pool.reserve_token,
+        reserve_sol_after: pool.reserve_sol,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct AdminSwap<'info> {
+    #[account(
+        seeds = [CurveConfiguration::SEED.as_bytes()],
+        bump = dex_configuration_account.bump,
+        has_one = authority @ CustomError::NotAuthority,
+    )]
+    pub dex_configuration_account: Box<Account<'info, CurveConfiguration>>,
+
+    #[account(
+        mut,
+        seeds = [LiquidityPool::POOL_SEED_PREFIX.as_bytes(), token_mint.key().as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Box<Account<'info, LiquidityPool>>,
+
+    pub token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program,
+    )]
+    pub pool_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK:
+    #[account(
+        mut,
+        seeds = [LiquidityPool::SOL_VAULT_PREFIX.as_bytes(), token_mint.key().as_ref()],
+        bump
+    )]
+    pub pool_sol_vault: AccountInfo<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        associated_token::mint = token_mint,
+        associated_token::authority = authority,
+        associated_token::token_program = token_program,
+    )]
+    pub authority_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub rent: Sysvar<'info, Rent>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
@@ -0,0 +1,47 @@
+use crate::{
+    consts::PAUSE_REASON_NONE,
+    errors::CustomError,
+    events::PauseUpdated,
+    state::{CurveConfiguration, TestClock},
+    utils::current_timestamp,
+};
+use anchor_lang::prelude::*;
+
+// Finishes an unpause scheduled by set_paused once its delay has passed
+pub fn apply_unpause(ctx: Context<ApplyUnpause>) -> Result<()> {
+    let now = current_timestamp(ctx.accounts.test_clock.as_deref().map(|test_clock| &**test_clock))?;
+    let dex_config = &mut ctx.accounts.dex_configuration_account;
+
+    if dex_config.unpause_ready_ts == 0 || now < dex_config.unpause_ready_ts {
+        return err!(CustomError::UnpauseNotReady);
+    }
+
+    dex_config.paused = false;
+    dex_config.pause_reason = PAUSE_REASON_NONE;
+    dex_config.unpause_ready_ts = 0;
+
+    emit!(PauseUpdated {
+        paused: false,
+        pause_reason: PAUSE_REASON_NONE,
+        unpause_ready_ts: 0,
+        timestamp: now,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct ApplyUnpause<'info> {
+    #[account(
+        mut,
+        seeds = [CurveConfiguration::SEED.as_bytes()],
+        bump = dex_configuration_account.bump,
+        has_one = authority @ CustomError::NotAuthority,
+    )]
+    pub dex_configuration_account: Box<Account<'info, CurveConfiguration>>,
+
+    // Testing builds only, replaces the cluster clock for every time rule
+    #[account(seeds = [TestClock::SEED.as_bytes()], bump)]
+    pub test_clock: Option<Box<Account<'info, TestClock>>>,
+
+    pub authority: Signer<'info>,
+}
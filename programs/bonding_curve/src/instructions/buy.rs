@@ -1,37 +1,160 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token::{Mint, Token, TokenAccount},
+    token_interface::{Mint, TokenAccount, TokenInterface},
 };
 
-use crate::state::{CurveConfiguration, LiquidityPool, LiquidityPoolAccount};
+use crate::{
+    consts::SWAP_DIRECTION_BUY,
+    errors::CustomError,
+    events::SwapEvent,
+    state::{CurveConfiguration, LiquidityPool, LiquidityPoolAccount, TestClock, UserState, WhitelistEntry},
+    utils::{current_timestamp, invoke_post_swap_hook, pay_swap_reward, PostSwapHookArgs},
+};
+
+pub fn buy<'info>(
+    ctx: Context<'_, '_, '_, 'info, Buy<'info>>,
+    amount: u64,
+) -> Result<()> {
+    // Remaining accounts hold the configured fee recipients first, then the post-swap hook accounts
+    let recipient_count = ctx.accounts.dex_configuration_account.fee_recipients.len();
+    let (fee_recipients, hook_accounts) =
+        ctx.remaining_accounts.split_at(recipient_count.min(ctx.remaining_accounts.len()));
+
+    // The tokens and the sell guards they come with belong to the recipient when one is given
+    if ctx.accounts.recipient_token_account.is_some() != ctx.accounts.recipient_state.is_some() {
+        return err!(CustomError::RecipientStateMissing);
+    }
 
-pub fn buy(ctx: Context<Buy>, amount: u64) -> Result<()> {
+    let timestamp = current_timestamp(ctx.accounts.test_clock.as_deref().map(|test_clock| &**test_clock))?;
     let pool = &mut ctx.accounts.pool;
+    pool.check_whitelisted(ctx.accounts.whitelist_entry.is_some(), timestamp)?;
 
+    let reserve_token_before = pool.reserve_token;
+    let reserve_sol_before = pool.reserve_sol;
+    let completed_before = pool.completed;
+
+    let (recipient, holder_state) = match ctx.accounts.recipient_state.as_deref_mut() {
+        Some(recipient_state) => (recipient_owner(&ctx.accounts.recipient_token_account), recipient_state),
+        None => (ctx.accounts.user.key(), &mut *ctx.accounts.user_state),
+    };
+    // The wallet receiving the tokens counts as a holder before this buy's graduation check
+    let new_holder = pool.count_holder(holder_state);
+
+    // The bought tokens go to the recipient when one is given, an integration buying for someone else
+    let output_token_account = match ctx.accounts.recipient_token_account.as_deref_mut() {
+        Some(recipient_token_account) => recipient_token_account,
+        None => &mut *ctx.accounts.user_token_account,
+    };
     let token_one_accounts = (
         &mut *ctx.accounts.token_mint,
         &mut *ctx.accounts.pool_token_account,
-        &mut *ctx.accounts.user_token_account,
+        output_token_account,
     );
 
-    pool.buy(
+    let curve_phase = !pool.completed;
+    let (amount_in, fee) = pool.buy(
+        &ctx.accounts.dex_configuration_account,
         token_one_accounts,
         &mut ctx.accounts.pool_sol_vault,
         amount,
+        ctx.bumps.pool_sol_vault,
+        timestamp,
         &ctx.accounts.user,
+        ctx.accounts.referrer.as_deref(),
+        fee_recipients,
         &ctx.accounts.token_program,
         &ctx.accounts.system_program,
     )?;
+    pool.check_holders(&ctx.accounts.dex_configuration_account, new_holder)?;
+
+    holder_state.last_buy_ts = timestamp;
+    holder_state.last_buy_slot = Clock::get()?.slot;
+
+    // Buy volume weighs each holder's share of the post-graduation airdrop, only the curve phase counts
+    if curve_phase {
+        holder_state.buy_volume = holder_state
+            .buy_volume
+            .checked_add(amount_in)
+            .ok_or(CustomError::OverflowOrUnderflowOccurred)?;
+        pool.total_buy_volume = pool
+            .total_buy_volume
+            .checked_add(amount_in)
+            .ok_or(CustomError::OverflowOrUnderflowOccurred)?;
+    }
+
+    let amount_out = reserve_token_before - pool.reserve_token;
+    let amount_out = amount_out - ctx.accounts.dex_configuration_account.buy_burn_amount(amount_out)?;
+    pool.credit_holder(holder_state, amount_out)?;
+    emit!(SwapEvent {
+        pool: pool.key(),
+        user: ctx.accounts.user.key(),
+        recipient,
+        direction: SWAP_DIRECTION_BUY,
+        amount_in,
+        amount_out,
+        fee,
+        reserve_token_before,
+        reserve_sol_before,
+        reserve_token_after: pool.reserve_token,
+        reserve_sol_after: pool.reserve_sol,
+        total_supply: pool.total_supply,
+        timestamp,
+    });
+
+    // The buy that graduates the curve pays the creator out of the raised SOL
+    if !completed_before && pool.completed {
+        pool.complete_graduation(
+            &ctx.accounts.dex_configuration_account,
+            &mut ctx.accounts.pool_sol_vault,
+            &ctx.accounts.creator,
+            ctx.bumps.pool_sol_vault,
+            &ctx.accounts.system_program,
+            timestamp,
+        )?;
+    }
+
+    pay_swap_reward(
+        &ctx.accounts.dex_configuration_account,
+        ctx.accounts.reward_mint.as_deref(),
+        ctx.accounts.reward_vault.as_deref(),
+        ctx.accounts.user_reward_account.as_deref(),
+        &ctx.accounts.token_program,
+    )?;
+
+    if let Some(hook_program) = ctx.accounts.pool.post_swap_program {
+        // Persist the pool with the lock set so a hook calling back into the curve is refused
+        ctx.accounts.pool.locked = true;
+        ctx.accounts.pool.exit(&crate::ID)?;
+        invoke_post_swap_hook(
+            hook_program,
+            hook_accounts,
+            PostSwapHookArgs {
+                pool: ctx.accounts.pool.key(),
+                user: ctx.accounts.user.key(),
+                direction: SWAP_DIRECTION_BUY,
+                amount_in,
+                amount_out,
+            },
+        )?;
+        ctx.accounts.pool.locked = false;
+    }
     Ok(())
 }
 
+// Owner of the recipient token account, the default key without one
+pub fn recipient_owner(recipient_token_account: &Option<Box<InterfaceAccount<TokenAccount>>>) -> Pubkey {
+    recipient_token_account
+        .as_ref()
+        .map_or(Pubkey::default(), |recipient_token_account| recipient_token_account.owner)
+}
+
 #[derive(Accounts)]
 pub struct Buy<'info> {
     #[account(
         mut,
         seeds = [CurveConfiguration::SEED.as_bytes()],
-        bump,
+        bump = dex_configuration_account.bump,
     )]
     pub dex_configuration_account: Box<Account<'info, CurveConfiguration>>,
 
@@ -43,14 +166,15 @@ pub struct Buy<'info> {
     pub pool: Box<Account<'info, LiquidityPool>>,
 
     #[account(mut)]
-    pub token_mint: Box<Account<'info, Mint>>,
+    pub token_mint: Box<InterfaceAccount<'info, Mint>>,
 
     #[account(
         mut,
         associated_token::mint = token_mint,
-        associated_token::authority = pool
+        associated_token::authority = pool,
+        associated_token::token_program = token_program,
     )]
-    pub pool_token_account: Box<Account<'info, TokenAccount>>,
+    pub pool_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
 
     /// CHECK:
     #[account(
@@ -65,13 +189,81 @@ pub struct Buy<'info> {
         payer = user,
         associated_token::mint = token_mint,
         associated_token::authority = user,
+        associated_token::token_program = token_program,
+    )]
+    pub user_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = recipient_token_account.mint == token_mint.key() @ CustomError::InvalidMint,
+        // Paying out into the pool's own account would take tokens off the reserve and keep them
+        constraint = recipient_token_account.key() != pool_token_account.key() @ CustomError::DuplicateTokenAccount,
+    )]
+    pub recipient_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = UserState::ACCOUNT_SIZE,
+        seeds = [UserState::SEED_PREFIX.as_bytes(), pool.key().as_ref(), user.key().as_ref()],
+        bump,
+    )]
+    pub user_state: Box<Account<'info, UserState>>,
+
+    // The recipient's state, required with a recipient token account
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = UserState::ACCOUNT_SIZE,
+        seeds = [
+            UserState::SEED_PREFIX.as_bytes(),
+            pool.key().as_ref(),
+            recipient_owner(&recipient_token_account).as_ref(),
+        ],
+        bump,
+    )]
+    pub recipient_state: Option<Box<Account<'info, UserState>>>,
+
+    // Required to buy while the pool is in its whitelist phase
+    #[account(
+        seeds = [WhitelistEntry::SEED_PREFIX.as_bytes(), pool.key().as_ref(), user.key().as_ref()],
+        bump,
     )]
-    pub user_token_account: Box<Account<'info, TokenAccount>>,
+    pub whitelist_entry: Option<Box<Account<'info, WhitelistEntry>>>,
+
+    /// CHECK: receives the graduation payout, checked against the pool creator
+    #[account(mut, address = pool.creator)]
+    pub creator: AccountInfo<'info>,
+
+    #[account(address = dex_configuration_account.reward_mint)]
+    pub reward_mint: Option<Box<InterfaceAccount<'info, Mint>>>,
+
+    #[account(
+        mut,
+        token::mint = dex_configuration_account.reward_mint,
+        token::authority = dex_configuration_account,
+    )]
+    pub reward_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        mut,
+        token::mint = dex_configuration_account.reward_mint,
+        token::authority = user,
+    )]
+    pub user_reward_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// CHECK: only receives the referral share of the swap fee
+    #[account(mut, constraint = referrer.key() != user.key() @ CustomError::InvalidReferrer)]
+    pub referrer: Option<UncheckedAccount<'info>>,
+
+    // Testing builds only, replaces the cluster clock for every time rule
+    #[account(seeds = [TestClock::SEED.as_bytes()], bump)]
+    pub test_clock: Option<Box<Account<'info, TestClock>>>,
 
     #[account(mut)]
     pub user: Signer<'info>,
     pub rent: Sysvar<'info, Rent>,
     pub system_program: Program<'info, System>,
-    pub token_program: Program<'info, Token>,
+    pub token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
 }
@@ -0,0 +1,144 @@
+use anchor_lang::{prelude::*, system_program};
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use crate::{
+    errors::CustomError,
+    events::BuybackEvent,
+    state::{CurveConfiguration, LiquidityPool, LiquidityPoolAccount},
+};
+
+// Spends `amount` lamports of the protocol treasury on a curve buy, through the same gates as a buy.
+// The lamports join the SOL reserve and the tokens bought are burned, or kept in the pool as protocol
+// tokens for `withdraw_protocol_tokens`. The fees owed to the liquidity providers are not touched
+pub fn buyback(ctx: Context<Buyback>, amount: u64, burn: bool) -> Result<()> {
+    let config = &ctx.accounts.dex_configuration_account;
+    let pool = &mut ctx.accounts.pool;
+    let clock = Clock::get()?;
+
+    if pool.completed {
+        return err!(CustomError::CurveComplete);
+    }
+    pool.check_swap_open(config, amount, clock.slot, clock.unix_timestamp)?;
+    pool.record_swap(config.max_swaps_per_slot, clock.slot)?;
+
+    // The treasury keeps its rent-exempt minimum
+    let treasury = &ctx.accounts.protocol_treasury;
+    if amount > treasury.lamports().saturating_sub(Rent::get()?.minimum_balance(0)) {
+        return err!(CustomError::InsufficientTreasury);
+    }
+
+    let amount_out = pool.buy_amount_out(amount)?;
+    if amount_out == 0 || amount_out > pool.available_token() {
+        return err!(CustomError::NotEnoughTokenInVault);
+    }
+    pool.check_price_cap(config, amount_out)?;
+
+    system_program::transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.system_program.to_account_info(),
+            system_program::Transfer {
+                from: treasury.to_account_info(),
+                to: ctx.accounts.pool_sol_vault.to_account_info(),
+            },
+            &[&[CurveConfiguration::TREASURY_SEED.as_bytes(), &[ctx.bumps.protocol_treasury]]],
+        ),
+        amount,
+    )?;
+
+    pool.reserve_sol = pool
+        .reserve_sol
+        .checked_add(amount)
+        .ok_or(CustomError::OverflowOrUnderflowOccurred)?;
+    pool.reserve_token -= amount_out;
+    pool.sol_raised = pool
+        .sol_raised
+        .checked_add(amount)
+        .ok_or(CustomError::OverflowOrUnderflowOccurred)?;
+
+    if burn {
+        pool.burn_token_from_pool(
+            &ctx.accounts.token_mint,
+            &ctx.accounts.pool_token_account,
+            amount_out,
+            &ctx.accounts.token_program,
+        )?;
+    } else {
+        pool.fee_token_accrued = pool
+            .fee_token_accrued
+            .checked_add(amount_out)
+            .ok_or(CustomError::OverflowOrUnderflowOccurred)?;
+    }
+
+    if pool.check_graduation(config) {
+        pool.complete_graduation(
+            config,
+            &mut ctx.accounts.pool_sol_vault,
+            &ctx.accounts.creator,
+            ctx.bumps.pool_sol_vault,
+            &ctx.accounts.system_program,
+            clock.unix_timestamp,
+        )?;
+    }
+
+    emit!(BuybackEvent {
+        pool: pool.key(),
+        amount_in: amount,
+        amount_out,
+        burned: burn,
+        spot_price_after: pool.spot_price(),
+        timestamp: clock.unix_timestamp,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct Buyback<'info> {
+    #[account(
+        seeds = [CurveConfiguration::SEED.as_bytes()],
+        bump = dex_configuration_account.bump,
+        has_one = authority @ CustomError::NotAuthority,
+    )]
+    pub dex_configuration_account: Box<Account<'info, CurveConfiguration>>,
+
+    #[account(
+        mut,
+        seeds = [LiquidityPool::POOL_SEED_PREFIX.as_bytes(), token_mint.key().as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Box<Account<'info, LiquidityPool>>,
+
+    #[account(mut)]
+    pub token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program,
+    )]
+    pub pool_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: system account owned by the program's treasury seed, only ever debited
+    #[account(
+        mut,
+        seeds = [CurveConfiguration::TREASURY_SEED.as_bytes()],
+        bump
+    )]
+    pub protocol_treasury: AccountInfo<'info>,
+
+    /// CHECK:
+    #[account(
+        mut,
+        seeds = [LiquidityPool::SOL_VAULT_PREFIX.as_bytes(), token_mint.key().as_ref()],
+        bump
+    )]
+    pub pool_sol_vault: AccountInfo<'info>,
+
+    /// CHECK: receives the graduation payout, checked against the pool creator
+    #[account(mut, address = pool.creator)]
+    pub creator: AccountInfo<'info>,
+
+    pub authority: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
@@ -0,0 +1,57 @@
+use crate::{
+    consts::{
+        MIGRATION_AUCTION_PENDING, MIGRATION_AUCTION_UNCLAIMED, MIGRATION_LOCKED, MIGRATION_NOT_COMPLETED,
+        MIGRATION_NO_DESTINATION, MIGRATION_NO_RESERVES, MIGRATION_READY, MIGRATION_TIMELOCK_SECS,
+    },
+    events::MigrationCheck,
+    state::{CurveConfiguration, LiquidityPool, TestClock},
+    utils::current_timestamp,
+};
+use anchor_lang::prelude::*;
+
+// Dry run of the migration preconditions for keepers, emits the first one failing as the reason.
+// Graduation comes first, a pool that never graduated reports that rather than its empty reserves
+pub fn can_migrate(ctx: Context<CanMigrate>) -> Result<()> {
+    let pool = &ctx.accounts.pool;
+    let now = current_timestamp(ctx.accounts.test_clock.as_deref().map(|test_clock| &**test_clock))?;
+
+    let reason = if !pool.completed {
+        MIGRATION_NOT_COMPLETED
+    } else if ctx.accounts.dex_configuration_account.migration_destination == Pubkey::default() {
+        MIGRATION_NO_DESTINATION
+    } else if pool.auction_pending() {
+        // Committed SOL and auction tokens belong to the committers, not to the reserves
+        MIGRATION_AUCTION_PENDING
+    } else if pool.auction_unclaimed() {
+        MIGRATION_AUCTION_UNCLAIMED
+    } else if now < pool.graduated_ts.saturating_add(MIGRATION_TIMELOCK_SECS) {
+        MIGRATION_LOCKED
+    } else if pool.reserve_token == 0 && pool.reserve_sol == 0 {
+        MIGRATION_NO_RESERVES
+    } else {
+        MIGRATION_READY
+    };
+
+    emit!(MigrationCheck {
+        pool: pool.key(),
+        ready: reason == MIGRATION_READY,
+        reason,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct CanMigrate<'info> {
+    #[account(
+        seeds = [CurveConfiguration::SEED.as_bytes()],
+        bump = dex_configuration_account.bump,
+    )]
+    pub dex_configuration_account: Box<Account<'info, CurveConfiguration>>,
+
+    pub pool: Box<Account<'info, LiquidityPool>>,
+
+    // Testing builds only, replaces the cluster clock for every time rule
+    #[account(seeds = [TestClock::SEED.as_bytes()], bump)]
+    pub test_clock: Option<Box<Account<'info, TestClock>>>,
+}
@@ -0,0 +1,93 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::{
+    errors::CustomError,
+    events::AirdropClaimed,
+    state::{LiquidityPool, LiquidityPoolAccount, UserState},
+};
+
+// Pays a buyer their share of the airdrop, proportional to their buy volume during the curve phase,
+// minus what they already claimed
+pub fn claim_airdrop(ctx: Context<ClaimAirdrop>) -> Result<()> {
+    let pool = &mut ctx.accounts.pool;
+    if !pool.completed {
+        return err!(CustomError::AirdropNotAvailable);
+    }
+
+    let user_state = &mut ctx.accounts.user_state;
+    let amount = pool
+        .airdrop_share(user_state.buy_volume, ctx.accounts.airdrop_vault.amount)?
+        .checked_sub(user_state.airdrop_claimed)
+        .ok_or(CustomError::OverflowOrUnderflowOccurred)?;
+
+    if amount > 0 {
+        user_state.airdrop_claimed += amount;
+        pool.airdrop_claimed = pool
+            .airdrop_claimed
+            .checked_add(amount)
+            .ok_or(CustomError::OverflowOrUnderflowOccurred)?;
+        pool.transfer_token_from_pool(
+            &ctx.accounts.airdrop_mint,
+            &ctx.accounts.airdrop_vault,
+            &ctx.accounts.user_airdrop_account,
+            amount,
+            &ctx.accounts.token_program,
+        )?;
+    }
+
+    emit!(AirdropClaimed {
+        pool: pool.key(),
+        user: ctx.accounts.user.key(),
+        amount,
+        claimed: user_state.airdrop_claimed,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct ClaimAirdrop<'info> {
+    #[account(
+        mut,
+        seeds = [LiquidityPool::POOL_SEED_PREFIX.as_bytes(), pool.token.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Box<Account<'info, LiquidityPool>>,
+
+    #[account(address = pool.airdrop_mint @ CustomError::AirdropNotAvailable)]
+    pub airdrop_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = airdrop_mint,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program,
+    )]
+    pub airdrop_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [UserState::SEED_PREFIX.as_bytes(), pool.key().as_ref(), user.key().as_ref()],
+        bump,
+    )]
+    pub user_state: Box<Account<'info, UserState>>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = airdrop_mint,
+        associated_token::authority = user,
+        associated_token::token_program = token_program,
+    )]
+    pub user_airdrop_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
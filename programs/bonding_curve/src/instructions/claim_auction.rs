@@ -0,0 +1,109 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::{
+    errors::CustomError,
+    events::AuctionClaimed,
+    state::{AuctionCommitment, LiquidityPool, LiquidityPoolAccount, UserState},
+};
+
+// Pays a committer their share of the tokens the settled auction bought, in proportion to their commitment,
+// and closes the commitment
+pub fn claim_auction(ctx: Context<ClaimAuction>) -> Result<()> {
+    let pool = &mut ctx.accounts.pool;
+    if !pool.auction_settled {
+        return err!(CustomError::AuctionNotSettled);
+    }
+    if pool.auction_tokens == 0 {
+        return err!(CustomError::AuctionNotFilled);
+    }
+
+    let committed = ctx.accounts.commitment.amount;
+    let tokens = u64::try_from(committed as u128 * pool.auction_tokens as u128 / pool.auction_sol as u128)
+        .map_err(|_| error!(CustomError::OverflowOrUnderflowOccurred))?;
+    pool.auction_sol_claimed = pool
+        .auction_sol_claimed
+        .checked_add(committed)
+        .ok_or(CustomError::OverflowOrUnderflowOccurred)?;
+    pool.transfer_token_from_pool(
+        &ctx.accounts.token_mint,
+        &ctx.accounts.pool_token_account,
+        &ctx.accounts.user_token_account,
+        tokens,
+        &ctx.accounts.token_program,
+    )?;
+    // Committers become holders when they claim, the next trade can then graduate the pool
+    let user_state = &mut ctx.accounts.user_state;
+    pool.count_holder(user_state);
+    pool.credit_holder(user_state, tokens)?;
+
+    // The commitment counts as buy volume like a regular buy
+    user_state.buy_volume = user_state
+        .buy_volume
+        .checked_add(committed)
+        .ok_or(CustomError::OverflowOrUnderflowOccurred)?;
+
+    emit!(AuctionClaimed {
+        pool: pool.key(),
+        user: ctx.accounts.user.key(),
+        committed,
+        tokens,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct ClaimAuction<'info> {
+    #[account(
+        mut,
+        seeds = [LiquidityPool::POOL_SEED_PREFIX.as_bytes(), token_mint.key().as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Box<Account<'info, LiquidityPool>>,
+
+    pub token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program,
+    )]
+    pub pool_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = token_mint,
+        associated_token::authority = user,
+        associated_token::token_program = token_program,
+    )]
+    pub user_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        close = user,
+        seeds = [AuctionCommitment::SEED_PREFIX.as_bytes(), pool.key().as_ref(), user.key().as_ref()],
+        bump,
+    )]
+    pub commitment: Box<Account<'info, AuctionCommitment>>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = UserState::ACCOUNT_SIZE,
+        seeds = [UserState::SEED_PREFIX.as_bytes(), pool.key().as_ref(), user.key().as_ref()],
+        bump,
+    )]
+    pub user_state: Box<Account<'info, UserState>>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
@@ -0,0 +1,56 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    errors::CustomError,
+    events::CreatorFeesClaimed,
+    state::{LiquidityPool, LiquidityPoolAccount},
+};
+
+pub fn claim_creator_fees(ctx: Context<ClaimCreatorFees>, bump: u8) -> Result<()> {
+    let pool = &mut ctx.accounts.pool;
+
+    let amount = pool.creator_fee_accrued;
+    if amount == 0 {
+        return Ok(());
+    }
+    pool.creator_fee_accrued = 0;
+
+    pool.transfer_sol_from_pool(
+        &mut ctx.accounts.pool_sol_vault,
+        &ctx.accounts.creator,
+        amount,
+        bump,
+        &ctx.accounts.system_program,
+    )?;
+
+    emit!(CreatorFeesClaimed {
+        pool: pool.key(),
+        creator: ctx.accounts.creator.key(),
+        amount,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct ClaimCreatorFees<'info> {
+    #[account(
+        mut,
+        seeds = [LiquidityPool::POOL_SEED_PREFIX.as_bytes(), pool.token.as_ref()],
+        bump = pool.bump,
+        has_one = creator @ CustomError::NotCreator,
+    )]
+    pub pool: Box<Account<'info, LiquidityPool>>,
+
+    /// CHECK:
+    #[account(
+        mut,
+        seeds = [LiquidityPool::SOL_VAULT_PREFIX.as_bytes(), pool.token.as_ref()],
+        bump
+    )]
+    pub pool_sol_vault: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
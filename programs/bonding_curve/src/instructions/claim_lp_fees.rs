@@ -0,0 +1,79 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+
+use crate::{
+    errors::CustomError,
+    events::LpFeesClaimed,
+    state::{LiquidityPool, LiquidityPoolAccount, LiquidityProvider, TestClock},
+    utils::current_timestamp,
+};
+
+pub fn claim_lp_fees(ctx: Context<ClaimLpFees>, bump: u8) -> Result<()> {
+    let timestamp = current_timestamp(ctx.accounts.test_clock.as_deref().map(|test_clock| &**test_clock))?;
+    let pool = &mut ctx.accounts.pool;
+    let liquidity_provider_account = &mut ctx.accounts.liquidity_provider_account;
+
+    liquidity_provider_account.accrue_shares_seconds(timestamp)?;
+    let amount = pool.pending_lp_fees(liquidity_provider_account)?;
+    liquidity_provider_account.fee_debt = pool.fee_debt_for(liquidity_provider_account.shares)?;
+
+    if amount == 0 {
+        return Ok(());
+    }
+
+    pool.fee_sol_accrued = pool
+        .fee_sol_accrued
+        .checked_sub(amount)
+        .ok_or(CustomError::OverflowOrUnderflowOccurred)?;
+
+    pool.transfer_sol_from_pool(
+        &mut ctx.accounts.pool_sol_vault,
+        &ctx.accounts.user,
+        amount,
+        bump,
+        &ctx.accounts.system_program,
+    )?;
+
+    emit!(LpFeesClaimed {
+        pool: pool.key(),
+        provider: ctx.accounts.user.key(),
+        amount,
+        timestamp,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct ClaimLpFees<'info> {
+    #[account(
+        mut,
+        seeds = [LiquidityPool::POOL_SEED_PREFIX.as_bytes(), token_mint.key().as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Box<Account<'info, LiquidityPool>>,
+
+    #[account(
+        mut,
+        seeds = [LiquidityProvider::SEED_PREFIX.as_bytes(), pool.key().as_ref(), user.key().as_ref()],
+        bump,
+    )]
+    pub liquidity_provider_account: Box<Account<'info, LiquidityProvider>>,
+
+    pub token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// CHECK:
+    #[account(
+        mut,
+        seeds = [LiquidityPool::SOL_VAULT_PREFIX.as_bytes(), token_mint.key().as_ref()],
+        bump
+    )]
+    pub pool_sol_vault: AccountInfo<'info>,
+
+    // Testing builds only, replaces the cluster clock for every time rule
+    #[account(seeds = [TestClock::SEED.as_bytes()], bump)]
+    pub test_clock: Option<Box<Account<'info, TestClock>>>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
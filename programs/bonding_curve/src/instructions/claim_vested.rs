@@ -0,0 +1,100 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{self, Mint, TokenAccount, TokenInterface},
+};
+
+use crate::{
+    errors::CustomError,
+    events::VestedClaimed,
+    state::{CreatorVesting, LiquidityPool, TestClock},
+    utils::current_timestamp,
+};
+
+// Releases whatever vested since the last claim, nothing before the cliff
+pub fn claim_vested(ctx: Context<ClaimVested>) -> Result<()> {
+    let creator_vesting = &mut ctx.accounts.creator_vesting;
+    let timestamp = current_timestamp(ctx.accounts.test_clock.as_deref().map(|test_clock| &**test_clock))?;
+
+    let amount = creator_vesting
+        .vested_amount(timestamp)
+        .checked_sub(creator_vesting.claimed)
+        .ok_or(CustomError::OverflowOrUnderflowOccurred)?;
+
+    if amount > 0 {
+        creator_vesting.claimed += amount;
+
+        let pool_key = ctx.accounts.pool.key();
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token_interface::TransferChecked {
+                    from: ctx.accounts.vesting_vault.to_account_info(),
+                    mint: ctx.accounts.token_mint.to_account_info(),
+                    to: ctx.accounts.beneficiary_token_account.to_account_info(),
+                    authority: creator_vesting.to_account_info(),
+                },
+                &[&[
+                    CreatorVesting::SEED_PREFIX.as_bytes(),
+                    pool_key.as_ref(),
+                    &[creator_vesting.bump],
+                ]],
+            ),
+            amount,
+            ctx.accounts.token_mint.decimals,
+        )?;
+    }
+
+    emit!(VestedClaimed {
+        pool: creator_vesting.pool,
+        beneficiary: creator_vesting.beneficiary,
+        amount,
+        claimed: creator_vesting.claimed,
+        total: creator_vesting.total,
+        timestamp,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct ClaimVested<'info> {
+    #[account(
+        seeds = [LiquidityPool::POOL_SEED_PREFIX.as_bytes(), token_mint.key().as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Box<Account<'info, LiquidityPool>>,
+
+    #[account(
+        mut,
+        seeds = [CreatorVesting::SEED_PREFIX.as_bytes(), pool.key().as_ref()],
+        bump = creator_vesting.bump,
+        has_one = beneficiary @ CustomError::NotCreator,
+    )]
+    pub creator_vesting: Box<Account<'info, CreatorVesting>>,
+
+    pub token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = creator_vesting,
+        associated_token::token_program = token_program,
+    )]
+    pub vesting_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = beneficiary,
+        associated_token::token_program = token_program,
+    )]
+    pub beneficiary_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    // Testing builds only, replaces the cluster clock for every time rule
+    #[account(seeds = [TestClock::SEED.as_bytes()], bump)]
+    pub test_clock: Option<Box<Account<'info, TestClock>>>,
+
+    pub beneficiary: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
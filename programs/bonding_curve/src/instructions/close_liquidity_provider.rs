@@ -0,0 +1,83 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+
+use crate::{
+    errors::CustomError,
+    events::ProviderExited,
+    state::{LiquidityPool, LiquidityPoolAccount, LiquidityProvider},
+};
+
+// Closes a liquidity provider: pays out every fee its shares earned, burns the shares and closes the
+// provider account. No principal is returned: the deposited tokens and SOL stay in the curve's reserves,
+// only the creator's remove_liquidity takes them. Fails when the vault's fee balance cannot cover what
+// the shares earned, closing would forfeit the rest
+pub fn close_liquidity_provider(ctx: Context<CloseLiquidityProvider>) -> Result<()> {
+    let pool = &mut ctx.accounts.pool;
+    let liquidity_provider_account = &ctx.accounts.liquidity_provider_account;
+    let shares = liquidity_provider_account.shares;
+
+    let earned = pool.earned_lp_fees(liquidity_provider_account)?;
+    if earned > pool.fee_sol_accrued as u128 {
+        return err!(CustomError::ExceedsAccruedFees);
+    }
+    let fees = earned as u64;
+    pool.fee_sol_accrued -= fees;
+    pool.total_shares = pool
+        .total_shares
+        .checked_sub(shares)
+        .ok_or(CustomError::FailedToDeallocateShares)?;
+    if shares > 0 {
+        pool.lp_count = pool.lp_count.saturating_sub(1);
+    }
+
+    if fees > 0 {
+        pool.transfer_sol_from_pool(
+            &mut ctx.accounts.pool_sol_vault,
+            &ctx.accounts.user,
+            fees,
+            ctx.bumps.pool_sol_vault,
+            &ctx.accounts.system_program,
+        )?;
+    }
+
+    emit!(ProviderExited {
+        pool: pool.key(),
+        provider: ctx.accounts.user.key(),
+        shares,
+        fees,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct CloseLiquidityProvider<'info> {
+    #[account(
+        mut,
+        seeds = [LiquidityPool::POOL_SEED_PREFIX.as_bytes(), token_mint.key().as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Box<Account<'info, LiquidityPool>>,
+
+    #[account(
+        mut,
+        close = user,
+        seeds = [LiquidityProvider::SEED_PREFIX.as_bytes(), pool.key().as_ref(), user.key().as_ref()],
+        bump,
+    )]
+    pub liquidity_provider_account: Box<Account<'info, LiquidityProvider>>,
+
+    pub token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// CHECK:
+    #[account(
+        mut,
+        seeds = [LiquidityPool::SOL_VAULT_PREFIX.as_bytes(), token_mint.key().as_ref()],
+        bump
+    )]
+    pub pool_sol_vault: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
@@ -0,0 +1,104 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    errors::CustomError,
+    events::AuctionCommitted,
+    state::{AuctionCommitment, CurveConfiguration, LiquidityPool, LiquidityPoolAccount, TestClock, WhitelistEntry},
+    utils::current_timestamp,
+};
+
+// Commits `amount` lamports to the pool's launch auction, filled with every other commitment at one price
+pub fn commit_auction(ctx: Context<CommitAuction>, amount: u64) -> Result<()> {
+    let config = &ctx.accounts.dex_configuration_account;
+    let pool = &mut ctx.accounts.pool;
+    let now = current_timestamp(ctx.accounts.test_clock.as_deref().map(|test_clock| &**test_clock))?;
+    if !pool.auction_pending() || now >= pool.auction_end_ts {
+        return err!(CustomError::AuctionClosed);
+    }
+    // A commitment is a buy filled later, it passes the same gates
+    config.check_trading_allowed(now)?;
+    pool.check_whitelisted(ctx.accounts.whitelist_entry.is_some(), now)?;
+    if amount == 0 {
+        return err!(CustomError::InvalidAmount);
+    }
+
+    let auction_sol = pool
+        .auction_sol
+        .checked_add(amount)
+        .ok_or(CustomError::OverflowOrUnderflowOccurred)?;
+    if auction_sol > pool.auction_capacity(config, now)? {
+        return err!(CustomError::AuctionFull);
+    }
+    pool.auction_sol = auction_sol;
+
+    let commitment = &mut ctx.accounts.commitment;
+    commitment.amount = commitment
+        .amount
+        .checked_add(amount)
+        .ok_or(CustomError::OverflowOrUnderflowOccurred)?;
+
+    pool.transfer_sol_to_pool(
+        &ctx.accounts.user,
+        &mut ctx.accounts.pool_sol_vault,
+        amount,
+        &ctx.accounts.system_program,
+    )?;
+
+    emit!(AuctionCommitted {
+        pool: pool.key(),
+        user: ctx.accounts.user.key(),
+        amount,
+        committed: commitment.amount,
+        auction_sol,
+        timestamp: now,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct CommitAuction<'info> {
+    #[account(
+        seeds = [CurveConfiguration::SEED.as_bytes()],
+        bump = dex_configuration_account.bump,
+    )]
+    pub dex_configuration_account: Box<Account<'info, CurveConfiguration>>,
+
+    #[account(
+        mut,
+        seeds = [LiquidityPool::POOL_SEED_PREFIX.as_bytes(), pool.token.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Box<Account<'info, LiquidityPool>>,
+
+    /// CHECK:
+    #[account(
+        mut,
+        seeds = [LiquidityPool::SOL_VAULT_PREFIX.as_bytes(), pool.token.as_ref()],
+        bump
+    )]
+    pub pool_sol_vault: AccountInfo<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = AuctionCommitment::ACCOUNT_SIZE,
+        seeds = [AuctionCommitment::SEED_PREFIX.as_bytes(), pool.key().as_ref(), user.key().as_ref()],
+        bump,
+    )]
+    pub commitment: Box<Account<'info, AuctionCommitment>>,
+
+    // Required to commit while the pool is in its whitelist phase
+    #[account(
+        seeds = [WhitelistEntry::SEED_PREFIX.as_bytes(), pool.key().as_ref(), user.key().as_ref()],
+        bump,
+    )]
+    pub whitelist_entry: Option<Box<Account<'info, WhitelistEntry>>>,
+
+    // Testing builds only, replaces the cluster clock for every time rule
+    #[account(seeds = [TestClock::SEED.as_bytes()], bump)]
+    pub test_clock: Option<Box<Account<'info, TestClock>>>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
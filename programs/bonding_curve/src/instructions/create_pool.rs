@@ -1,23 +1,171 @@
-use crate::state::*;
-use anchor_lang::prelude::*;
+use crate::{
+    consts::{FEE_BPS_DENOMINATOR, INITIAL_PRICE_DIVIDER, SHARE_DECIMALS},
+    errors::CustomError,
+    events::{PoolCreated, ProtocolLiquiditySeeded},
+    state::*,
+};
+use anchor_lang::{prelude::*, system_program};
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token::{Mint, Token, TokenAccount},
+    token_interface::{Mint, TokenAccount, TokenInterface},
 };
 
-pub fn create_pool(ctx: Context<CreateLiquidityPool>) -> Result<()> {
+pub fn create_pool(
+    ctx: Context<CreateLiquidityPool>,
+    launch_sell_fee_bps: u16,
+    launch_fee_window_secs: u32,
+    metadata_uri: String,
+) -> Result<()> {
+    check_new_pool(&ctx.accounts.dex_configuration_account, &ctx.accounts.token_mint, launch_sell_fee_bps, &metadata_uri)?;
+    count_creator_pool(&mut ctx.accounts.dex_configuration_account, &mut ctx.accounts.creator_pools)?;
+
     let pool = &mut ctx.accounts.pool;
 
     pool.set_inner(LiquidityPool::new(
         ctx.accounts.payer.key(),
         ctx.accounts.token_mint.key(),
         ctx.bumps.pool,
+        launch_sell_fee_bps,
+        launch_fee_window_secs,
+        ctx.accounts.token_mint.decimals,
+        metadata_uri,
     ));
+
+    emit!(PoolCreated {
+        pool: pool.key(),
+        creator: pool.creator,
+        token: pool.token,
+        bump: pool.bump,
+        launch_sell_fee_bps,
+        launch_fee_window_secs,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    let pool_key = pool.key();
+    seed_protocol_liquidity(
+        &mut ctx.accounts.dex_configuration_account,
+        pool,
+        pool_key,
+        &ctx.accounts.protocol_treasury,
+        ctx.bumps.protocol_treasury,
+        &ctx.accounts.pool_sol_vault,
+        &ctx.accounts.system_program,
+    )
+}
+
+// Moves the configured seed from the protocol treasury into a new pool's SOL reserve, within what is left
+// of the seeding budget, and credits it as shares the pool holds for the treasury. Every new pool goes
+// through it, single or batched, so the creator has no say in whether it is seeded
+pub fn seed_protocol_liquidity<'info>(
+    config: &mut CurveConfiguration,
+    pool: &mut LiquidityPool,
+    pool_key: Pubkey,
+    protocol_treasury: &AccountInfo<'info>,
+    treasury_bump: u8,
+    pool_sol_vault: &AccountInfo<'info>,
+    system_program: &Program<'info, System>,
+) -> Result<()> {
+    // The treasury keeps its rent-exempt minimum, a short treasury seeds what it has
+    let spendable = protocol_treasury.lamports().saturating_sub(Rent::get()?.minimum_balance(0));
+    let amount = config.protocol_seed_amount.min(config.protocol_seed_budget).min(spendable);
+    if amount == 0 {
+        return Ok(());
+    }
+    config.protocol_seed_budget -= amount;
+
+    system_program::transfer(
+        CpiContext::new_with_signer(
+            system_program.to_account_info(),
+            system_program::Transfer {
+                from: protocol_treasury.clone(),
+                to: pool_sol_vault.clone(),
+            },
+            &[&[CurveConfiguration::TREASURY_SEED.as_bytes(), &[treasury_bump]]],
+        ),
+        amount,
+    )?;
+
+    // The seed is valued in whole tokens at the launch price
+    let shares = u64::try_from(amount as u128 * 10_u128.pow(SHARE_DECIMALS as u32) / INITIAL_PRICE_DIVIDER as u128)
+        .map_err(|_| error!(CustomError::FailedToAllocateShares))?;
+    pool.reserve_sol = amount;
+    pool.protocol_seed_sol = amount;
+    pool.protocol_shares = shares;
+    pool.total_shares = shares;
+    pool.lp_count = 1;
+
+    emit!(ProtocolLiquiditySeeded {
+        pool: pool_key,
+        provider: protocol_treasury.key(),
+        amount,
+        shares,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+// Validation every new pool goes through, single or batched
+pub fn check_new_pool(
+    config: &CurveConfiguration,
+    token_mint: &Mint,
+    launch_sell_fee_bps: u16,
+    metadata_uri: &str,
+) -> Result<()> {
+    if launch_sell_fee_bps as u64 > FEE_BPS_DENOMINATOR {
+        return err!(CustomError::InvalidFee);
+    }
+    // The launch fee gives way to the swap fee, a step the fee change cap bounds like any other
+    let max_step = config.max_fee_change_bps as u64;
+    if max_step != 0 && launch_sell_fee_bps as u64 > config.fee_bps() + max_step {
+        return err!(CustomError::InvalidFee);
+    }
+
+    if metadata_uri.len() > LiquidityPool::MAX_METADATA_URI_LEN {
+        return err!(CustomError::MetadataUriTooLong);
+    }
+
+    // A freeze authority could lock the pool token account, and a mint without supply
+    // or a mint authority can never fund the pool
+    if token_mint.freeze_authority.is_some()
+        || (token_mint.supply == 0 && token_mint.mint_authority.is_none())
+    {
+        return err!(CustomError::InvalidMint);
+    }
+    Ok(())
+}
+
+// Counts one more pool for the creator, within the configured limit, and one more in the global count
+pub fn count_creator_pool(config: &mut CurveConfiguration, creator_pools: &mut CreatorPools) -> Result<()> {
+    let max_pools = config.max_pools_per_creator;
+    if max_pools != 0 && creator_pools.pools >= max_pools {
+        return err!(CustomError::CreatorPoolLimitReached);
+    }
+    creator_pools.pools = creator_pools
+        .pools
+        .checked_add(1)
+        .ok_or(CustomError::OverflowOrUnderflowOccurred)?;
+    config.pool_count = config.pool_count.saturating_add(1);
     Ok(())
 }
 
 #[derive(Accounts)]
 pub struct CreateLiquidityPool<'info> {
+    #[account(
+        mut,
+        seeds = [CurveConfiguration::SEED.as_bytes()],
+        bump = dex_configuration_account.bump,
+    )]
+    pub dex_configuration_account: Box<Account<'info, CurveConfiguration>>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = CreatorPools::ACCOUNT_SIZE,
+        seeds = [CreatorPools::SEED_PREFIX.as_bytes(), payer.key().as_ref()],
+        bump,
+    )]
+    pub creator_pools: Box<Account<'info, CreatorPools>>,
+
     #[account(
         init,
         space = LiquidityPool::ACCOUNT_SIZE,
@@ -28,19 +176,37 @@ pub struct CreateLiquidityPool<'info> {
     pub pool: Box<Account<'info, LiquidityPool>>,
 
     #[account(mut)]
-    pub token_mint: Box<Account<'info, Mint>>,
+    pub token_mint: Box<InterfaceAccount<'info, Mint>>,
 
     #[account(
         init,
         payer = payer,
         associated_token::mint = token_mint,
-        associated_token::authority = pool
+        associated_token::authority = pool,
+        associated_token::token_program = token_program,
+    )]
+    pub pool_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    // Seeds the pool when the configuration sets a seed, see `protocol_seed_amount`
+    /// CHECK: system account owned by the program's treasury seed, only ever debited
+    #[account(
+        mut,
+        seeds = [CurveConfiguration::TREASURY_SEED.as_bytes()],
+        bump
+    )]
+    pub protocol_treasury: AccountInfo<'info>,
+
+    /// CHECK:
+    #[account(
+        mut,
+        seeds = [LiquidityPool::SOL_VAULT_PREFIX.as_bytes(), token_mint.key().as_ref()],
+        bump
     )]
-    pub pool_token_account: Box<Account<'info, TokenAccount>>,
+    pub pool_sol_vault: AccountInfo<'info>,
 
     #[account(mut)]
     pub payer: Signer<'info>,
-    pub token_program: Program<'info, Token>,
+    pub token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub rent: Sysvar<'info, Rent>,
     pub system_program: Program<'info, System>,
@@ -0,0 +1,196 @@
+use crate::{
+    consts::MAX_POOLS_PER_BATCH,
+    errors::CustomError,
+    events::PoolCreated,
+    instructions::{check_new_pool, count_creator_pool, seed_protocol_liquidity},
+    state::*,
+};
+use anchor_lang::{prelude::*, system_program};
+use anchor_spl::{
+    associated_token::{self, AssociatedToken},
+    token_interface::{Mint, TokenInterface},
+};
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct PoolParams {
+    pub launch_sell_fee_bps: u16,
+    pub launch_fee_window_secs: u32,
+    pub metadata_uri: String,
+}
+
+// Creates up to MAX_POOLS_PER_BATCH pools in one transaction, each with the checks and the protocol seed of
+// `create_pool`. Remaining accounts hold ( token mint, pool, pool token account, pool sol vault ) for every
+// pool, in `pools` order
+pub fn create_pools_batch<'info>(
+    ctx: Context<'_, '_, 'info, 'info, CreatePoolsBatch<'info>>,
+    pools: Vec<PoolParams>,
+) -> Result<()> {
+    if pools.len() > MAX_POOLS_PER_BATCH {
+        return err!(CustomError::TooManyPools);
+    }
+    if pools.is_empty() || ctx.remaining_accounts.len() != pools.len() * 4 {
+        return err!(CustomError::InvalidAmount);
+    }
+
+    let payer = &ctx.accounts.payer;
+    let timestamp = Clock::get()?.unix_timestamp;
+
+    for (params, accounts) in pools.into_iter().zip(ctx.remaining_accounts.chunks(4)) {
+        let (mint_info, pool_info, pool_token_info, pool_sol_vault) = (&accounts[0], &accounts[1], &accounts[2], &accounts[3]);
+        let token_mint = InterfaceAccount::<Mint>::try_from(mint_info)?;
+        check_new_pool(&ctx.accounts.dex_configuration_account, &token_mint, params.launch_sell_fee_bps, &params.metadata_uri)?;
+        count_creator_pool(&mut ctx.accounts.dex_configuration_account, &mut ctx.accounts.creator_pools)?;
+
+        let (pool_key, bump) = Pubkey::find_program_address(
+            &[LiquidityPool::POOL_SEED_PREFIX.as_bytes(), mint_info.key.as_ref()],
+            &crate::ID,
+        );
+        let (pool_sol_vault_key, _) = Pubkey::find_program_address(
+            &[LiquidityPool::SOL_VAULT_PREFIX.as_bytes(), mint_info.key.as_ref()],
+            &crate::ID,
+        );
+        if pool_info.key() != pool_key || pool_sol_vault.key() != pool_sol_vault_key {
+            return err!(anchor_lang::error::ErrorCode::ConstraintSeeds);
+        }
+
+        create_pool_account(
+            payer,
+            pool_info,
+            &ctx.accounts.system_program,
+            &[LiquidityPool::POOL_SEED_PREFIX.as_bytes(), mint_info.key.as_ref(), &[bump]],
+        )?;
+
+        let mut pool = LiquidityPool::new(
+            payer.key(),
+            mint_info.key(),
+            bump,
+            params.launch_sell_fee_bps,
+            params.launch_fee_window_secs,
+            token_mint.decimals,
+            params.metadata_uri,
+        );
+        seed_protocol_liquidity(
+            &mut ctx.accounts.dex_configuration_account,
+            &mut pool,
+            pool_key,
+            &ctx.accounts.protocol_treasury,
+            ctx.bumps.protocol_treasury,
+            pool_sol_vault,
+            &ctx.accounts.system_program,
+        )?;
+        pool.try_serialize(&mut &mut pool_info.try_borrow_mut_data()?[..])?;
+
+        associated_token::create(CpiContext::new(
+            ctx.accounts.associated_token_program.to_account_info(),
+            associated_token::Create {
+                payer: payer.to_account_info(),
+                associated_token: pool_token_info.clone(),
+                authority: pool_info.clone(),
+                mint: mint_info.clone(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+            },
+        ))?;
+
+        emit!(PoolCreated {
+            pool: pool_key,
+            creator: pool.creator,
+            token: pool.token,
+            bump,
+            launch_sell_fee_bps: pool.launch_sell_fee_bps,
+            launch_fee_window_secs: pool.launch_fee_window_secs,
+            timestamp,
+        });
+    }
+    Ok(())
+}
+
+// Creates the pool account the way Anchor's `init` does. Anyone can send lamports to the pool address
+// ahead of time, which `create_account` refuses, so a funded address is topped up to rent exemption,
+// allocated and assigned instead
+fn create_pool_account<'info>(
+    payer: &Signer<'info>,
+    pool_info: &AccountInfo<'info>,
+    system_program: &Program<'info, System>,
+    seeds: &[&[u8]],
+) -> Result<()> {
+    let rent = Rent::get()?.minimum_balance(LiquidityPool::ACCOUNT_SIZE);
+    let lamports = pool_info.lamports();
+    if lamports == 0 {
+        return system_program::create_account(
+            CpiContext::new_with_signer(
+                system_program.to_account_info(),
+                system_program::CreateAccount {
+                    from: payer.to_account_info(),
+                    to: pool_info.clone(),
+                },
+                &[seeds],
+            ),
+            rent,
+            LiquidityPool::ACCOUNT_SIZE as u64,
+            &crate::ID,
+        );
+    }
+
+    if lamports < rent {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.to_account_info(),
+                system_program::Transfer {
+                    from: payer.to_account_info(),
+                    to: pool_info.clone(),
+                },
+            ),
+            rent - lamports,
+        )?;
+    }
+    system_program::allocate(
+        CpiContext::new_with_signer(
+            system_program.to_account_info(),
+            system_program::Allocate { account_to_allocate: pool_info.clone() },
+            &[seeds],
+        ),
+        LiquidityPool::ACCOUNT_SIZE as u64,
+    )?;
+    system_program::assign(
+        CpiContext::new_with_signer(
+            system_program.to_account_info(),
+            system_program::Assign { account_to_assign: pool_info.clone() },
+            &[seeds],
+        ),
+        &crate::ID,
+    )
+}
+
+#[derive(Accounts)]
+pub struct CreatePoolsBatch<'info> {
+    #[account(
+        mut,
+        seeds = [CurveConfiguration::SEED.as_bytes()],
+        bump = dex_configuration_account.bump,
+    )]
+    pub dex_configuration_account: Box<Account<'info, CurveConfiguration>>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = CreatorPools::ACCOUNT_SIZE,
+        seeds = [CreatorPools::SEED_PREFIX.as_bytes(), payer.key().as_ref()],
+        bump,
+    )]
+    pub creator_pools: Box<Account<'info, CreatorPools>>,
+
+    /// CHECK: system account owned by the program's treasury seed, only ever debited
+    #[account(
+        mut,
+        seeds = [CurveConfiguration::TREASURY_SEED.as_bytes()],
+        bump
+    )]
+    pub protocol_treasury: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
@@ -0,0 +1,105 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{self, Mint, TokenAccount, TokenInterface},
+};
+
+use crate::{
+    errors::CustomError,
+    state::{CreatorVesting, LiquidityPool, TestClock},
+    utils::current_timestamp,
+};
+
+// Locks part of the creator's allocation in a vault that releases it linearly over `duration_secs`
+pub fn create_vesting(
+    ctx: Context<CreateVesting>,
+    total: u64,
+    cliff_secs: u32,
+    duration_secs: u32,
+) -> Result<()> {
+    if total == 0 {
+        return err!(CustomError::InvalidAmount);
+    }
+    if duration_secs == 0 || cliff_secs > duration_secs {
+        return err!(CustomError::InvalidVestingSchedule);
+    }
+
+    ctx.accounts.creator_vesting.set_inner(CreatorVesting {
+        pool: ctx.accounts.pool.key(),
+        beneficiary: ctx.accounts.creator.key(),
+        total,
+        claimed: 0,
+        start_ts: current_timestamp(ctx.accounts.test_clock.as_deref().map(|test_clock| &**test_clock))?,
+        cliff_secs,
+        duration_secs,
+        bump: ctx.bumps.creator_vesting,
+    });
+
+    token_interface::transfer_checked(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            token_interface::TransferChecked {
+                from: ctx.accounts.creator_token_account.to_account_info(),
+                mint: ctx.accounts.token_mint.to_account_info(),
+                to: ctx.accounts.vesting_vault.to_account_info(),
+                authority: ctx.accounts.creator.to_account_info(),
+            },
+        ),
+        total,
+        ctx.accounts.token_mint.decimals,
+    )?;
+
+    // The vault is new, its balance is what vests once a transfer fee is taken
+    ctx.accounts.vesting_vault.reload()?;
+    ctx.accounts.creator_vesting.total = ctx.accounts.vesting_vault.amount;
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct CreateVesting<'info> {
+    #[account(
+        seeds = [LiquidityPool::POOL_SEED_PREFIX.as_bytes(), token_mint.key().as_ref()],
+        bump = pool.bump,
+        has_one = creator @ CustomError::NotCreator,
+    )]
+    pub pool: Box<Account<'info, LiquidityPool>>,
+
+    #[account(
+        init,
+        payer = creator,
+        space = CreatorVesting::ACCOUNT_SIZE,
+        seeds = [CreatorVesting::SEED_PREFIX.as_bytes(), pool.key().as_ref()],
+        bump,
+    )]
+    pub creator_vesting: Box<Account<'info, CreatorVesting>>,
+
+    pub token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        init,
+        payer = creator,
+        associated_token::mint = token_mint,
+        associated_token::authority = creator_vesting,
+        associated_token::token_program = token_program,
+    )]
+    pub vesting_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = creator,
+        associated_token::token_program = token_program,
+    )]
+    pub creator_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    // Testing builds only, replaces the cluster clock for every time rule
+    #[account(seeds = [TestClock::SEED.as_bytes()], bump)]
+    pub test_clock: Option<Box<Account<'info, TestClock>>>,
+
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    pub rent: Sysvar<'info, Rent>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
@@ -0,0 +1,74 @@
+use crate::{events::ConfigEvent, state::CurveConfiguration};
+use anchor_lang::prelude::*;
+
+// Emits every configuration field so clients can read it without knowing the account layout
+pub fn get_config(ctx: Context<GetConfig>) -> Result<()> {
+    let config = &ctx.accounts.dex_configuration_account;
+
+    emit!(ConfigEvent {
+        fees: config.fees,
+        authority: config.authority,
+        max_lps: config.max_lps,
+        withdraw_fee_bps: config.withdraw_fee_bps,
+        min_hold_secs: config.min_hold_secs,
+        graduation_price: config.graduation_price,
+        fee_schedule: config.fee_schedule.clone(),
+        max_slippage_bps: config.max_slippage_bps,
+        large_trade_threshold: config.large_trade_threshold,
+        large_trade_fee_bps: config.large_trade_fee_bps,
+        min_swap_amount: config.min_swap_amount,
+        creator_graduation_payout: config.creator_graduation_payout,
+        migration_reserve_bps: config.migration_reserve_bps,
+        trading_window_start: config.trading_window_start,
+        trading_window_end: config.trading_window_end,
+        dump_tax_max_bps: config.dump_tax_max_bps,
+        migration_destination: config.migration_destination,
+        reward_mint: config.reward_mint,
+        reward_per_swap: config.reward_per_swap,
+        max_swaps_per_slot: config.max_swaps_per_slot,
+        paused: config.paused,
+        unpause_delay_secs: config.unpause_delay_secs,
+        unpause_ready_ts: config.unpause_ready_ts,
+        creator_fee_share_bps: config.creator_fee_share_bps,
+        referral_fee_share_bps: config.referral_fee_share_bps,
+        buy_burn_bps: config.buy_burn_bps,
+        fee_recipients: config.fee_recipients.clone(),
+        max_pools_per_creator: config.max_pools_per_creator,
+        auto_compound: config.auto_compound,
+        max_fee_change_bps: config.max_fee_change_bps,
+        fee_change_period_secs: config.fee_change_period_secs,
+        last_fee_change_ts: config.last_fee_change_ts,
+        min_trade_slot_spacing: config.min_trade_slot_spacing,
+        launch_auction_secs: config.launch_auction_secs,
+        graduation_partial_fill: config.graduation_partial_fill,
+        max_price: config.max_price,
+        pool_count: config.pool_count,
+        min_reserve_floor: config.min_reserve_floor,
+        max_lp_price_impact_bps: config.max_lp_price_impact_bps,
+        dynamic_fee_min_bps: config.dynamic_fee_min_bps,
+        dynamic_fee_max_bps: config.dynamic_fee_max_bps,
+        volatility_window_secs: config.volatility_window_secs,
+        post_graduation_trade_cap: config.post_graduation_trade_cap,
+        graduation_sol_target: config.graduation_sol_target,
+        pause_reason: config.pause_reason,
+        protocol_seed_amount: config.protocol_seed_amount,
+        min_graduation_holders: config.min_graduation_holders,
+        protocol_seed_budget: config.protocol_seed_budget,
+        pending_max_fee_change_bps: config.pending_max_fee_change_bps,
+        pending_fee_change_period_secs: config.pending_fee_change_period_secs,
+        fee_cap_ready_ts: config.fee_cap_ready_ts,
+        pending_unpause_delay_secs: config.pending_unpause_delay_secs,
+        unpause_delay_ready_ts: config.unpause_delay_ready_ts,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct GetConfig<'info> {
+    #[account(
+        seeds = [CurveConfiguration::SEED.as_bytes()],
+        bump = dex_configuration_account.bump,
+    )]
+    pub dex_configuration_account: Box<Account<'info, CurveConfiguration>>,
+}
@@ -7,11 +7,15 @@ pub fn initialize(
 ) -> Result<()> {
     let dex_config = &mut ctx.accounts.dex_configuration_account;
 
-    if fees < 0_f64 || fees > 100_f64 {
+    if !(0_f64..=100_f64).contains(&fees) {
         return err!(CustomError::InvalidFee);
     }
 
-    dex_config.set_inner(CurveConfiguration::new(fees));
+    dex_config.set_inner(CurveConfiguration::new(
+        fees,
+        ctx.accounts.admin.key(),
+        ctx.bumps.dex_configuration_account,
+    ));
 
     Ok(())
 }
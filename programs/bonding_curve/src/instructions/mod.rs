@@ -1,13 +1,91 @@
 pub mod add_liquidity;
+pub mod add_to_whitelist;
+pub mod admin_swap;
+pub mod apply_unpause;
+pub mod can_migrate;
+pub mod commit_auction;
 pub mod create_pool;
+pub mod create_pools_batch;
+pub mod create_vesting;
+pub mod get_config;
 pub mod initialize;
+pub mod quote_fair_price;
+pub mod quote_graduation;
+pub mod quote_lp_apr;
+pub mod quote_market_cap;
+pub mod quote_spread;
+pub mod recover_stuck_sol;
+pub mod redeem_protocol_seed;
+pub mod refund_auction;
 pub mod remove_liquidity;
+pub mod reset_pool;
 pub mod buy;
+pub mod buyback;
+pub mod claim_airdrop;
+pub mod claim_auction;
+pub mod claim_creator_fees;
+pub mod claim_lp_fees;
+pub mod claim_vested;
+pub mod close_liquidity_provider;
 pub mod sell;
+pub mod set_airdrop_mint;
+pub mod set_graduation_target;
+pub mod set_paused;
+pub mod set_post_swap_program;
+pub mod set_test_clock;
+pub mod set_virtual_reserve;
+pub mod set_whitelist_phase;
+pub mod settle_auction;
+pub mod split_provider;
+pub mod simulate_graduation;
+pub mod simulate_swap;
+pub mod simulate_swap_path;
+pub mod swap_to_price;
+pub mod update_configuration;
+pub mod withdraw_protocol_tokens;
 
 pub use add_liquidity::*;
+pub use add_to_whitelist::*;
+pub use admin_swap::*;
+pub use apply_unpause::*;
+pub use can_migrate::*;
+pub use commit_auction::*;
 pub use create_pool::*;
+pub use create_pools_batch::*;
+pub use create_vesting::*;
+pub use get_config::*;
 pub use initialize::*;
+pub use quote_fair_price::*;
+pub use quote_graduation::*;
+pub use quote_lp_apr::*;
+pub use quote_market_cap::*;
+pub use quote_spread::*;
+pub use recover_stuck_sol::*;
+pub use redeem_protocol_seed::*;
+pub use refund_auction::*;
 pub use remove_liquidity::*;
+pub use reset_pool::*;
 pub use buy::*;
+pub use buyback::*;
+pub use claim_airdrop::*;
+pub use claim_auction::*;
+pub use claim_creator_fees::*;
+pub use claim_lp_fees::*;
+pub use claim_vested::*;
+pub use close_liquidity_provider::*;
 pub use sell::*;
+pub use set_airdrop_mint::*;
+pub use set_graduation_target::*;
+pub use set_paused::*;
+pub use set_post_swap_program::*;
+pub use set_test_clock::*;
+pub use set_virtual_reserve::*;
+pub use set_whitelist_phase::*;
+pub use settle_auction::*;
+pub use split_provider::*;
+pub use simulate_graduation::*;
+pub use simulate_swap::*;
+pub use simulate_swap_path::*;
+pub use swap_to_price::*;
+pub use update_configuration::*;
+pub use withdraw_protocol_tokens::*;
@@ -0,0 +1,20 @@
+use crate::{events::FairPriceQuote, state::LiquidityPool};
+use anchor_lang::prelude::*;
+
+// Quotes the pool's fair price, the unit aggregators compare pools in
+pub fn quote_fair_price(ctx: Context<QuoteFairPrice>) -> Result<()> {
+    let pool = &ctx.accounts.pool;
+
+    emit!(FairPriceQuote {
+        pool: pool.key(),
+        fair_price: pool.fair_price(),
+        spot_price: pool.spot_price(),
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct QuoteFairPrice<'info> {
+    pub pool: Box<Account<'info, LiquidityPool>>,
+}
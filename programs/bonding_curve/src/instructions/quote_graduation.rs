@@ -0,0 +1,33 @@
+use crate::{
+    events::GraduationQuote,
+    state::{CurveConfiguration, LiquidityPool},
+};
+use anchor_lang::prelude::*;
+
+// Quotes what is left to buy before the pool reaches the graduation price, for progress displays
+pub fn quote_graduation(ctx: Context<QuoteGraduation>) -> Result<()> {
+    let pool = &ctx.accounts.pool;
+    let (sol_remaining, token_remaining) = pool.graduation_gap(
+        &ctx.accounts.dex_configuration_account,
+        Clock::get()?.unix_timestamp,
+    )?;
+
+    emit!(GraduationQuote {
+        pool: pool.key(),
+        sol_remaining,
+        token_remaining,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct QuoteGraduation<'info> {
+    #[account(
+        seeds = [CurveConfiguration::SEED.as_bytes()],
+        bump = dex_configuration_account.bump,
+    )]
+    pub dex_configuration_account: Box<Account<'info, CurveConfiguration>>,
+
+    pub pool: Box<Account<'info, LiquidityPool>>,
+}
@@ -0,0 +1,32 @@
+use crate::{
+    events::LpAprQuote,
+    state::{LiquidityPool, TestClock},
+    utils::current_timestamp,
+};
+use anchor_lang::prelude::*;
+
+// Quotes an approximate LP APR, the swap fees the LPs earned over the current fee window
+// extrapolated to a year against the SOL reserve. A window younger than LP_APR_MIN_WINDOW_SECS is
+// priced as that long
+pub fn quote_lp_apr(ctx: Context<QuoteLpApr>) -> Result<()> {
+    let pool = &ctx.accounts.pool;
+    let now = current_timestamp(ctx.accounts.test_clock.as_deref().map(|test_clock| &**test_clock))?;
+
+    emit!(LpAprQuote {
+        pool: pool.key(),
+        fees_accrued_window: pool.fees_accrued_window,
+        window_secs: pool.lp_apr_window_secs(now),
+        reserve_sol: pool.reserve_sol,
+        apr_bps: pool.lp_apr_bps(now),
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct QuoteLpApr<'info> {
+    pub pool: Box<Account<'info, LiquidityPool>>,
+
+    #[account(seeds = [TestClock::SEED.as_bytes()], bump)]
+    pub test_clock: Option<Box<Account<'info, TestClock>>>,
+}
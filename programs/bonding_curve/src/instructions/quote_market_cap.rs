@@ -0,0 +1,29 @@
+use crate::{errors::CustomError, events::MarketCapQuote, state::LiquidityPool};
+use anchor_lang::prelude::*;
+
+// Quotes the implied market cap in lamports, the spot price applied to every token the curve sold.
+// Nothing sold yet is a market cap of zero
+pub fn quote_market_cap(ctx: Context<QuoteMarketCap>) -> Result<()> {
+    let pool = &ctx.accounts.pool;
+    let spot_price = pool.spot_price();
+    let tokens_sold = pool.total_supply.saturating_sub(pool.reserve_token);
+
+    // The spot price is per million whole tokens
+    let unit = 1_000_000_u128 * 10_u128.pow(pool.token_decimals as u32);
+    let market_cap = u64::try_from(spot_price as u128 * tokens_sold as u128 / unit)
+        .map_err(|_| error!(CustomError::OverflowOrUnderflowOccurred))?;
+
+    emit!(MarketCapQuote {
+        pool: pool.key(),
+        spot_price,
+        tokens_sold,
+        market_cap,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct QuoteMarketCap<'info> {
+    pub pool: Box<Account<'info, LiquidityPool>>,
+}
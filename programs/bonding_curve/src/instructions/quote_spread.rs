@@ -0,0 +1,60 @@
+use crate::{
+    consts::FEE_BPS_DENOMINATOR,
+    errors::CustomError,
+    events::SpreadQuote,
+    state::{CurveConfiguration, LiquidityPool},
+    utils::calculate_fee,
+};
+use anchor_lang::prelude::*;
+
+// Quotes both sides for `amount` tokens at the current reserves: the SOL a buyer pays for them
+// and the SOL a seller receives, fees included
+pub fn quote_spread(ctx: Context<QuoteSpread>, amount: u64) -> Result<()> {
+    let config = &ctx.accounts.dex_configuration_account;
+    let pool = &ctx.accounts.pool;
+    let now = Clock::get()?.unix_timestamp;
+
+    if amount == 0 {
+        return err!(CustomError::InvalidAmount);
+    }
+    if amount > pool.available_token() {
+        return err!(CustomError::NotEnoughTokenInVault);
+    }
+    if amount > pool.total_supply.saturating_sub(pool.reserve_token) {
+        return err!(CustomError::InsufficientReserves);
+    }
+
+    let buy_in = pool.buy_amount_in(amount)?;
+    let buy_fee_bps = pool.trade_fee_bps(config, now, buy_in);
+    if buy_fee_bps >= FEE_BPS_DENOMINATOR {
+        return err!(CustomError::FeeExceedsInput);
+    }
+    let buy_price = (buy_in as u128 * FEE_BPS_DENOMINATOR as u128)
+        .div_ceil((FEE_BPS_DENOMINATOR - buy_fee_bps) as u128) as u64;
+
+    let sell_out = pool.sell_amount_out(amount)?;
+    let sell_fee = calculate_fee(sell_out, pool.sell_trade_fee_bps(config, now, sell_out))?;
+    let sell_price = sell_out
+        .checked_sub(sell_fee)
+        .ok_or(CustomError::OverflowOrUnderflowOccurred)?;
+
+    emit!(SpreadQuote {
+        pool: pool.key(),
+        amount,
+        buy_price,
+        sell_price,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct QuoteSpread<'info> {
+    #[account(
+        seeds = [CurveConfiguration::SEED.as_bytes()],
+        bump = dex_configuration_account.bump,
+    )]
+    pub dex_configuration_account: Box<Account<'info, CurveConfiguration>>,
+
+    pub pool: Box<Account<'info, LiquidityPool>>,
+}
@@ -0,0 +1,74 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    errors::CustomError,
+    events::StuckSolRecovered,
+    state::{CurveConfiguration, LiquidityPool, LiquidityPoolAccount},
+};
+
+// Sends SOL transferred straight to the sol vault, everything above the balances the pool tracks, to `recipient`
+pub fn recover_stuck_sol(ctx: Context<RecoverStuckSol>) -> Result<()> {
+    let pool = &ctx.accounts.pool;
+
+    // The vault holds the curve reserve, the LP fees, the creator fees and the unsettled auction commitments,
+    // and never drops below rent exemption
+    let auction_sol = if pool.auction_settled { 0 } else { pool.auction_sol };
+    let tracked = pool
+        .reserve_sol
+        .checked_add(pool.fee_sol_accrued)
+        .and_then(|sum| sum.checked_add(pool.creator_fee_accrued))
+        .and_then(|sum| sum.checked_add(auction_sol))
+        .ok_or(CustomError::OverflowOrUnderflowOccurred)?;
+    let kept = tracked.max(Rent::get()?.minimum_balance(0));
+    let amount = ctx.accounts.pool_sol_vault.lamports().saturating_sub(kept);
+    if amount == 0 {
+        return Ok(());
+    }
+
+    pool.transfer_sol_from_pool(
+        &mut ctx.accounts.pool_sol_vault,
+        &ctx.accounts.recipient,
+        amount,
+        ctx.bumps.pool_sol_vault,
+        &ctx.accounts.system_program,
+    )?;
+
+    emit!(StuckSolRecovered {
+        pool: pool.key(),
+        recipient: ctx.accounts.recipient.key(),
+        amount,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct RecoverStuckSol<'info> {
+    #[account(
+        seeds = [CurveConfiguration::SEED.as_bytes()],
+        bump = dex_configuration_account.bump,
+        has_one = authority @ CustomError::NotAuthority,
+    )]
+    pub dex_configuration_account: Box<Account<'info, CurveConfiguration>>,
+
+    #[account(
+        seeds = [LiquidityPool::POOL_SEED_PREFIX.as_bytes(), pool.token.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Box<Account<'info, LiquidityPool>>,
+
+    /// CHECK:
+    #[account(
+        mut,
+        seeds = [LiquidityPool::SOL_VAULT_PREFIX.as_bytes(), pool.token.as_ref()],
+        bump
+    )]
+    pub pool_sol_vault: AccountInfo<'info>,
+
+    /// CHECK: only receives the recovered SOL
+    #[account(mut)]
+    pub recipient: UncheckedAccount<'info>,
+
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
@@ -0,0 +1,60 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    errors::CustomError,
+    state::{CurveConfiguration, LiquidityPool, LiquidityPoolAccount},
+};
+
+// Takes the protocol seed back from a pool that was never funded. A funded pool returns it through
+// the creator's remove_liquidity instead, while it trades the seed backs its sells
+pub fn redeem_protocol_seed(ctx: Context<RedeemProtocolSeed>) -> Result<()> {
+    let pool = &mut ctx.accounts.pool;
+    if pool.total_supply != 0 {
+        return err!(CustomError::ProtocolLiquidityLocked);
+    }
+
+    pool.return_protocol_seed(
+        &mut ctx.accounts.pool_sol_vault,
+        &ctx.accounts.protocol_treasury,
+        ctx.bumps.pool_sol_vault,
+        &ctx.accounts.system_program,
+        Clock::get()?.unix_timestamp,
+    )?;
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct RedeemProtocolSeed<'info> {
+    #[account(
+        seeds = [CurveConfiguration::SEED.as_bytes()],
+        bump = dex_configuration_account.bump,
+        has_one = authority @ CustomError::NotAuthority,
+    )]
+    pub dex_configuration_account: Box<Account<'info, CurveConfiguration>>,
+
+    #[account(
+        mut,
+        seeds = [LiquidityPool::POOL_SEED_PREFIX.as_bytes(), pool.token.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Box<Account<'info, LiquidityPool>>,
+
+    /// CHECK:
+    #[account(
+        mut,
+        seeds = [LiquidityPool::SOL_VAULT_PREFIX.as_bytes(), pool.token.as_ref()],
+        bump
+    )]
+    pub pool_sol_vault: AccountInfo<'info>,
+
+    /// CHECK: system account owned by the program's treasury seed, only credited here
+    #[account(
+        mut,
+        seeds = [CurveConfiguration::TREASURY_SEED.as_bytes()],
+        bump
+    )]
+    pub protocol_treasury: AccountInfo<'info>,
+
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
@@ -0,0 +1,69 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    errors::CustomError,
+    events::AuctionRefunded,
+    state::{AuctionCommitment, LiquidityPool, LiquidityPoolAccount},
+};
+
+// Returns a committer's lamports when the launch auction settled without filling, and closes the commitment
+pub fn refund_auction(ctx: Context<RefundAuction>) -> Result<()> {
+    let pool = &mut ctx.accounts.pool;
+    if !pool.auction_settled {
+        return err!(CustomError::AuctionNotSettled);
+    }
+    if pool.auction_tokens != 0 {
+        return err!(CustomError::AuctionFilled);
+    }
+
+    let amount = ctx.accounts.commitment.amount;
+    pool.auction_sol_claimed = pool
+        .auction_sol_claimed
+        .checked_add(amount)
+        .ok_or(CustomError::OverflowOrUnderflowOccurred)?;
+    pool.transfer_sol_from_pool(
+        &mut ctx.accounts.pool_sol_vault,
+        &ctx.accounts.user,
+        amount,
+        ctx.bumps.pool_sol_vault,
+        &ctx.accounts.system_program,
+    )?;
+
+    emit!(AuctionRefunded {
+        pool: pool.key(),
+        user: ctx.accounts.user.key(),
+        amount,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct RefundAuction<'info> {
+    #[account(
+        mut,
+        seeds = [LiquidityPool::POOL_SEED_PREFIX.as_bytes(), pool.token.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Box<Account<'info, LiquidityPool>>,
+
+    /// CHECK:
+    #[account(
+        mut,
+        seeds = [LiquidityPool::SOL_VAULT_PREFIX.as_bytes(), pool.token.as_ref()],
+        bump
+    )]
+    pub pool_sol_vault: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        close = user,
+        seeds = [AuctionCommitment::SEED_PREFIX.as_bytes(), pool.key().as_ref(), user.key().as_ref()],
+        bump,
+    )]
+    pub commitment: Box<Account<'info, AuctionCommitment>>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
@@ -1,20 +1,67 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
-    associated_token::AssociatedToken,
-    token::{Mint, Token, TokenAccount},
+    associated_token::{get_associated_token_address_with_program_id, AssociatedToken},
+    token_interface::{Mint, TokenAccount, TokenInterface},
 };
-use crate::{consts::TOKEN_SELL_LIMIT_PERCENT, errors::CustomError, state::{LiquidityPool, LiquidityPoolAccount}};
+use crate::{consts::FEE_BPS_DENOMINATOR, errors::CustomError, events::LiquidityRemoved, state::{CurveConfiguration, LiquidityPool, LiquidityPoolAccount, LiquidityProvider}};
 
 pub fn remove_liquidity(ctx: Context<RemoveLiquidity>, bump: u8) -> Result<()> {
     let pool = &mut ctx.accounts.pool;
     if pool.creator.key() != ctx.accounts.user.key() {
-        return Err(CustomError::NotCreator.into());
+        return err!(CustomError::NotCreator);
+    }
+    if pool.completed {
+        return err!(CustomError::CurveComplete);
+    }
+    // Committed SOL and auction tokens sit in the vaults until every committer took them out
+    if pool.auction_pending() {
+        return err!(CustomError::AuctionActive);
+    }
+    if pool.auction_unclaimed() {
+        return err!(CustomError::AuctionUnclaimed);
+    }
+    // Removing liquidity empties both reserves, which no floor allows
+    if ctx.accounts.dex_configuration_account.min_reserve_floor.is_set() {
+        return err!(CustomError::ReserveFloorBreached);
     }
 
-    // if pool.total_supply.checked_div(10000).ok_or(CustomError::OverflowOrUnderflowOccurred)?
-    // .checked_mul(TOKEN_SELL_LIMIT_PERCENT) > Some(pool.reserve_token) {
-    //     return Err(CustomError::NotEnoughToRemove.into());
-    // }
+    let reserve_token_before = pool.reserve_token;
+    let reserve_sol_before = pool.reserve_sol;
+    let total_supply_before = pool.total_supply;
+    let spot_price_before = pool.spot_price();
+
+    // The protocol's seed and its shares go back to the treasury, the creator takes the rest
+    if pool.protocol_seed_sol > 0 || pool.protocol_shares > 0 {
+        pool.return_protocol_seed(
+            &mut ctx.accounts.pool_sol_vault,
+            &ctx.accounts.protocol_treasury,
+            bump,
+            &ctx.accounts.system_program,
+            Clock::get()?.unix_timestamp,
+        )?;
+    }
+
+    // The creator's shares stand for the reserves it takes, they are burned and their pending fees leave
+    // the vault with the reserves. Shares held by anyone else would be left earning the fees of whoever
+    // funds the pool next
+    let provider = &mut ctx.accounts.liquidity_provider_account;
+    let shares = provider.shares;
+    if pool.total_shares != shares {
+        return err!(CustomError::LiquidityProvidersRemaining);
+    }
+    let fees = pool.pending_lp_fees(provider)?;
+    provider.accrue_shares_seconds(Clock::get()?.unix_timestamp)?;
+    provider.shares = 0;
+    provider.fee_debt = 0;
+    pool.total_shares = 0;
+    pool.acc_fee_per_share = 0;
+    if shares > 0 {
+        pool.lp_count = pool.lp_count.saturating_sub(1);
+    }
+    pool.fee_sol_accrued = pool
+        .fee_sol_accrued
+        .checked_sub(fees)
+        .ok_or(CustomError::OverflowOrUnderflowOccurred)?;
 
     let token_accounts = (
         &mut *ctx.accounts.token_mint,
@@ -23,6 +70,7 @@ pub fn remove_liquidity(ctx: Context<RemoveLiquidity>, bump: u8) -> Result<()> {
     );
 
     pool.remove_liquidity(
+        &ctx.accounts.dex_configuration_account,
         token_accounts,
         &mut ctx.accounts.pool_sol_vault,
         &ctx.accounts.user,
@@ -30,11 +78,39 @@ pub fn remove_liquidity(ctx: Context<RemoveLiquidity>, bump: u8) -> Result<()> {
         &ctx.accounts.token_program,
         &ctx.accounts.system_program,
     )?;
+
+    // Protects whoever still holds tokens from the price dropping under them
+    let max_impact_bps = ctx.accounts.dex_configuration_account.max_lp_price_impact_bps as u128;
+    if max_impact_bps != 0 && spot_price_before != 0 {
+        let impact_bps = spot_price_before.abs_diff(pool.spot_price()) as u128 * FEE_BPS_DENOMINATOR as u128
+            / spot_price_before as u128;
+        if impact_bps > max_impact_bps {
+            return err!(CustomError::LpPriceImpactTooHigh);
+        }
+    }
+
+    emit!(LiquidityRemoved {
+        pool: pool.key(),
+        provider: ctx.accounts.user.key(),
+        reserve_token_before,
+        reserve_sol_before,
+        reserve_token_after: pool.reserve_token,
+        reserve_sol_after: pool.reserve_sol,
+        total_supply_before,
+        total_supply_after: pool.total_supply,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
     Ok(())
 }
 
 #[derive(Accounts)]
 pub struct RemoveLiquidity<'info> {
+    #[account(
+        seeds = [CurveConfiguration::SEED.as_bytes()],
+        bump = dex_configuration_account.bump,
+    )]
+    pub dex_configuration_account: Box<Account<'info, CurveConfiguration>>,
+
     #[account(
         mut,
        seeds = [LiquidityPool::POOL_SEED_PREFIX.as_bytes(), token_mint.key().as_ref()],
@@ -42,22 +118,32 @@ pub struct RemoveLiquidity<'info> {
     )]
     pub pool: Box<Account<'info, LiquidityPool>>,
 
+    #[account(
+        mut,
+        seeds = [LiquidityProvider::SEED_PREFIX.as_bytes(), pool.key().as_ref(), user.key().as_ref()],
+        bump,
+    )]
+    pub liquidity_provider_account: Box<Account<'info, LiquidityProvider>>,
+
     #[account(mut)]
-    pub token_mint: Box<Account<'info, Mint>>,
+    pub token_mint: Box<InterfaceAccount<'info, Mint>>,
 
     #[account(
         mut,
         associated_token::mint = token_mint,
-        associated_token::authority = pool
+        associated_token::authority = pool,
+        associated_token::token_program = token_program,
     )]
-    pub pool_token_account: Box<Account<'info, TokenAccount>>,
+    pub pool_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
 
     #[account(
         mut,
-        associated_token::mint = token_mint,
-        associated_token::authority = user,
+        address = get_associated_token_address_with_program_id(&user.key(), &token_mint.key(), &token_program.key())
+            @ anchor_lang::error::ErrorCode::AccountNotAssociatedTokenAccount,
+        token::mint = token_mint,
+        token::authority = user,
     )]
-    pub user_token_account: Box<Account<'info, TokenAccount>>,
+    pub user_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
 
     /// CHECK:
     #[account(
@@ -67,10 +153,18 @@ pub struct RemoveLiquidity<'info> {
     )]
     pub pool_sol_vault: AccountInfo<'info>,
 
+    /// CHECK: system account owned by the program's treasury seed, receives the protocol seed back
+    #[account(
+        mut,
+        seeds = [CurveConfiguration::TREASURY_SEED.as_bytes()],
+        bump
+    )]
+    pub protocol_treasury: AccountInfo<'info>,
+
     #[account(mut)]
     pub user: Signer<'info>,
     pub rent: Sysvar<'info, Rent>,
     pub system_program: Program<'info, System>,
-    pub token_program: Program<'info, Token>,
+    pub token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
 }
@@ -0,0 +1,42 @@
+use crate::{
+    errors::CustomError,
+    state::{CurveConfiguration, LiquidityPool},
+};
+use anchor_lang::prelude::*;
+
+// Zeroes a pool's reserves, supply and flags so flows can be retested on devnet.
+// Only compiled with the `testing` feature, the vault balances are left as they are.
+#[cfg(feature = "testing")]
+pub fn reset_pool(ctx: Context<ResetPool>) -> Result<()> {
+    let pool = &mut ctx.accounts.pool;
+
+    pool.total_supply = 0;
+    pool.reserve_token = 0;
+    pool.reserve_sol = 0;
+    pool.reserved_for_migration = 0;
+    pool.sol_raised = 0;
+    pool.completed = false;
+    pool.graduated_ts = 0;
+    pool.locked = false;
+    Ok(())
+}
+
+#[cfg(not(feature = "testing"))]
+pub fn reset_pool(_ctx: Context<ResetPool>) -> Result<()> {
+    err!(CustomError::TestingOnly)
+}
+
+#[derive(Accounts)]
+pub struct ResetPool<'info> {
+    #[account(
+        seeds = [CurveConfiguration::SEED.as_bytes()],
+        bump = dex_configuration_account.bump,
+        has_one = authority @ CustomError::NotAuthority,
+    )]
+    pub dex_configuration_account: Box<Account<'info, CurveConfiguration>>,
+
+    #[account(mut)]
+    pub pool: Box<Account<'info, LiquidityPool>>,
+
+    pub authority: Signer<'info>,
+}
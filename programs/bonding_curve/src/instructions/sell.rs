@@ -1,29 +1,113 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
-    associated_token::AssociatedToken,
-    token::{Mint, Token, TokenAccount},
+    associated_token::{get_associated_token_address_with_program_id, AssociatedToken},
+    token_interface::{Mint, TokenAccount, TokenInterface},
 };
 
-use crate::state::{CurveConfiguration, LiquidityPool, LiquidityPoolAccount};
+use crate::{
+    consts::SWAP_DIRECTION_SELL,
+    errors::CustomError,
+    events::SwapEvent,
+    state::{CurveConfiguration, LiquidityPool, LiquidityPoolAccount, TestClock, UserState},
+    utils::{current_timestamp, invoke_post_swap_hook, pay_swap_reward, PostSwapHookArgs},
+};
+
+pub fn sell<'info>(
+    ctx: Context<'_, '_, '_, 'info, Sell<'info>>,
+    amount: u64,
+    bump: u8,
+) -> Result<()> {
+    let clock = Clock::get()?;
+    let timestamp = current_timestamp(ctx.accounts.test_clock.as_deref().map(|test_clock| &**test_clock))?;
+    ctx.accounts.user_state.check_sell_allowed(
+        ctx.accounts.dex_configuration_account.min_hold_secs,
+        clock.slot,
+        timestamp,
+    )?;
+
+    // Remaining accounts hold the configured fee recipients first, then the post-swap hook accounts
+    let recipient_count = ctx.accounts.dex_configuration_account.fee_recipients.len();
+    let (fee_recipients, hook_accounts) =
+        ctx.remaining_accounts.split_at(recipient_count.min(ctx.remaining_accounts.len()));
 
-pub fn sell(ctx: Context<Sell>, amount: u64, bump: u8) -> Result<()> {
     let pool = &mut ctx.accounts.pool;
+    let reserve_token_before = pool.reserve_token;
+    let reserve_sol_before = pool.reserve_sol;
 
+    let curve_phase = !pool.completed;
     let token_one_accounts = (
         &mut *ctx.accounts.token_mint,
         &mut *ctx.accounts.pool_token_account,
         &mut *ctx.accounts.user_token_account,
     );
 
-    pool.sell(
+    let (amount_out, fee) = pool.sell(
+        &ctx.accounts.dex_configuration_account,
         token_one_accounts,
         &mut ctx.accounts.pool_sol_vault,
         amount,
         bump,
+        timestamp,
         &ctx.accounts.user,
+        ctx.accounts.referrer.as_deref(),
+        fee_recipients,
         &ctx.accounts.token_program,
         &ctx.accounts.system_program,
     )?;
+    // Selling back what the curve paid drops the seller from the holder count
+    pool.debit_holder(&mut ctx.accounts.user_state, amount);
+
+    // What the sell took out of the curve comes off the seller's buy volume, so buying and selling in a
+    // loop earns no airdrop share
+    if curve_phase {
+        let user_state = &mut ctx.accounts.user_state;
+        let netted = user_state.buy_volume.min(amount_out + fee);
+        user_state.buy_volume -= netted;
+        pool.total_buy_volume = pool.total_buy_volume.saturating_sub(netted);
+    }
+
+    let amount_in = pool.reserve_token - reserve_token_before;
+    emit!(SwapEvent {
+        pool: pool.key(),
+        user: ctx.accounts.user.key(),
+        recipient: ctx.accounts.user.key(),
+        direction: SWAP_DIRECTION_SELL,
+        amount_in,
+        amount_out,
+        fee,
+        reserve_token_before,
+        reserve_sol_before,
+        reserve_token_after: pool.reserve_token,
+        reserve_sol_after: pool.reserve_sol,
+        total_supply: pool.total_supply,
+        timestamp,
+    });
+
+    pay_swap_reward(
+        &ctx.accounts.dex_configuration_account,
+        ctx.accounts.reward_mint.as_deref(),
+        ctx.accounts.reward_vault.as_deref(),
+        ctx.accounts.user_reward_account.as_deref(),
+        &ctx.accounts.token_program,
+    )?;
+
+    if let Some(hook_program) = ctx.accounts.pool.post_swap_program {
+        // Persist the pool with the lock set so a hook calling back into the curve is refused
+        ctx.accounts.pool.locked = true;
+        ctx.accounts.pool.exit(&crate::ID)?;
+        invoke_post_swap_hook(
+            hook_program,
+            hook_accounts,
+            PostSwapHookArgs {
+                pool: ctx.accounts.pool.key(),
+                user: ctx.accounts.user.key(),
+                direction: SWAP_DIRECTION_SELL,
+                amount_in,
+                amount_out,
+            },
+        )?;
+        ctx.accounts.pool.locked = false;
+    }
     Ok(())
 }
 
@@ -32,7 +116,7 @@ pub struct Sell<'info> {
     #[account(
         mut,
         seeds = [CurveConfiguration::SEED.as_bytes()],
-        bump,
+        bump = dex_configuration_account.bump,
     )]
     pub dex_configuration_account: Box<Account<'info, CurveConfiguration>>,
 
@@ -44,14 +128,15 @@ pub struct Sell<'info> {
     pub pool: Box<Account<'info, LiquidityPool>>,
 
     #[account(mut)]
-    pub token_mint: Box<Account<'info, Mint>>,
+    pub token_mint: Box<InterfaceAccount<'info, Mint>>,
 
     #[account(
         mut,
         associated_token::mint = token_mint,
-        associated_token::authority = pool
+        associated_token::authority = pool,
+        associated_token::token_program = token_program,
     )]
-    pub pool_token_account: Box<Account<'info, TokenAccount>>,
+    pub pool_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
 
     /// CHECK:
     #[account(
@@ -61,17 +146,54 @@ pub struct Sell<'info> {
     )]
     pub pool_sol_vault: AccountInfo<'info>,
 
+    // Only the user's canonical associated token account, the same error buy's init_if_needed raises
     #[account(
         mut,
-        associated_token::mint = token_mint,
-        associated_token::authority = user,
+        address = get_associated_token_address_with_program_id(&user.key(), &token_mint.key(), &token_program.key())
+            @ anchor_lang::error::ErrorCode::AccountNotAssociatedTokenAccount,
+        token::mint = token_mint,
+        token::authority = user,
     )]
-    pub user_token_account: Box<Account<'info, TokenAccount>>,
+    pub user_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = UserState::ACCOUNT_SIZE,
+        seeds = [UserState::SEED_PREFIX.as_bytes(), pool.key().as_ref(), user.key().as_ref()],
+        bump,
+    )]
+    pub user_state: Box<Account<'info, UserState>>,
+
+    #[account(address = dex_configuration_account.reward_mint)]
+    pub reward_mint: Option<Box<InterfaceAccount<'info, Mint>>>,
+
+    #[account(
+        mut,
+        token::mint = dex_configuration_account.reward_mint,
+        token::authority = dex_configuration_account,
+    )]
+    pub reward_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        mut,
+        token::mint = dex_configuration_account.reward_mint,
+        token::authority = user,
+    )]
+    pub user_reward_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// CHECK: only receives the referral share of the swap fee
+    #[account(mut, constraint = referrer.key() != user.key() @ CustomError::InvalidReferrer)]
+    pub referrer: Option<UncheckedAccount<'info>>,
+
+    // Testing builds only, replaces the cluster clock for every time rule
+    #[account(seeds = [TestClock::SEED.as_bytes()], bump)]
+    pub test_clock: Option<Box<Account<'info, TestClock>>>,
 
     #[account(mut)]
     pub user: Signer<'info>,
     pub rent: Sysvar<'info, Rent>,
     pub system_program: Program<'info, System>,
-    pub token_program: Program<'info, Token>,
+    pub token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
 }
@@ -0,0 +1,42 @@
+use crate::{
+    errors::CustomError,
+    state::{CurveConfiguration, LiquidityPool},
+};
+use anchor_lang::prelude::*;
+
+// Points a graduated pool at the token its buyers are airdropped, typically the LP token of the
+// pool it migrated to. The airdrop is funded by sending that token to the pool's associated account.
+// It is set once, a new mint would strand whatever the buyers were promised in the first one
+pub fn set_airdrop_mint(ctx: Context<SetAirdropMint>) -> Result<()> {
+    let pool = &mut ctx.accounts.pool;
+    if !pool.completed {
+        return err!(CustomError::AirdropNotAvailable);
+    }
+    if pool.airdrop_mint != Pubkey::default() {
+        return err!(CustomError::AirdropMintSet);
+    }
+    pool.airdrop_mint = ctx.accounts.airdrop_mint.key();
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetAirdropMint<'info> {
+    #[account(
+        seeds = [CurveConfiguration::SEED.as_bytes()],
+        bump = dex_configuration_account.bump,
+        has_one = authority @ CustomError::NotAuthority,
+    )]
+    pub dex_configuration_account: Box<Account<'info, CurveConfiguration>>,
+
+    #[account(
+        mut,
+        seeds = [LiquidityPool::POOL_SEED_PREFIX.as_bytes(), pool.token.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Box<Account<'info, LiquidityPool>>,
+
+    /// CHECK: only its address is stored, claims check it against the mint they are given
+    pub airdrop_mint: UncheckedAccount<'info>,
+
+    pub authority: Signer<'info>,
+}
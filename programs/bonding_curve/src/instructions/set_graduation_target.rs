@@ -0,0 +1,34 @@
+use crate::{
+    errors::CustomError,
+    state::{CurveConfiguration, LiquidityPool},
+};
+use anchor_lang::prelude::*;
+
+// Sets ( or clears with 0 ) a pool's own graduation price, only before anything was bought from it
+pub fn set_graduation_target(ctx: Context<SetGraduationTarget>, graduation_price: u64) -> Result<()> {
+    let pool = &mut ctx.accounts.pool;
+    if pool.has_traded() {
+        return err!(CustomError::CurveAlreadyActive);
+    }
+    pool.graduation_target = graduation_price;
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetGraduationTarget<'info> {
+    #[account(
+        seeds = [CurveConfiguration::SEED.as_bytes()],
+        bump = dex_configuration_account.bump,
+        has_one = authority @ CustomError::NotAuthority,
+    )]
+    pub dex_configuration_account: Box<Account<'info, CurveConfiguration>>,
+
+    #[account(
+        mut,
+        seeds = [LiquidityPool::POOL_SEED_PREFIX.as_bytes(), pool.token.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Box<Account<'info, LiquidityPool>>,
+
+    pub authority: Signer<'info>,
+}
@@ -0,0 +1,53 @@
+use crate::{
+    consts::{PAUSE_REASON_NONE, PAUSE_REASON_OTHER},
+    errors::CustomError,
+    events::PauseUpdated,
+    state::{CurveConfiguration, TestClock},
+    utils::current_timestamp,
+};
+use anchor_lang::prelude::*;
+
+// Pausing is immediate and records one of the PAUSE_REASON_* codes. Unpausing waits out
+// `unpause_delay_secs` and is finished by apply_unpause, `reason` is ignored then.
+pub fn set_paused(ctx: Context<SetPaused>, paused: bool, reason: u8) -> Result<()> {
+    let now = current_timestamp(ctx.accounts.test_clock.as_deref().map(|test_clock| &**test_clock))?;
+    let dex_config = &mut ctx.accounts.dex_configuration_account;
+    dex_config.apply_pending_unpause_delay(now);
+
+    if paused && reason > PAUSE_REASON_OTHER {
+        return err!(CustomError::InvalidPauseReason);
+    }
+
+    if paused || dex_config.unpause_delay_secs == 0 {
+        dex_config.paused = paused;
+        dex_config.pause_reason = if paused { reason } else { PAUSE_REASON_NONE };
+        dex_config.unpause_ready_ts = 0;
+    } else if dex_config.paused {
+        dex_config.unpause_ready_ts = now + dex_config.unpause_delay_secs as i64;
+    }
+
+    emit!(PauseUpdated {
+        paused: dex_config.paused,
+        pause_reason: dex_config.pause_reason,
+        unpause_ready_ts: dex_config.unpause_ready_ts,
+        timestamp: now,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetPaused<'info> {
+    #[account(
+        mut,
+        seeds = [CurveConfiguration::SEED.as_bytes()],
+        bump = dex_configuration_account.bump,
+        has_one = authority @ CustomError::NotAuthority,
+    )]
+    pub dex_configuration_account: Box<Account<'info, CurveConfiguration>>,
+
+    // Testing builds only, replaces the cluster clock for every time rule
+    #[account(seeds = [TestClock::SEED.as_bytes()], bump)]
+    pub test_clock: Option<Box<Account<'info, TestClock>>>,
+
+    pub authority: Signer<'info>,
+}
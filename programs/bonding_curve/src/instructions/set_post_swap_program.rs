@@ -0,0 +1,25 @@
+use crate::{errors::CustomError, state::LiquidityPool};
+use anchor_lang::prelude::*;
+
+// Lets the creator register ( or clear ) a program called after every swap on the pool. Traders see
+// the hook before they buy, it cannot change once liquidity opened trading
+pub fn set_post_swap_program(ctx: Context<SetPostSwapProgram>, program: Option<Pubkey>) -> Result<()> {
+    if ctx.accounts.pool.trade_start_ts != 0 {
+        return err!(CustomError::PostSwapProgramLocked);
+    }
+    ctx.accounts.pool.post_swap_program = program;
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetPostSwapProgram<'info> {
+    #[account(
+        mut,
+        seeds = [LiquidityPool::POOL_SEED_PREFIX.as_bytes(), pool.token.as_ref()],
+        bump = pool.bump,
+        has_one = creator @ CustomError::NotCreator,
+    )]
+    pub pool: Box<Account<'info, LiquidityPool>>,
+
+    pub creator: Signer<'info>,
+}
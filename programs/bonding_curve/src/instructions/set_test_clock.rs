@@ -0,0 +1,41 @@
+use crate::{
+    errors::CustomError,
+    state::{CurveConfiguration, TestClock},
+};
+use anchor_lang::prelude::*;
+
+// Sets the timestamp instructions read when given the test clock, so cooldowns and windows can be tested
+// without warping the validator clock. Only compiled with the `testing` feature
+#[cfg(feature = "testing")]
+pub fn set_test_clock(ctx: Context<SetTestClock>, unix_timestamp: i64) -> Result<()> {
+    ctx.accounts.test_clock.unix_timestamp = unix_timestamp;
+    Ok(())
+}
+
+#[cfg(not(feature = "testing"))]
+pub fn set_test_clock(_ctx: Context<SetTestClock>, _unix_timestamp: i64) -> Result<()> {
+    err!(CustomError::TestingOnly)
+}
+
+#[derive(Accounts)]
+pub struct SetTestClock<'info> {
+    #[account(
+        seeds = [CurveConfiguration::SEED.as_bytes()],
+        bump = dex_configuration_account.bump,
+        has_one = authority @ CustomError::NotAuthority,
+    )]
+    pub dex_configuration_account: Box<Account<'info, CurveConfiguration>>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = TestClock::ACCOUNT_SIZE,
+        seeds = [TestClock::SEED.as_bytes()],
+        bump,
+    )]
+    pub test_clock: Box<Account<'info, TestClock>>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
@@ -0,0 +1,41 @@
+use crate::{
+    errors::CustomError,
+    events::CurveParamsUpdated,
+    state::{CurveConfiguration, LiquidityPool},
+};
+use anchor_lang::prelude::*;
+
+// Moves the curve starting point, only while the pool holds no real reserves yet
+pub fn set_virtual_reserve(ctx: Context<SetVirtualReserve>, virtual_sol_reserve: u64) -> Result<()> {
+    let pool = &mut ctx.accounts.pool;
+
+    if pool.reserve_token != 0 || pool.reserve_sol != 0 {
+        return err!(CustomError::ReservesNotEmpty);
+    }
+
+    let virtual_sol_reserve_before = pool.virtual_sol_reserve;
+    pool.virtual_sol_reserve = virtual_sol_reserve;
+
+    emit!(CurveParamsUpdated {
+        pool: pool.key(),
+        virtual_sol_reserve_before,
+        virtual_sol_reserve,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetVirtualReserve<'info> {
+    #[account(
+        seeds = [CurveConfiguration::SEED.as_bytes()],
+        bump = dex_configuration_account.bump,
+        has_one = authority @ CustomError::NotAuthority,
+    )]
+    pub dex_configuration_account: Box<Account<'info, CurveConfiguration>>,
+
+    #[account(mut)]
+    pub pool: Box<Account<'info, LiquidityPool>>,
+
+    pub authority: Signer<'info>,
+}
@@ -0,0 +1,34 @@
+use crate::{
+    errors::CustomError,
+    state::{CurveConfiguration, LiquidityPool},
+};
+use anchor_lang::prelude::*;
+
+// Sets the end of a pool's whitelist phase ( 0 opens the pool to everyone ), only before anything was bought from it
+pub fn set_whitelist_phase(ctx: Context<SetWhitelistPhase>, whitelist_phase_end_ts: i64) -> Result<()> {
+    let pool = &mut ctx.accounts.pool;
+    if pool.has_traded() {
+        return err!(CustomError::CurveAlreadyActive);
+    }
+    pool.whitelist_phase_end_ts = whitelist_phase_end_ts;
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetWhitelistPhase<'info> {
+    #[account(
+        seeds = [CurveConfiguration::SEED.as_bytes()],
+        bump = dex_configuration_account.bump,
+        has_one = authority @ CustomError::NotAuthority,
+    )]
+    pub dex_configuration_account: Box<Account<'info, CurveConfiguration>>,
+
+    #[account(
+        mut,
+        seeds = [LiquidityPool::POOL_SEED_PREFIX.as_bytes(), pool.token.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Box<Account<'info, LiquidityPool>>,
+
+    pub authority: Signer<'info>,
+}
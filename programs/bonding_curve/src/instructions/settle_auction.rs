@@ -0,0 +1,120 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    errors::CustomError,
+    events::AuctionSettled,
+    state::{CurveConfiguration, LiquidityPool, LiquidityPoolAccount, TestClock},
+    utils::current_timestamp,
+};
+
+// Fills every launch auction commitment as one curve buy once commitments closed, so all committers pay
+// the same clearing price. Anyone can settle, remaining accounts hold the configured fee recipients.
+// An auction the curve cannot fill settles with no tokens and its committers take a refund instead
+pub fn settle_auction<'info>(ctx: Context<'_, '_, '_, 'info, SettleAuction<'info>>) -> Result<()> {
+    let config = &ctx.accounts.dex_configuration_account;
+    let pool = &mut ctx.accounts.pool;
+    let now = current_timestamp(ctx.accounts.test_clock.as_deref().map(|test_clock| &**test_clock))?;
+    if !pool.auction_pending() {
+        return err!(CustomError::AuctionClosed);
+    }
+    if now < pool.auction_end_ts {
+        return err!(CustomError::AuctionNotEnded);
+    }
+    config.check_trading_allowed(now)?;
+    pool.auction_settled = true;
+
+    let auction_sol = pool.auction_sol;
+    let (mut fee, mut tokens, mut clearing_price) = (0, 0, 0);
+    let fill = match auction_sol {
+        0 => None,
+        _ => pool.fill_buy(config, auction_sol, now, pool.available_token()).ok(),
+    };
+    if let Some((amount_in, fill_fee, fee_dust, amount_out)) = fill {
+        (fee, tokens) = (fill_fee, amount_out);
+        clearing_price = (amount_in as u128 * 1_000_000 * 10_u128.pow(pool.token_decimals as u32) / tokens as u128)
+            as u64;
+
+        pool.reserve_sol = pool
+            .reserve_sol
+            .checked_add(amount_in)
+            .ok_or(CustomError::OverflowOrUnderflowOccurred)?;
+        pool.reserve_token -= tokens;
+        pool.sol_raised = pool
+            .sol_raised
+            .checked_add(amount_in)
+            .ok_or(CustomError::OverflowOrUnderflowOccurred)?;
+        pool.total_buy_volume = pool
+            .total_buy_volume
+            .checked_add(auction_sol)
+            .ok_or(CustomError::OverflowOrUnderflowOccurred)?;
+        pool.auction_tokens = tokens;
+
+        let (_, routed) = pool.accrue_swap_fee(config, fee, false, now)?;
+        pool.fee_dust = fee_dust;
+        // Commitments are capped at the cost of the whole curve, what a fee change since left over goes to the LPs
+        pool.accrue_sol_fee(auction_sol - amount_in - fee)?;
+        pool.pay_fee_recipients(
+            config,
+            ctx.remaining_accounts,
+            &routed,
+            &mut ctx.accounts.pool_sol_vault,
+            ctx.bumps.pool_sol_vault,
+            &ctx.accounts.system_program,
+        )?;
+
+        if pool.check_graduation(config) {
+            pool.complete_graduation(
+                config,
+                &mut ctx.accounts.pool_sol_vault,
+                &ctx.accounts.creator,
+                ctx.bumps.pool_sol_vault,
+                &ctx.accounts.system_program,
+                now,
+            )?;
+        }
+    }
+
+    emit!(AuctionSettled {
+        pool: pool.key(),
+        auction_sol,
+        fee,
+        tokens,
+        clearing_price,
+        timestamp: now,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SettleAuction<'info> {
+    #[account(
+        seeds = [CurveConfiguration::SEED.as_bytes()],
+        bump = dex_configuration_account.bump,
+    )]
+    pub dex_configuration_account: Box<Account<'info, CurveConfiguration>>,
+
+    #[account(
+        mut,
+        seeds = [LiquidityPool::POOL_SEED_PREFIX.as_bytes(), pool.token.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Box<Account<'info, LiquidityPool>>,
+
+    /// CHECK:
+    #[account(
+        mut,
+        seeds = [LiquidityPool::SOL_VAULT_PREFIX.as_bytes(), pool.token.as_ref()],
+        bump
+    )]
+    pub pool_sol_vault: AccountInfo<'info>,
+
+    /// CHECK: receives the graduation payout, checked against the pool creator
+    #[account(mut, address = pool.creator)]
+    pub creator: AccountInfo<'info>,
+
+    // Testing builds only, replaces the cluster clock for every time rule
+    #[account(seeds = [TestClock::SEED.as_bytes()], bump)]
+    pub test_clock: Option<Box<Account<'info, TestClock>>>,
+
+    pub system_program: Program<'info, System>,
+}
@@ -0,0 +1,38 @@
+use crate::{
+    events::GraduationSimulation,
+    state::{CurveConfiguration, LiquidityPool},
+};
+use anchor_lang::prelude::*;
+
+// Books a graduation at the pool's current state on a copy of it, the same bookkeeping the buy, auction
+// settlement or buyback completing the curve goes through: the creator payout leaves the SOL reserve and
+// both reserves stay in the pool. A completed pool has nothing left to pay out
+pub fn simulate_graduation(ctx: Context<SimulateGraduation>) -> Result<()> {
+    let mut pool = LiquidityPool::clone(&ctx.accounts.pool);
+    let creator_payout = if pool.completed {
+        0
+    } else {
+        pool.book_graduation(&ctx.accounts.dex_configuration_account)?
+    };
+
+    emit!(GraduationSimulation {
+        pool: ctx.accounts.pool.key(),
+        completed: pool.completed,
+        creator_payout,
+        reserve_sol: pool.reserve_sol,
+        reserve_token: pool.reserve_token,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SimulateGraduation<'info> {
+    #[account(
+        seeds = [CurveConfiguration::SEED.as_bytes()],
+        bump = dex_configuration_account.bump,
+    )]
+    pub dex_configuration_account: Box<Account<'info, CurveConfiguration>>,
+
+    pub pool: Box<Account<'info, LiquidityPool>>,
+}
@@ -0,0 +1,111 @@
+use crate::{
+    consts::{SWAP_DIRECTION_BUY, SWAP_DIRECTION_SELL},
+    errors::CustomError,
+    events::SwapSimulation,
+    state::{CurveConfiguration, LiquidityPool, TestClock, UserState, WhitelistEntry},
+    utils::current_timestamp,
+};
+use anchor_lang::prelude::*;
+
+// Dry run of a swap through the same guards as `buy` / `sell`, emits the outcome instead of failing.
+// A sell is checked against the seller's state when it is passed, a buy during the whitelist phase
+// succeeds only for the `user` whose whitelist entry is passed
+pub fn simulate_swap(
+    ctx: Context<SimulateSwap>,
+    direction: u8,
+    amount: u64,
+) -> Result<()> {
+    let pool = &ctx.accounts.pool;
+    let whitelisted = match (ctx.accounts.user.as_ref(), ctx.accounts.whitelist_entry.as_ref()) {
+        (Some(user), Some(whitelist_entry)) => {
+            let (entry, _) = Pubkey::find_program_address(
+                &[WhitelistEntry::SEED_PREFIX.as_bytes(), pool.key().as_ref(), user.key().as_ref()],
+                &crate::ID,
+            );
+            whitelist_entry.key() == entry
+        }
+        _ => false,
+    };
+    let outcome = quote_swap(
+        &ctx.accounts.dex_configuration_account,
+        pool,
+        ctx.accounts.user_state.as_deref().map(|user_state| &**user_state),
+        ctx.accounts.test_clock.as_deref().map(|test_clock| &**test_clock),
+        whitelisted,
+        direction,
+        amount,
+    );
+
+    // Program errors other than the curve's own guards are not a simulation result
+    let (amount_out, fee, error_code) = match outcome {
+        Ok((amount_out, fee)) => (amount_out, fee, 0),
+        Err(Error::AnchorError(error)) => (0, 0, error.error_code_number),
+        Err(error) => return Err(error),
+    };
+
+    emit!(SwapSimulation {
+        pool: pool.key(),
+        direction,
+        amount,
+        amount_out,
+        fee,
+        would_succeed: error_code == 0,
+        error_code,
+    });
+
+    Ok(())
+}
+
+// Tokens or lamports the user would receive and the fee, or the first guard the swap would fail
+#[allow(clippy::too_many_arguments)]
+fn quote_swap(
+    config: &CurveConfiguration,
+    pool: &LiquidityPool,
+    user_state: Option<&UserState>,
+    test_clock: Option<&TestClock>,
+    whitelisted: bool,
+    direction: u8,
+    amount: u64,
+) -> Result<(u64, u64)> {
+    let slot = Clock::get()?.slot;
+    let now = current_timestamp(test_clock)?;
+    pool.check_swap_open(config, amount, slot, now)?;
+
+    match direction {
+        SWAP_DIRECTION_BUY => {
+            pool.check_whitelisted(whitelisted, now)?;
+            let (_, fee, _, amount_out) = pool.quote_buy(config, amount, now)?;
+            Ok((amount_out - config.buy_burn_amount(amount_out)?, fee))
+        }
+        SWAP_DIRECTION_SELL => {
+            if let Some(user_state) = user_state {
+                user_state.check_sell_allowed(config.min_hold_secs, slot, now)?;
+            }
+            let (amount_out, fee, _) = pool.quote_sell(config, amount, now)?;
+            Ok((amount_out - fee, fee))
+        }
+        _ => err!(CustomError::InvalidSwapPath),
+    }
+}
+
+#[derive(Accounts)]
+pub struct SimulateSwap<'info> {
+    #[account(
+        seeds = [CurveConfiguration::SEED.as_bytes()],
+        bump = dex_configuration_account.bump,
+    )]
+    pub dex_configuration_account: Box<Account<'info, CurveConfiguration>>,
+
+    pub pool: Box<Account<'info, LiquidityPool>>,
+
+    pub user_state: Option<Box<Account<'info, UserState>>>,
+
+    /// CHECK: only its key, the wallet a whitelist phase buy is simulated for
+    pub user: Option<UncheckedAccount<'info>>,
+
+    pub whitelist_entry: Option<Box<Account<'info, WhitelistEntry>>>,
+
+    // Testing builds only, replaces the cluster clock like it does for the swaps
+    #[account(seeds = [TestClock::SEED.as_bytes()], bump)]
+    pub test_clock: Option<Box<Account<'info, TestClock>>>,
+}
@@ -0,0 +1,90 @@
+use crate::{
+    consts::{SWAP_DIRECTION_BUY, SWAP_DIRECTION_SELL},
+    errors::CustomError,
+    events::SwapPathQuote,
+    state::{CurveConfiguration, LiquidityPool},
+    utils::calculate_fee_with_dust,
+};
+use anchor_lang::prelude::*;
+
+// Quotes a multi-hop route through the pools passed as remaining accounts, one direction per pool.
+// A buy leg turns SOL into the pool token, a sell leg turns the pool token back into SOL.
+pub fn simulate_swap_path(
+    ctx: Context<SimulateSwapPath>,
+    amount_in: u64,
+    directions: Vec<u8>,
+) -> Result<()> {
+    let config = &ctx.accounts.dex_configuration_account;
+
+    if amount_in == 0 {
+        return err!(CustomError::InvalidAmount);
+    }
+    if directions.is_empty() || directions.len() != ctx.remaining_accounts.len() {
+        return err!(CustomError::InvalidSwapPath);
+    }
+
+    let now = Clock::get()?.unix_timestamp;
+    let mut amount = amount_in;
+    // Token held after the previous leg, `None` while holding SOL
+    let mut held_token: Option<Pubkey> = None;
+
+    for (index, (pool_info, direction)) in ctx
+        .remaining_accounts
+        .iter()
+        .zip(directions.iter())
+        .enumerate()
+    {
+        if pool_info.owner != &crate::ID {
+            return err!(CustomError::InvalidSwapPath);
+        }
+        let pool = LiquidityPool::try_deserialize(&mut &pool_info.data.borrow()[..])?;
+
+        amount = match *direction {
+            SWAP_DIRECTION_BUY => {
+                if held_token.is_some() {
+                    return err!(CustomError::InvalidSwapPath);
+                }
+                let (fee, _) = calculate_fee_with_dust(amount, pool.trade_fee_bps(config, now, amount), pool.fee_dust)?;
+                let amount_out = pool.buy_amount_out(amount - fee)?;
+                if amount_out > pool.reserve_token {
+                    return err!(CustomError::NotEnoughTokenInVault);
+                }
+                held_token = Some(pool.token);
+                amount_out - config.buy_burn_amount(amount_out)?
+            }
+            SWAP_DIRECTION_SELL => {
+                if index > 0 && held_token != Some(pool.token) {
+                    return err!(CustomError::InvalidSwapPath);
+                }
+                let amount_out = pool.sell_amount_out(amount)?;
+                if pool.reserve_sol < amount_out {
+                    return err!(CustomError::NotEnoughSolInVault);
+                }
+                let fee_bps = pool.sell_trade_fee_bps(config, now, amount_out);
+                let (fee, _) = calculate_fee_with_dust(amount_out, fee_bps, pool.fee_dust)?;
+                held_token = None;
+                amount_out
+                    .checked_sub(fee)
+                    .ok_or(CustomError::OverflowOrUnderflowOccurred)?
+            }
+            _ => return err!(CustomError::InvalidSwapPath),
+        };
+    }
+
+    emit!(SwapPathQuote {
+        amount_in,
+        amount_out: amount,
+        hops: directions.len() as u8,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SimulateSwapPath<'info> {
+    #[account(
+        seeds = [CurveConfiguration::SEED.as_bytes()],
+        bump = dex_configuration_account.bump,
+    )]
+    pub dex_configuration_account: Box<Account<'info, CurveConfiguration>>,
+}
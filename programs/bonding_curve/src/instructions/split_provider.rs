@@ -0,0 +1,105 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    errors::CustomError,
+    events::ProviderSplit,
+    state::{CurveConfiguration, LiquidityPool, LiquidityProvider},
+};
+
+// Moves `shares` from the user's liquidity provider account to the recipient's with their share of the
+// fee debt, so the moved shares keep their unclaimed fees. The pool's total shares are unchanged
+pub fn split_provider(ctx: Context<SplitProvider>, shares: u64) -> Result<()> {
+    let pool = &mut ctx.accounts.pool;
+    let from = &mut ctx.accounts.liquidity_provider_account;
+    let to = &mut ctx.accounts.recipient_provider_account;
+
+    if shares == 0 {
+        return err!(CustomError::InvalidAmount);
+    }
+    if shares > from.shares {
+        return err!(CustomError::InsufficientShares);
+    }
+
+    let now = Clock::get()?.unix_timestamp;
+    from.accrue_shares_seconds(now)?;
+    to.accrue_shares_seconds(now)?;
+
+    // The provider count follows the accounts holding shares, within the configured cap
+    if shares == from.shares {
+        pool.lp_count = pool.lp_count.saturating_sub(1);
+    }
+    if to.shares == 0 {
+        let max_lps = ctx.accounts.dex_configuration_account.max_lps;
+        if max_lps != 0 && pool.lp_count >= max_lps {
+            return err!(CustomError::MaxLpsReached);
+        }
+        pool.lp_count = pool
+            .lp_count
+            .checked_add(1)
+            .ok_or(CustomError::OverflowOrUnderflowOccurred)?;
+    }
+
+    let moved_debt = from
+        .fee_debt
+        .checked_mul(shares as u128)
+        .ok_or(CustomError::OverflowOrUnderflowOccurred)?
+        / from.shares as u128;
+    from.fee_debt -= moved_debt;
+    from.shares -= shares;
+    to.fee_debt = to
+        .fee_debt
+        .checked_add(moved_debt)
+        .ok_or(CustomError::FailedToAllocateShares)?;
+    to.shares = to
+        .shares
+        .checked_add(shares)
+        .ok_or(CustomError::FailedToAllocateShares)?;
+
+    emit!(ProviderSplit {
+        pool: pool.key(),
+        provider: ctx.accounts.user.key(),
+        recipient: ctx.accounts.recipient.key(),
+        shares,
+        timestamp: now,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SplitProvider<'info> {
+    #[account(
+        seeds = [CurveConfiguration::SEED.as_bytes()],
+        bump = dex_configuration_account.bump,
+    )]
+    pub dex_configuration_account: Box<Account<'info, CurveConfiguration>>,
+
+    #[account(
+        mut,
+        seeds = [LiquidityPool::POOL_SEED_PREFIX.as_bytes(), pool.token.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Box<Account<'info, LiquidityPool>>,
+
+    #[account(
+        mut,
+        seeds = [LiquidityProvider::SEED_PREFIX.as_bytes(), pool.key().as_ref(), user.key().as_ref()],
+        bump,
+    )]
+    pub liquidity_provider_account: Box<Account<'info, LiquidityProvider>>,
+
+    #[account(
+        init_if_needed,
+        payer = recipient,
+        space = LiquidityProvider::ACCOUNT_SIZE,
+        seeds = [LiquidityProvider::SEED_PREFIX.as_bytes(), pool.key().as_ref(), recipient.key().as_ref()],
+        bump,
+    )]
+    pub recipient_provider_account: Box<Account<'info, LiquidityProvider>>,
+
+    pub user: Signer<'info>,
+
+    // Both accounts are written back at the end, splitting into the same account would mint shares
+    #[account(mut, constraint = recipient.key() != user.key() @ CustomError::SplitToSelf)]
+    pub recipient: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
@@ -0,0 +1,27 @@
+use crate::{errors::CustomError, instructions::buy::Buy, utils::current_timestamp};
+use anchor_lang::prelude::*;
+
+// Buys exactly what moves the spot price up to `target_price`, solving the curve for the SOL needed.
+// The price only goes up through a buy, a target at or below the spot price is refused, and so is one
+// costing more than `max_sol_in` lamports ( fee included )
+pub fn swap_to_price<'info>(
+    ctx: Context<'_, '_, '_, 'info, Buy<'info>>,
+    target_price: u64,
+    max_sol_in: u64,
+) -> Result<()> {
+    let pool = &ctx.accounts.pool;
+    if target_price <= pool.spot_price() {
+        return err!(CustomError::InvalidTargetPrice);
+    }
+
+    let (amount, _) = pool.price_gap(
+        &ctx.accounts.dex_configuration_account,
+        current_timestamp(ctx.accounts.test_clock.as_deref().map(|test_clock| &**test_clock))?,
+        target_price,
+    )?;
+    if amount > max_sol_in {
+        return err!(CustomError::MaxSolInExceeded);
+    }
+
+    super::buy(ctx, amount)
+}
@@ -0,0 +1,321 @@
+use crate::{
+    consts::{FEE_BPS_DENOMINATOR, FEE_CAP_TIMELOCK_SECS, SECONDS_PER_DAY},
+    errors::CustomError,
+    state::*,
+};
+use anchor_lang::prelude::*;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct ConfigurationParams {
+    pub max_lps: Option<u32>,
+    pub withdraw_fee_bps: Option<u16>,
+    pub min_hold_secs: Option<u32>,
+    pub graduation_price: Option<u64>,
+    pub fee_schedule: Option<Vec<FeeTier>>,
+    pub max_slippage_bps: Option<u16>,
+    pub large_trade_threshold: Option<u64>,
+    pub large_trade_fee_bps: Option<u16>,
+    pub min_swap_amount: Option<u64>,
+    pub creator_graduation_payout: Option<u64>,
+    pub migration_reserve_bps: Option<u16>,
+    pub trading_window_start: Option<u32>,
+    pub trading_window_end: Option<u32>,
+    pub dump_tax_max_bps: Option<u16>,
+    pub migration_destination: Option<Pubkey>,
+    pub reward_mint: Option<Pubkey>,
+    pub reward_per_swap: Option<u64>,
+    pub max_swaps_per_slot: Option<u16>,
+    pub unpause_delay_secs: Option<u32>,
+    pub creator_fee_share_bps: Option<u16>,
+    pub referral_fee_share_bps: Option<u16>,
+    pub buy_burn_bps: Option<u16>,
+    pub fee_recipients: Option<Vec<FeeRecipient>>,
+    pub max_pools_per_creator: Option<u32>,
+    pub auto_compound: Option<bool>,
+    pub fee_bps: Option<u16>,
+    pub max_fee_change_bps: Option<u16>,
+    pub fee_change_period_secs: Option<u32>,
+    pub min_trade_slot_spacing: Option<u16>,
+    pub launch_auction_secs: Option<u32>,
+    pub graduation_partial_fill: Option<bool>,
+    pub max_price: Option<u64>,
+    pub min_reserve_floor: Option<ReserveFloor>,
+    pub max_lp_price_impact_bps: Option<u16>,
+    pub dynamic_fee_min_bps: Option<u16>,
+    pub dynamic_fee_max_bps: Option<u16>,
+    pub volatility_window_secs: Option<u32>,
+    pub post_graduation_trade_cap: Option<u64>,
+    pub graduation_sol_target: Option<u64>,
+    pub protocol_seed_amount: Option<u64>,
+    pub min_graduation_holders: Option<u32>,
+    pub protocol_seed_budget: Option<u64>,
+}
+
+pub fn update_configuration(
+    ctx: Context<UpdateCurveConfiguration>,
+    params: ConfigurationParams,
+) -> Result<()> {
+    let dex_config = &mut ctx.accounts.dex_configuration_account;
+    let now = Clock::get()?.unix_timestamp;
+    dex_config.apply_pending_fee_cap(now);
+    dex_config.apply_pending_unpause_delay(now);
+    // Fee knobs are checked against the cap in place before this update
+    let mut fee_changed = false;
+
+    if let Some(max_lps) = params.max_lps {
+        dex_config.max_lps = max_lps;
+    }
+
+    if let Some(withdraw_fee_bps) = params.withdraw_fee_bps {
+        if withdraw_fee_bps as u64 > FEE_BPS_DENOMINATOR {
+            return err!(CustomError::InvalidFee);
+        }
+        fee_changed |= dex_config.check_fee_change(dex_config.withdraw_fee_bps as u64, withdraw_fee_bps as u64, now)?;
+        dex_config.withdraw_fee_bps = withdraw_fee_bps;
+    }
+
+    if let Some(min_hold_secs) = params.min_hold_secs {
+        dex_config.min_hold_secs = min_hold_secs;
+    }
+
+    if let Some(graduation_price) = params.graduation_price {
+        dex_config.graduation_price = graduation_price;
+    }
+
+    if let Some(fee_schedule) = params.fee_schedule {
+        if fee_schedule.len() > CurveConfiguration::MAX_FEE_TIERS
+            || fee_schedule.iter().any(|tier| tier.fee_bps as u64 > FEE_BPS_DENOMINATOR)
+            || fee_schedule.windows(2).any(|pair| pair[0].start_ts >= pair[1].start_ts)
+        {
+            return err!(CustomError::InvalidFeeSchedule);
+        }
+        dex_config.check_fee_schedule(&fee_schedule, now)?;
+        fee_changed = true;
+        dex_config.fee_schedule = fee_schedule;
+    }
+
+    if let Some(max_slippage_bps) = params.max_slippage_bps {
+        dex_config.max_slippage_bps = max_slippage_bps;
+    }
+
+    if let Some(large_trade_threshold) = params.large_trade_threshold {
+        dex_config.large_trade_threshold = large_trade_threshold;
+    }
+
+    if let Some(large_trade_fee_bps) = params.large_trade_fee_bps {
+        if large_trade_fee_bps as u64 > FEE_BPS_DENOMINATOR {
+            return err!(CustomError::InvalidFee);
+        }
+        fee_changed |= dex_config.check_fee_change(dex_config.large_trade_fee_bps as u64, large_trade_fee_bps as u64, now)?;
+        dex_config.large_trade_fee_bps = large_trade_fee_bps;
+    }
+
+    if let Some(min_swap_amount) = params.min_swap_amount {
+        dex_config.min_swap_amount = min_swap_amount;
+    }
+
+    if let Some(creator_graduation_payout) = params.creator_graduation_payout {
+        dex_config.creator_graduation_payout = creator_graduation_payout;
+    }
+
+    if let Some(migration_reserve_bps) = params.migration_reserve_bps {
+        if migration_reserve_bps as u64 >= FEE_BPS_DENOMINATOR {
+            return err!(CustomError::InvalidAmount);
+        }
+        dex_config.migration_reserve_bps = migration_reserve_bps;
+    }
+
+    if let Some(trading_window_start) = params.trading_window_start {
+        if trading_window_start as i64 >= SECONDS_PER_DAY {
+            return err!(CustomError::InvalidTradingWindow);
+        }
+        dex_config.trading_window_start = trading_window_start;
+    }
+
+    if let Some(trading_window_end) = params.trading_window_end {
+        if trading_window_end as i64 >= SECONDS_PER_DAY {
+            return err!(CustomError::InvalidTradingWindow);
+        }
+        dex_config.trading_window_end = trading_window_end;
+    }
+
+    if let Some(dump_tax_max_bps) = params.dump_tax_max_bps {
+        if dump_tax_max_bps as u64 > FEE_BPS_DENOMINATOR {
+            return err!(CustomError::InvalidFee);
+        }
+        fee_changed |= dex_config.check_fee_change(dex_config.dump_tax_max_bps as u64, dump_tax_max_bps as u64, now)?;
+        dex_config.dump_tax_max_bps = dump_tax_max_bps;
+    }
+
+    if let Some(migration_destination) = params.migration_destination {
+        dex_config.migration_destination = migration_destination;
+    }
+
+    if let Some(reward_mint) = params.reward_mint {
+        dex_config.reward_mint = reward_mint;
+    }
+
+    if let Some(reward_per_swap) = params.reward_per_swap {
+        dex_config.reward_per_swap = reward_per_swap;
+    }
+
+    if let Some(max_swaps_per_slot) = params.max_swaps_per_slot {
+        dex_config.max_swaps_per_slot = max_swaps_per_slot;
+    }
+
+    // A longer unpause delay applies at once, a shorter one only after the current delay ran out, so it
+    // cannot cut short an unpause the current delay holds back
+    if let Some(unpause_delay_secs) = params.unpause_delay_secs {
+        if unpause_delay_secs < dex_config.unpause_delay_secs {
+            dex_config.pending_unpause_delay_secs = unpause_delay_secs;
+            dex_config.unpause_delay_ready_ts = now + dex_config.unpause_delay_secs as i64;
+        } else {
+            dex_config.unpause_delay_secs = unpause_delay_secs;
+            dex_config.unpause_delay_ready_ts = 0;
+        }
+    }
+
+    if let Some(creator_fee_share_bps) = params.creator_fee_share_bps {
+        dex_config.creator_fee_share_bps = creator_fee_share_bps;
+    }
+
+    if let Some(referral_fee_share_bps) = params.referral_fee_share_bps {
+        dex_config.referral_fee_share_bps = referral_fee_share_bps;
+    }
+
+    if dex_config.creator_fee_share_bps as u64 + dex_config.referral_fee_share_bps as u64 > FEE_BPS_DENOMINATOR {
+        return err!(CustomError::InvalidFee);
+    }
+
+    if let Some(buy_burn_bps) = params.buy_burn_bps {
+        if buy_burn_bps as u64 >= FEE_BPS_DENOMINATOR {
+            return err!(CustomError::InvalidAmount);
+        }
+        dex_config.buy_burn_bps = buy_burn_bps;
+    }
+
+    if let Some(fee_recipients) = params.fee_recipients {
+        let total_weight: u64 = fee_recipients.iter().map(|route| route.weight_bps as u64).sum();
+        if fee_recipients.len() > CurveConfiguration::MAX_FEE_RECIPIENTS
+            || (!fee_recipients.is_empty() && total_weight != FEE_BPS_DENOMINATOR)
+        {
+            return err!(CustomError::InvalidFeeRecipients);
+        }
+        dex_config.fee_recipients = fee_recipients;
+    }
+
+    if let Some(max_pools_per_creator) = params.max_pools_per_creator {
+        dex_config.max_pools_per_creator = max_pools_per_creator;
+    }
+
+    if let Some(auto_compound) = params.auto_compound {
+        dex_config.auto_compound = auto_compound;
+    }
+
+    if let Some(fee_bps) = params.fee_bps {
+        if fee_bps as u64 > FEE_BPS_DENOMINATOR {
+            return err!(CustomError::InvalidFee);
+        }
+        fee_changed |= dex_config.check_fee_change(dex_config.fee_bps(), fee_bps as u64, now)?;
+        dex_config.fees = fee_bps as f64 / 100.0;
+    }
+
+    if let Some(min_trade_slot_spacing) = params.min_trade_slot_spacing {
+        dex_config.min_trade_slot_spacing = min_trade_slot_spacing;
+    }
+
+    if let Some(launch_auction_secs) = params.launch_auction_secs {
+        dex_config.launch_auction_secs = launch_auction_secs;
+    }
+
+    if let Some(graduation_partial_fill) = params.graduation_partial_fill {
+        dex_config.graduation_partial_fill = graduation_partial_fill;
+    }
+
+    if let Some(max_price) = params.max_price {
+        dex_config.max_price = max_price;
+    }
+
+    if let Some(min_reserve_floor) = params.min_reserve_floor {
+        dex_config.min_reserve_floor = min_reserve_floor;
+    }
+
+    if let Some(max_lp_price_impact_bps) = params.max_lp_price_impact_bps {
+        dex_config.max_lp_price_impact_bps = max_lp_price_impact_bps;
+    }
+
+    if params.dynamic_fee_min_bps.is_some() || params.dynamic_fee_max_bps.is_some() {
+        let min_bps = params.dynamic_fee_min_bps.unwrap_or(dex_config.dynamic_fee_min_bps);
+        let max_bps = params.dynamic_fee_max_bps.unwrap_or(dex_config.dynamic_fee_max_bps);
+        if max_bps as u64 > FEE_BPS_DENOMINATOR || (max_bps != 0 && min_bps > max_bps) {
+            return err!(CustomError::InvalidFee);
+        }
+        fee_changed |= dex_config.check_fee_change(dex_config.dynamic_fee_min_bps as u64, min_bps as u64, now)?;
+        fee_changed |= dex_config.check_fee_change(dex_config.dynamic_fee_max_bps as u64, max_bps as u64, now)?;
+        dex_config.dynamic_fee_min_bps = min_bps;
+        dex_config.dynamic_fee_max_bps = max_bps;
+    }
+
+    if let Some(volatility_window_secs) = params.volatility_window_secs {
+        dex_config.volatility_window_secs = volatility_window_secs;
+    }
+
+    if let Some(post_graduation_trade_cap) = params.post_graduation_trade_cap {
+        dex_config.post_graduation_trade_cap = post_graduation_trade_cap;
+    }
+
+    if let Some(graduation_sol_target) = params.graduation_sol_target {
+        dex_config.graduation_sol_target = graduation_sol_target;
+    }
+
+    if let Some(protocol_seed_amount) = params.protocol_seed_amount {
+        dex_config.protocol_seed_amount = protocol_seed_amount;
+    }
+
+    if let Some(min_graduation_holders) = params.min_graduation_holders {
+        dex_config.min_graduation_holders = min_graduation_holders;
+    }
+
+    if let Some(protocol_seed_budget) = params.protocol_seed_budget {
+        dex_config.protocol_seed_budget = protocol_seed_budget;
+    }
+
+    if fee_changed {
+        dex_config.last_fee_change_ts = now;
+    }
+
+    // A tighter fee change cap applies at once, a looser one, disabling included, waits out the time lock
+    if params.max_fee_change_bps.is_some() || params.fee_change_period_secs.is_some() {
+        let max_fee_change_bps = params.max_fee_change_bps.unwrap_or(dex_config.max_fee_change_bps);
+        let fee_change_period_secs = params.fee_change_period_secs.unwrap_or(dex_config.fee_change_period_secs);
+        let current_bps = dex_config.max_fee_change_bps;
+        let looser = current_bps != 0
+            && (max_fee_change_bps == 0
+                || max_fee_change_bps > current_bps
+                || fee_change_period_secs < dex_config.fee_change_period_secs);
+        if looser {
+            dex_config.pending_max_fee_change_bps = max_fee_change_bps;
+            dex_config.pending_fee_change_period_secs = fee_change_period_secs;
+            dex_config.fee_cap_ready_ts = now + FEE_CAP_TIMELOCK_SECS;
+        } else {
+            dex_config.max_fee_change_bps = max_fee_change_bps;
+            dex_config.fee_change_period_secs = fee_change_period_secs;
+            dex_config.fee_cap_ready_ts = 0;
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct UpdateCurveConfiguration<'info> {
+    #[account(
+        mut,
+        seeds = [CurveConfiguration::SEED.as_bytes()],
+        bump = dex_configuration_account.bump,
+        has_one = authority @ CustomError::NotAuthority,
+    )]
+    pub dex_configuration_account: Box<Account<'info, CurveConfiguration>>,
+
+    pub authority: Signer<'info>,
+}
@@ -0,0 +1,77 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::{
+    errors::CustomError,
+    events::ProtocolTokensWithdrawn,
+    state::{CurveConfiguration, LiquidityPool, LiquidityPoolAccount},
+};
+
+pub fn withdraw_protocol_tokens(ctx: Context<WithdrawProtocolTokens>, amount: u64) -> Result<()> {
+    let pool = &mut ctx.accounts.pool;
+
+    // Only the accrued fees can leave, the curve reserve stays untouched
+    if amount == 0 {
+        return err!(CustomError::InvalidAmount);
+    }
+    if amount > pool.fee_token_accrued {
+        return err!(CustomError::ExceedsAccruedFees);
+    }
+    pool.fee_token_accrued -= amount;
+
+    pool.transfer_token_from_pool(
+        &ctx.accounts.token_mint,
+        &ctx.accounts.pool_token_account,
+        &ctx.accounts.recipient_token_account,
+        amount,
+        &ctx.accounts.token_program,
+    )?;
+
+    emit!(ProtocolTokensWithdrawn {
+        pool: pool.key(),
+        recipient: ctx.accounts.recipient_token_account.owner,
+        amount,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct WithdrawProtocolTokens<'info> {
+    #[account(
+        seeds = [CurveConfiguration::SEED.as_bytes()],
+        bump = dex_configuration_account.bump,
+        has_one = authority @ CustomError::NotAuthority,
+    )]
+    pub dex_configuration_account: Box<Account<'info, CurveConfiguration>>,
+
+    #[account(
+        mut,
+        seeds = [LiquidityPool::POOL_SEED_PREFIX.as_bytes(), token_mint.key().as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Box<Account<'info, LiquidityPool>>,
+
+    pub token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program,
+    )]
+    pub pool_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = token_mint,
+    )]
+    pub recipient_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub authority: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
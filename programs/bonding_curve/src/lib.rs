@@ -1,6 +1,7 @@
 use anchor_lang::prelude::*;
 
 pub mod errors;
+pub mod events;
 pub mod utils;
 pub mod instructions;
 pub mod state;
@@ -8,6 +9,7 @@ pub mod consts;
 
 use crate::instructions::*;
 
+// The entrypoint Anchor generates refuses to run under any other id ( DeclaredProgramIdMismatch )
 declare_id!("5mdPUgyK9mqosLtqZvfpY5pcpCqQBWHuS3XoU34CrJK3");
 
 #[program]
@@ -18,13 +20,36 @@ pub mod bonding_curve {
         instructions::initialize(ctx, fee)
     }
 
-    pub fn create_pool(ctx: Context<CreateLiquidityPool>) -> Result<()> {
-        instructions::create_pool(ctx)
+    pub fn create_pool(
+        ctx: Context<CreateLiquidityPool>,
+        launch_sell_fee_bps: u16,
+        launch_fee_window_secs: u32,
+        metadata_uri: String,
+    ) -> Result<()> {
+        instructions::create_pool(ctx, launch_sell_fee_bps, launch_fee_window_secs, metadata_uri)
+    }
+
+    pub fn create_pools_batch<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CreatePoolsBatch<'info>>,
+        pools: Vec<PoolParams>,
+    ) -> Result<()> {
+        instructions::create_pools_batch(ctx, pools)
     }
 
-    pub fn add_liquidity(
-        ctx: Context<AddLiquidity>,
+    pub fn create_vesting(
+        ctx: Context<CreateVesting>,
+        total: u64,
+        cliff_secs: u32,
+        duration_secs: u32,
     ) -> Result<()> {
+        instructions::create_vesting(ctx, total, cliff_secs, duration_secs)
+    }
+
+    pub fn claim_vested(ctx: Context<ClaimVested>) -> Result<()> {
+        instructions::claim_vested(ctx)
+    }
+
+    pub fn add_liquidity(ctx: Context<AddLiquidity>) -> Result<()> {
         instructions::add_liquidity(ctx)
     }
 
@@ -32,13 +57,181 @@ pub mod bonding_curve {
         instructions::remove_liquidity(ctx, bump)
     }
 
-    pub fn buy(ctx: Context<Buy>, amount: u64) -> Result<()> {
+    pub fn buy<'info>(
+        ctx: Context<'_, '_, '_, 'info, Buy<'info>>,
+        amount: u64,
+    ) -> Result<()> {
         instructions::buy(ctx, amount)
     }
 
-    pub fn sell(ctx: Context<Sell>, amount: u64, bump: u8) -> Result<()> {
+    pub fn swap_to_price<'info>(
+        ctx: Context<'_, '_, '_, 'info, Buy<'info>>,
+        target_price: u64,
+        max_sol_in: u64,
+    ) -> Result<()> {
+        instructions::swap_to_price(ctx, target_price, max_sol_in)
+    }
+
+    pub fn sell<'info>(
+        ctx: Context<'_, '_, '_, 'info, Sell<'info>>,
+        amount: u64,
+        bump: u8,
+    ) -> Result<()> {
         instructions::sell(ctx, amount, bump)
     }
-    
+
+    pub fn set_post_swap_program(ctx: Context<SetPostSwapProgram>, program: Option<Pubkey>) -> Result<()> {
+        instructions::set_post_swap_program(ctx, program)
+    }
+
+    pub fn claim_creator_fees(ctx: Context<ClaimCreatorFees>, bump: u8) -> Result<()> {
+        instructions::claim_creator_fees(ctx, bump)
+    }
+
+    pub fn set_airdrop_mint(ctx: Context<SetAirdropMint>) -> Result<()> {
+        instructions::set_airdrop_mint(ctx)
+    }
+
+    pub fn claim_airdrop(ctx: Context<ClaimAirdrop>) -> Result<()> {
+        instructions::claim_airdrop(ctx)
+    }
+
+    pub fn commit_auction(ctx: Context<CommitAuction>, amount: u64) -> Result<()> {
+        instructions::commit_auction(ctx, amount)
+    }
+
+    pub fn settle_auction<'info>(ctx: Context<'_, '_, '_, 'info, SettleAuction<'info>>) -> Result<()> {
+        instructions::settle_auction(ctx)
+    }
+
+    pub fn claim_auction(ctx: Context<ClaimAuction>) -> Result<()> {
+        instructions::claim_auction(ctx)
+    }
+
+    pub fn refund_auction(ctx: Context<RefundAuction>) -> Result<()> {
+        instructions::refund_auction(ctx)
+    }
+
+    pub fn claim_lp_fees(ctx: Context<ClaimLpFees>, bump: u8) -> Result<()> {
+        instructions::claim_lp_fees(ctx, bump)
+    }
+
+    /// Pays out the provider's fees, burns its shares and closes its account. No principal is returned,
+    /// the deposit stays in the curve's reserves
+    pub fn close_liquidity_provider(ctx: Context<CloseLiquidityProvider>) -> Result<()> {
+        instructions::close_liquidity_provider(ctx)
+    }
+
+    pub fn split_provider(ctx: Context<SplitProvider>, shares: u64) -> Result<()> {
+        instructions::split_provider(ctx, shares)
+    }
+
+    pub fn reset_pool(ctx: Context<ResetPool>) -> Result<()> {
+        instructions::reset_pool(ctx)
+    }
+
+    pub fn set_virtual_reserve(ctx: Context<SetVirtualReserve>, virtual_sol_reserve: u64) -> Result<()> {
+        instructions::set_virtual_reserve(ctx, virtual_sol_reserve)
+    }
+
+    pub fn admin_swap(ctx: Context<AdminSwap>, amount: u64, direction: u8, bump: u8) -> Result<()> {
+        instructions::admin_swap(ctx, amount, direction, bump)
+    }
+
+    pub fn buyback(ctx: Context<Buyback>, amount: u64, burn: bool) -> Result<()> {
+        instructions::buyback(ctx, amount, burn)
+    }
+
+    pub fn set_graduation_target(ctx: Context<SetGraduationTarget>, graduation_price: u64) -> Result<()> {
+        instructions::set_graduation_target(ctx, graduation_price)
+    }
+
+    pub fn set_whitelist_phase(ctx: Context<SetWhitelistPhase>, whitelist_phase_end_ts: i64) -> Result<()> {
+        instructions::set_whitelist_phase(ctx, whitelist_phase_end_ts)
+    }
+
+    pub fn add_to_whitelist(ctx: Context<AddToWhitelist>, user: Pubkey) -> Result<()> {
+        instructions::add_to_whitelist(ctx, user)
+    }
+
+    pub fn set_test_clock(ctx: Context<SetTestClock>, unix_timestamp: i64) -> Result<()> {
+        instructions::set_test_clock(ctx, unix_timestamp)
+    }
+
+    pub fn set_paused(ctx: Context<SetPaused>, paused: bool, reason: u8) -> Result<()> {
+        instructions::set_paused(ctx, paused, reason)
+    }
+
+    pub fn apply_unpause(ctx: Context<ApplyUnpause>) -> Result<()> {
+        instructions::apply_unpause(ctx)
+    }
+
+    pub fn update_configuration(
+        ctx: Context<UpdateCurveConfiguration>,
+        params: ConfigurationParams,
+    ) -> Result<()> {
+        instructions::update_configuration(ctx, params)
+    }
+
+    pub fn quote_graduation(ctx: Context<QuoteGraduation>) -> Result<()> {
+        instructions::quote_graduation(ctx)
+    }
+
+    pub fn quote_market_cap(ctx: Context<QuoteMarketCap>) -> Result<()> {
+        instructions::quote_market_cap(ctx)
+    }
+
+    pub fn simulate_graduation(ctx: Context<SimulateGraduation>) -> Result<()> {
+        instructions::simulate_graduation(ctx)
+    }
+
+    pub fn can_migrate(ctx: Context<CanMigrate>) -> Result<()> {
+        instructions::can_migrate(ctx)
+    }
+
+    pub fn get_config(ctx: Context<GetConfig>) -> Result<()> {
+        instructions::get_config(ctx)
+    }
+
+    pub fn quote_fair_price(ctx: Context<QuoteFairPrice>) -> Result<()> {
+        instructions::quote_fair_price(ctx)
+    }
+
+    pub fn quote_lp_apr(ctx: Context<QuoteLpApr>) -> Result<()> {
+        instructions::quote_lp_apr(ctx)
+    }
+
+    pub fn quote_spread(ctx: Context<QuoteSpread>, amount: u64) -> Result<()> {
+        instructions::quote_spread(ctx, amount)
+    }
+
+    pub fn withdraw_protocol_tokens(ctx: Context<WithdrawProtocolTokens>, amount: u64) -> Result<()> {
+        instructions::withdraw_protocol_tokens(ctx, amount)
+    }
+
+    pub fn recover_stuck_sol(ctx: Context<RecoverStuckSol>) -> Result<()> {
+        instructions::recover_stuck_sol(ctx)
+    }
+
+    pub fn redeem_protocol_seed(ctx: Context<RedeemProtocolSeed>) -> Result<()> {
+        instructions::redeem_protocol_seed(ctx)
+    }
+
+    pub fn simulate_swap(
+        ctx: Context<SimulateSwap>,
+        direction: u8,
+        amount: u64,
+    ) -> Result<()> {
+        instructions::simulate_swap(ctx, direction, amount)
+    }
+
+    pub fn simulate_swap_path(
+        ctx: Context<SimulateSwapPath>,
+        amount_in: u64,
+        directions: Vec<u8>,
+    ) -> Result<()> {
+        instructions::simulate_swap_path(ctx, amount_in, directions)
+    }
+
 }
 
@@ -1,39 +1,442 @@
+use crate::consts::ACC_FEE_PRECISION;
+use crate::consts::FEE_BPS_DENOMINATOR;
 use crate::consts::INITIAL_LAMPORTS_FOR_POOL;
-use crate::consts::INITIAL_PRICE_DIVIDER;
+use crate::consts::LP_APR_MIN_WINDOW_SECS;
+use crate::consts::LP_APR_WINDOW_SECS;
+use crate::consts::MAX_PRICE;
+use crate::consts::PRICE_SCALE;
 use crate::consts::PROPORTION;
+use crate::consts::SECONDS_PER_DAY;
+use crate::consts::SECONDS_PER_YEAR;
+use crate::consts::SHARE_DECIMALS;
+use crate::consts::VOLATILITY_FULL_FEE_BPS;
+use crate::consts::{SWAP_DIRECTION_BUY, SWAP_DIRECTION_SELL};
 use crate::errors::CustomError;
+use crate::events::{PoolCompleted, ProtocolSeedReturned};
+use crate::utils::{calculate_fee, calculate_fee_with_dust, check_rounding_favors_pool, split_fee};
 use anchor_lang::prelude::*;
 use anchor_lang::system_program;
-use anchor_spl::token::{self, Mint, Token, TokenAccount};
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface};
 
 #[account]
 pub struct CurveConfiguration {
     pub fees: f64,
+    pub authority: Pubkey, // Admin allowed to update the configuration
+    pub max_lps: u32,      // Maximum liquidity providers per pool ( 0 means unlimited )
+    pub withdraw_fee_bps: u16, // Fee on tokens withdrawn by remove_liquidity ( 0 disables it )
+    pub min_hold_secs: u32,    // Seconds a buyer has to hold before selling ( 0 disables it )
+    pub graduation_price: u64, // Spot price completing the curve ( 0 disables it )
+    pub fee_schedule: Vec<FeeTier>, // Swap fee tiers sorted by start time, overriding `fees` once started
+    pub bump: u8,              // Nonce for the program-derived address
+    pub max_slippage_bps: u16, // Price impact any swap may reach ( 0 disables it )
+    pub large_trade_threshold: u64, // Swap size in lamports from which the large trade fee applies ( 0 disables it )
+    pub large_trade_fee_bps: u16,   // Reduced swap fee for large trades
+    pub min_swap_amount: u64,       // Smallest swap in lamports ( 0 disables it )
+    pub creator_graduation_payout: u64, // Lamports paid to the pool creator from the raised SOL at graduation
+    pub migration_reserve_bps: u16,     // Share of the funded tokens held back from the curve for migration
+    pub trading_window_start: u32,      // Seconds into the UTC day trading opens ( equal bounds disable the window )
+    pub trading_window_end: u32,        // Seconds into the UTC day trading closes, may wrap past midnight
+    pub dump_tax_max_bps: u16,          // Extra sell fee reached by a sell draining the whole SOL reserve ( 0 disables it )
+    pub migration_destination: Pubkey,  // Program graduated pools migrate to ( default key means unset )
+    pub reward_mint: Pubkey,            // Secondary token paid to traders on every swap ( default key means unset )
+    pub reward_per_swap: u64,           // Reward tokens paid per swap from the configuration's vault ( 0 disables it )
+    pub max_swaps_per_slot: u16,        // Swaps a pool accepts within one slot ( 0 means unlimited )
+    pub paused: bool,                   // Swaps are halted on every pool
+    pub unpause_delay_secs: u32,        // Delay between scheduling and applying an unpause ( 0 unpauses at once )
+    pub unpause_ready_ts: i64,          // When the scheduled unpause can be applied ( 0 when none is scheduled )
+    pub creator_fee_share_bps: u16,     // Share of every swap fee owed to the pool creator
+    pub referral_fee_share_bps: u16,    // Share of every swap fee paid to the referrer, kept by the protocol without one
+    pub buy_burn_bps: u16,              // Share of every buy's tokens burned instead of delivered ( 0 disables it )
+    pub fee_recipients: Vec<FeeRecipient>, // Wallets the protocol fee share is routed to by weight, instead of the LPs
+    pub max_pools_per_creator: u32,     // Pools one creator may create ( 0 means unlimited )
+    pub auto_compound: bool,            // Swap fees left to the pool deepen its SOL reserve instead of paying the LPs
+    pub max_fee_change_bps: u16,        // Largest step of a swap fee change ( 0 disables the cap )
+    pub fee_change_period_secs: u32,    // Minimum time between two capped swap fee changes
+    pub last_fee_change_ts: i64,        // Time of the latest swap fee change
+    pub min_trade_slot_spacing: u16,    // Slots between two trades on a pool, against sandwiches ( 0 disables it )
+    pub launch_auction_secs: u32,       // Length of the launch auction opening every newly funded pool ( 0 disables it )
+    pub graduation_partial_fill: bool,  // A buy crossing the graduation price stops there, the rest of its input is not taken
+    pub max_price: u64,                 // Spot price no buy may push the curve past, at most MAX_PRICE ( 0 means MAX_PRICE )
+    pub pool_count: u64,                // Pools created so far, saturating at u64::MAX
+    pub min_reserve_floor: ReserveFloor, // Reserves swaps and remove_liquidity may not take a pool below
+    pub max_lp_price_impact_bps: u16,   // Spot price move a liquidity removal may cause ( 0 disables it )
+    pub dynamic_fee_min_bps: u16,       // Swap fee of a calm pool with the dynamic fee
+    pub dynamic_fee_max_bps: u16,       // Swap fee reached as recent volatility grows, replaces the static fee ( 0 disables it )
+    pub volatility_window_secs: u32,    // Seconds over which a pool's recorded volatility decays to zero
+    pub post_graduation_trade_cap: u64, // Lamports a curve graduating from now on still trades, for price discovery ( 0 closes it at graduation )
+    pub graduation_sol_target: u64,     // Net lamports raised by the curve that complete it, fees excluded ( 0 disables it )
+    pub pause_reason: u8,               // Why swaps are paused, one of the PAUSE_REASON_* codes
+    pub protocol_seed_amount: u64,      // Lamports the protocol treasury seeds into every new pool's SOL reserve ( 0 disables it )
+    pub min_graduation_holders: u32,    // Distinct buyers a pool needs before it can graduate ( 0 disables it )
+    pub protocol_seed_budget: u64,      // Lamports the treasury may still spend seeding pools, every seed takes from it
+    pub pending_max_fee_change_bps: u16, // Looser fee change cap waiting out FEE_CAP_TIMELOCK_SECS
+    pub pending_fee_change_period_secs: u32, // Shorter fee change period waiting with it
+    pub fee_cap_ready_ts: i64,          // When the pending fee change cap applies ( 0 when none is pending )
+    pub pending_unpause_delay_secs: u32, // Shorter unpause delay waiting out the current one
+    pub unpause_delay_ready_ts: i64,    // When the pending unpause delay applies ( 0 when none is pending )
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct FeeTier {
+    pub start_ts: i64,
+    pub fee_bps: u16,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct FeeRecipient {
+    pub recipient: Pubkey,
+    pub weight_bps: u16,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct ReserveFloor {
+    pub token: u64, // Token base units left in reserve_token ( 0 disables it )
+    pub sol: u64,   // Lamports left in reserve_sol ( 0 disables it )
+}
+
+impl ReserveFloor {
+    pub fn is_set(&self) -> bool {
+        self.token != 0 || self.sol != 0
+    }
 }
 
 impl CurveConfiguration {
     pub const SEED: &'static str = "CurveConfiguration";
+    pub const TREASURY_SEED: &'static str = "protocol_treasury"; // System account holding the SOL new pools are seeded from
+
+    pub const MAX_FEE_TIERS: usize = 4;
+
+    pub const MAX_FEE_RECIPIENTS: usize = 4;
+
+    // Discriminator (8) + fees (8) + authority (32) + max lps (4) + withdraw fee (2) + min hold (4)
+    // + graduation price (8) + fee schedule vec (4 + MAX_FEE_TIERS * (start ts 8 + fee bps 2)) + bump (1)
+    // + max slippage (2) + large trade threshold (8) + large trade fee (2) + min swap amount (8)
+    // + creator graduation payout (8) + migration reserve (2) + trading window start (4) + trading window end (4)
+    // + dump tax (2) + migration destination (32) + reward mint (32) + reward per swap (8) + max swaps per slot (2)
+    // + paused (1) + unpause delay (4) + unpause ready (8) + creator fee share (2) + referral fee share (2)
+    // + buy burn (2) + fee recipients vec (4 + MAX_FEE_RECIPIENTS * (recipient 32 + weight 2))
+    // + max pools per creator (4) + auto compound (1) + max fee change (2) + fee change period (4)
+    // + last fee change (8) + min trade slot spacing (2) + launch auction (4) + graduation partial fill (1)
+    // + max price (8) + pool count (8) + min reserve floor (8 + 8) + max lp price impact (2)
+    // + dynamic fee min (2) + dynamic fee max (2) + volatility window (4) + post graduation trade cap (8)
+    // + graduation sol target (8) + pause reason (1) + protocol seed amount (8) + min graduation holders (4)
+    // + protocol seed budget (8) + pending max fee change (2) + pending fee change period (4) + fee cap ready (8)
+    // + pending unpause delay (4) + unpause delay ready (8)
+    pub const ACCOUNT_SIZE: usize =
+        8 + 8 + 32 + 4 + 2 + 4 + 8 + 4 + Self::MAX_FEE_TIERS * (8 + 2) + 1 + 2 + 8 + 2 + 8 + 8 + 2 + 4 + 4 + 2
+            + 32 + 32 + 8 + 2 + 1 + 4 + 8 + 2 + 2 + 2
+            + 4 + Self::MAX_FEE_RECIPIENTS * (32 + 2)
+            + 4 + 1 + 2 + 4 + 8 + 2 + 4 + 1 + 8 + 8 + 8 + 8 + 2 + 2 + 2 + 4 + 8 + 8 + 1 + 8 + 4 + 8 + 2 + 4 + 8 + 4 + 8;
+
+    pub fn new(fees: f64, authority: Pubkey, bump: u8) -> Self {
+        Self {
+            fees,
+            authority,
+            max_lps: 0,
+            withdraw_fee_bps: 0,
+            min_hold_secs: 0,
+            graduation_price: 0,
+            fee_schedule: Vec::new(),
+            bump,
+            max_slippage_bps: 0,
+            large_trade_threshold: 0,
+            large_trade_fee_bps: 0,
+            min_swap_amount: 0,
+            creator_graduation_payout: 0,
+            migration_reserve_bps: 0,
+            trading_window_start: 0,
+            trading_window_end: 0,
+            dump_tax_max_bps: 0,
+            migration_destination: Pubkey::default(),
+            reward_mint: Pubkey::default(),
+            reward_per_swap: 0,
+            max_swaps_per_slot: 0,
+            paused: false,
+            unpause_delay_secs: 0,
+            unpause_ready_ts: 0,
+            creator_fee_share_bps: 0,
+            referral_fee_share_bps: 0,
+            buy_burn_bps: 0,
+            fee_recipients: Vec::new(),
+            max_pools_per_creator: 0,
+            auto_compound: false,
+            max_fee_change_bps: 0,
+            fee_change_period_secs: 0,
+            last_fee_change_ts: 0,
+            min_trade_slot_spacing: 0,
+            launch_auction_secs: 0,
+            graduation_partial_fill: false,
+            max_price: 0,
+            pool_count: 0,
+            min_reserve_floor: ReserveFloor::default(),
+            max_lp_price_impact_bps: 0,
+            dynamic_fee_min_bps: 0,
+            dynamic_fee_max_bps: 0,
+            volatility_window_secs: 0,
+            post_graduation_trade_cap: 0,
+            graduation_sol_target: 0,
+            pause_reason: 0,
+            protocol_seed_amount: 0,
+            min_graduation_holders: 0,
+            protocol_seed_budget: 0,
+            pending_max_fee_change_bps: 0,
+            pending_fee_change_period_secs: 0,
+            fee_cap_ready_ts: 0,
+            pending_unpause_delay_secs: 0,
+            unpause_delay_ready_ts: 0,
+        }
+    }
+
+    // Normal swap fee converted from percent to basis points
+    pub fn fee_bps(&self) -> u64 {
+        (self.fees * 100.0).round() as u64
+    }
+
+    // Every fee knob moves within the fee change cap, at most once per fee change period. Returns whether
+    // the fee changes, for the caller to restart the period
+    pub fn check_fee_change(&self, from_bps: u64, to_bps: u64, now: i64) -> Result<bool> {
+        if from_bps == to_bps {
+            return Ok(false);
+        }
+        if self.max_fee_change_bps != 0
+            && (from_bps.abs_diff(to_bps) > self.max_fee_change_bps as u64
+                || now < self.last_fee_change_ts + self.fee_change_period_secs as i64)
+        {
+            return err!(CustomError::FeeChangeTooFast);
+        }
+        Ok(true)
+    }
+
+    // A fee schedule under the cap steps like successive fee changes: each tier within the cap of the one
+    // before it, the first of the normal fee, and a fee change period after it
+    pub fn check_fee_schedule(&self, fee_schedule: &[FeeTier], now: i64) -> Result<()> {
+        if self.max_fee_change_bps == 0 {
+            return Ok(());
+        }
+        let (mut previous_bps, mut previous_ts) = (self.fee_bps(), self.last_fee_change_ts);
+        for tier in fee_schedule {
+            let start_ts = tier.start_ts.max(now);
+            if previous_bps.abs_diff(tier.fee_bps as u64) > self.max_fee_change_bps as u64
+                || start_ts < previous_ts + self.fee_change_period_secs as i64
+            {
+                return err!(CustomError::FeeChangeTooFast);
+            }
+            (previous_bps, previous_ts) = (tier.fee_bps as u64, start_ts);
+        }
+        Ok(())
+    }
+
+    // Applies a looser fee change cap once its time lock ran out
+    pub fn apply_pending_fee_cap(&mut self, now: i64) {
+        if self.fee_cap_ready_ts != 0 && now >= self.fee_cap_ready_ts {
+            self.max_fee_change_bps = self.pending_max_fee_change_bps;
+            self.fee_change_period_secs = self.pending_fee_change_period_secs;
+            self.fee_cap_ready_ts = 0;
+        }
+    }
+
+    // Applies a shorter unpause delay once the delay it replaces ran out
+    pub fn apply_pending_unpause_delay(&mut self, now: i64) {
+        if self.unpause_delay_ready_ts != 0 && now >= self.unpause_delay_ready_ts {
+            self.unpause_delay_secs = self.pending_unpause_delay_secs;
+            self.unpause_delay_ready_ts = 0;
+        }
+    }
+
+    // Swap fee at `now`, taken from the latest started tier of the schedule
+    pub fn swap_fee_bps(&self, now: i64) -> u64 {
+        self.fee_schedule
+            .iter()
+            .rev()
+            .find(|tier| tier.start_ts <= now)
+            .map(|tier| tier.fee_bps as u64)
+            .unwrap_or_else(|| self.fee_bps())
+    }
 
-    // Discriminator (8) + f64 (8)
-    pub const ACCOUNT_SIZE: usize = 8 + 32 + 8;
+    // Program wide gates on anything that trades against a curve: the pause and the daily trading window
+    pub fn check_trading_allowed(&self, now: i64) -> Result<()> {
+        if self.paused {
+            return err!(CustomError::ProgramPaused);
+        }
+        if !self.is_trading_open(now) {
+            return err!(CustomError::OutsideTradingWindow);
+        }
+        Ok(())
+    }
 
-    pub fn new(fees: f64) -> Self {
-        Self { fees }
+    // Whether `now` falls in the daily trading window, a start after the end wraps past midnight
+    pub fn is_trading_open(&self, now: i64) -> bool {
+        let start = self.trading_window_start as i64;
+        let end = self.trading_window_end as i64;
+        if start == end {
+            return true;
+        }
+        let time_of_day = now.rem_euclid(SECONDS_PER_DAY);
+        if start < end {
+            time_of_day >= start && time_of_day < end
+        } else {
+            time_of_day >= start || time_of_day < end
+        }
+    }
+
+    // Tokens burned out of a buy paying out `amount_out`, rounded down
+    pub fn buy_burn_amount(&self, amount_out: u64) -> Result<u64> {
+        calculate_fee(amount_out, self.buy_burn_bps as u64)
+    }
+
+    // Price cap buys are checked against, the configured one bounded by MAX_PRICE
+    pub fn price_cap(&self) -> u64 {
+        if self.max_price == 0 {
+            MAX_PRICE
+        } else {
+            self.max_price.min(MAX_PRICE)
+        }
+    }
+
+    // Swap fee for a trade worth `sol_amount` lamports, large trades get the reduced rate when it is lower
+    pub fn trade_fee_bps(&self, now: i64, sol_amount: u64) -> u64 {
+        let fee_bps = self.swap_fee_bps(now);
+        if self.large_trade_threshold != 0 && sol_amount >= self.large_trade_threshold {
+            return fee_bps.min(self.large_trade_fee_bps as u64);
+        }
+        fee_bps
     }
 }
 
 #[account]
 pub struct LiquidityProvider {
     pub shares: u64, // The number of shares this provider holds in the liquidity pool ( didnt add to contract now )
+    pub fee_debt: u128, // Part of the pool fee accumulator already settled for these shares
+    pub shares_seconds: u128, // Shares held integrated over time, for time-weighted LP rewards
+    pub shares_updated_ts: i64, // Time `shares_seconds` was last brought up to date
 }
 
 impl LiquidityProvider {
     pub const SEED_PREFIX: &'static str = "LiqudityProvider"; // Prefix for generating PDAs
 
-    // Discriminator (8) + f64 (8)
+    // Discriminator (8) + u64 (8) + u128 (16) + shares seconds (16) + shares updated (8)
+    pub const ACCOUNT_SIZE: usize = 8 + 8 + 16 + 16 + 8;
+
+    // Adds the current shares held since the last update to `shares_seconds`, call before the shares change
+    pub fn accrue_shares_seconds(&mut self, now: i64) -> Result<()> {
+        let elapsed = now.saturating_sub(self.shares_updated_ts).max(0) as u128;
+        self.shares_seconds = self
+            .shares_seconds
+            .checked_add(self.shares as u128 * elapsed)
+            .ok_or(CustomError::OverflowOrUnderflowOccurred)?;
+        self.shares_updated_ts = now;
+        Ok(())
+    }
+}
+
+#[account]
+pub struct UserState {
+    pub last_buy_ts: i64, // Timestamp of the user's last buy from the pool
+    pub last_buy_slot: u64, // Slot of the user's last buy from the pool
+    pub buy_volume: u64, // Lamports the user spent on buys during the curve phase, fee included, net of sells
+    pub airdrop_claimed: u64, // Airdrop tokens the user already claimed
+    pub holder_tokens: u64, // Tokens the curve paid the user net of what it sold back, see `count_holder`
+    pub counted_as_holder: bool, // Whether the user is in the pool's holder count
+}
+
+impl UserState {
+    pub const SEED_PREFIX: &'static str = "user_state";
+
+    // Discriminator (8) + i64 (8) + u64 (8) + buy volume (8) + airdrop claimed (8) + holder tokens (8)
+    // + counted as holder (1)
+    pub const ACCOUNT_SIZE: usize = 8 + 8 + 8 + 8 + 8 + 8 + 1;
+
+    // Sell guards tied to the seller's last buy, shared with `simulate_swap`
+    pub fn check_sell_allowed(&self, min_hold_secs: u32, slot: u64, now: i64) -> Result<()> {
+        // A buy and sell landing in one slot is the shape of a sandwich
+        if self.last_buy_slot == slot {
+            return err!(CustomError::SameSlotTradeBlocked);
+        }
+
+        if now < self.last_buy_ts + min_hold_secs as i64 {
+            return err!(CustomError::MinHoldTimeNotMet);
+        }
+        Ok(())
+    }
+}
+
+#[account]
+pub struct AuctionCommitment {
+    pub amount: u64, // Lamports the user committed to the pool's launch auction
+}
+
+impl AuctionCommitment {
+    pub const SEED_PREFIX: &'static str = "auction_commitment";
+
+    // Discriminator (8) + u64 (8)
     pub const ACCOUNT_SIZE: usize = 8 + 8;
 }
 
+#[account]
+pub struct TestClock {
+    pub unix_timestamp: i64, // Time instructions given this account read instead of the cluster clock
+}
+
+impl TestClock {
+    pub const SEED: &'static str = "test_clock";
+
+    // Discriminator (8) + i64 (8)
+    pub const ACCOUNT_SIZE: usize = 8 + 8;
+}
+
+#[account]
+pub struct WhitelistEntry {}
+
+impl WhitelistEntry {
+    pub const SEED_PREFIX: &'static str = "whitelist";
+
+    // Discriminator (8), the entry existing is what whitelists the user
+    pub const ACCOUNT_SIZE: usize = 8;
+}
+
+#[account]
+pub struct CreatorPools {
+    pub pools: u32, // Pools created so far by the creator
+}
+
+impl CreatorPools {
+    pub const SEED_PREFIX: &'static str = "creator_pools";
+
+    // Discriminator (8) + u32 (4)
+    pub const ACCOUNT_SIZE: usize = 8 + 4;
+}
+
+#[account]
+pub struct CreatorVesting {
+    pub pool: Pubkey,         // Pool the vested allocation belongs to
+    pub beneficiary: Pubkey,  // Creator receiving the vested tokens
+    pub total: u64,           // Tokens locked in the vesting vault
+    pub claimed: u64,         // Tokens already released
+    pub start_ts: i64,        // Vesting start
+    pub cliff_secs: u32,      // Nothing vests before start + cliff
+    pub duration_secs: u32,   // Everything is vested at start + duration
+    pub bump: u8,
+}
+
+impl CreatorVesting {
+    pub const SEED_PREFIX: &'static str = "creator_vesting";
+
+    // Discriminator (8) + Pubkey (32) + Pubkey (32) + u64 (8) + u64 (8) + i64 (8) + u32 (4) + u32 (4) + u8 (1)
+    pub const ACCOUNT_SIZE: usize = 8 + 32 + 32 + 8 + 8 + 8 + 4 + 4 + 1;
+
+    // Tokens vested at `now`, linear from the start once the cliff has passed
+    pub fn vested_amount(&self, now: i64) -> u64 {
+        let elapsed = now.saturating_sub(self.start_ts).max(0) as u64;
+        if elapsed < self.cliff_secs as u64 {
+            return 0;
+        }
+        if elapsed >= self.duration_secs as u64 {
+            return self.total;
+        }
+        (self.total as u128 * elapsed as u128 / self.duration_secs as u128) as u64
+    }
+}
+
 #[account]
 pub struct LiquidityPool {
     pub creator: Pubkey,    // Public key of the pool creator
@@ -42,18 +445,80 @@ pub struct LiquidityPool {
     pub reserve_token: u64, // Reserve amount of token in the pool
     pub reserve_sol: u64,   // Reserve amount of sol_token in the pool
     pub bump: u8,           // Nonce for the program-derived address
+    pub lp_count: u32,      // Number of liquidity providers that funded the pool
+    pub launch_sell_fee_bps: u16,    // Sell fee right after launch, decaying to the normal fee
+    pub launch_fee_window_secs: u32, // Seconds over which the launch sell fee decays
+    pub trade_start_ts: i64,         // Timestamp trading started ( liquidity was added )
+    pub fee_sol_accrued: u64,        // Fees held in the sol vault on top of reserve_sol
+    pub fee_token_accrued: u64,      // Fees held in the pool token account on top of reserve_token
+    pub completed: bool,             // Curve reached graduation, trading is closed
+    pub total_shares: u64,           // Sum of all liquidity provider shares
+    pub acc_fee_per_share: u128,     // Fees earned per share, scaled by ACC_FEE_PRECISION
+    pub virtual_sol_reserve: u64,    // Lamports the curve prices as already paid in, shifting the starting price
+    pub sol_raised: u64,             // Real SOL bought into the curve, net of sells
+    pub reserved_for_migration: u64, // Part of reserve_token the curve never sells, kept for migration
+    pub fee_dust: u64,               // Swap fee remainder below one lamport, in 1 / FEE_BPS_DENOMINATOR lamports
+    pub token_decimals: u8,          // Decimals of the pool token, cached at creation
+    pub post_swap_program: Option<Pubkey>, // Program the creator wants called after every swap
+    pub locked: bool,                // Set while the post-swap hook runs, blocks reentrant swaps
+    pub current_slot: u64,           // Slot of the latest swap
+    pub swaps_this_slot: u16,        // Swaps executed in `current_slot`
+    pub creator_fee_accrued: u64,    // Creator share of the swap fees held in the sol vault until claimed
+    pub metadata_uri: String,        // Off-chain JSON metadata of the pool, at most MAX_METADATA_URI_LEN bytes
+    pub total_buy_volume: u64,       // Lamports spent on buys during the curve phase, fee included, net of sells
+    pub airdrop_mint: Pubkey,        // Token airdropped to buyers after graduation ( default key means unset )
+    pub airdrop_claimed: u64,        // Airdrop tokens already claimed
+    pub graduation_target: u64,      // Graduation price of this pool overriding the configured one ( 0 uses the configuration )
+    pub whitelist_phase_end_ts: i64, // Only whitelisted users can buy before this timestamp ( 0 means no whitelist phase )
+    pub auction_end_ts: i64,         // Launch auction commitments close at this timestamp ( 0 means no auction )
+    pub auction_sol: u64,            // Lamports committed to the launch auction, held in the sol vault until settled
+    pub auction_tokens: u64,         // Tokens the settled auction bought for its committers, held until claimed
+    pub auction_settled: bool,       // Launch auction was filled at its clearing price
+    pub fees_accrued_window: u64,    // Swap fees earned by the LPs since `window_start_ts`
+    pub window_start_ts: i64,        // Start of the current LP fee window
+    pub volatility_accumulator: u64, // Recent swap price moves in basis points, decaying over the volatility window
+    pub volatility_updated_ts: i64,  // Time of the swap that last added to `volatility_accumulator`
+    pub post_graduation_volume: u64, // Lamports traded since the curve completed, bounded by the post-graduation cap
+    pub total_fees_collected: u128,  // Lifetime swap fees in lamports, every share included
+    pub protocol_seed_sol: u64,      // Lamports the protocol treasury seeded at creation, returned to it by `return_protocol_seed`
+    pub holder_count: u32,           // Wallets holding tokens bought from the curve, see `count_holder`
+    pub auction_sol_claimed: u64,    // Committed lamports whose commitment was claimed or refunded
+    pub post_graduation_trade_cap: u64, // Configured post-graduation cap at the time the pool graduated
+    pub protocol_shares: u64,        // Shares credited for the protocol seed, held by the pool for the treasury
+    pub graduated_ts: i64,           // Time the curve completed, starts the migration time lock ( 0 before graduation )
 }
 
 impl LiquidityPool {
     pub const POOL_SEED_PREFIX: &'static str = "liquidity_pool";
     pub const SOL_VAULT_PREFIX: &'static str = "liquidity_sol_vault";
 
+    pub const MAX_METADATA_URI_LEN: usize = 128;
+
     // Discriminator (8) + Pubkey (32) + Pubkey (32) + totalsupply (8)
-    // + reserve one (8) + reserve two (8) + Bump (1)
-    pub const ACCOUNT_SIZE: usize = 8 + 32 + 32 + 8 + 8 + 8 + 1;
+    // + reserve one (8) + reserve two (8) + Bump (1) + lp count (4)
+    // + launch sell fee (2) + launch fee window (4) + trade start (8) + accrued fees (8 + 8) + completed (1)
+    // + total shares (8) + fee accumulator (16) + virtual sol reserve (8) + sol raised (8) + migration reserve (8)
+    // + fee dust (8) + token decimals (1) + post swap program (1 + 32) + locked (1) + current slot (8) + swaps this slot (2)
+    // + creator fees (8) + metadata uri (4 + MAX_METADATA_URI_LEN) + buy volume (8) + airdrop mint (32)
+    // + airdrop claimed (8) + graduation target (8) + whitelist phase end (8) + auction end (8)
+    // + auction sol (8) + auction tokens (8) + auction settled (1) + fees accrued window (8) + window start (8)
+    // + volatility accumulator (8) + volatility updated (8) + post graduation volume (8) + total fees collected (16)
+    // + protocol seed (8) + holder count (4) + auction sol claimed (8) + post graduation trade cap (8)
+    // + protocol shares (8) + graduated (8)
+    pub const ACCOUNT_SIZE: usize =
+        8 + 32 + 32 + 8 + 8 + 8 + 1 + 4 + 2 + 4 + 8 + 8 + 8 + 1 + 8 + 16 + 8 + 8 + 8 + 8 + 1 + 33 + 1 + 8 + 2 + 8
+            + 4 + Self::MAX_METADATA_URI_LEN + 8 + 32 + 8 + 8 + 8 + 8 + 8 + 8 + 1 + 8 + 8 + 8 + 8 + 8 + 16 + 8 + 4 + 8 + 8 + 8 + 8;
 
     // Constructor to initialize a LiquidityPool with two tokens and a bump for the PDA
-    pub fn new(creator: Pubkey, token: Pubkey, bump: u8) -> Self {
+    pub fn new(
+        creator: Pubkey,
+        token: Pubkey,
+        bump: u8,
+        launch_sell_fee_bps: u16,
+        launch_fee_window_secs: u32,
+        token_decimals: u8,
+        metadata_uri: String,
+    ) -> Self {
         Self {
             creator,
             token,
@@ -61,89 +526,885 @@ impl LiquidityPool {
             reserve_token: 0_u64,
             reserve_sol: 0_u64,
             bump,
+            lp_count: 0_u32,
+            launch_sell_fee_bps,
+            launch_fee_window_secs,
+            trade_start_ts: 0_i64,
+            fee_sol_accrued: 0_u64,
+            fee_token_accrued: 0_u64,
+            completed: false,
+            total_shares: 0_u64,
+            acc_fee_per_share: 0_u128,
+            virtual_sol_reserve: 0_u64,
+            sol_raised: 0_u64,
+            reserved_for_migration: 0_u64,
+            fee_dust: 0_u64,
+            token_decimals,
+            post_swap_program: None,
+            locked: false,
+            current_slot: 0,
+            swaps_this_slot: 0,
+            creator_fee_accrued: 0,
+            metadata_uri,
+            total_buy_volume: 0,
+            airdrop_mint: Pubkey::default(),
+            airdrop_claimed: 0,
+            graduation_target: 0,
+            whitelist_phase_end_ts: 0,
+            auction_end_ts: 0,
+            auction_sol: 0,
+            auction_tokens: 0,
+            auction_settled: false,
+            fees_accrued_window: 0,
+            window_start_ts: 0,
+            volatility_accumulator: 0,
+            volatility_updated_ts: 0,
+            post_graduation_volume: 0,
+            total_fees_collected: 0,
+            protocol_seed_sol: 0,
+            holder_count: 0,
+            auction_sol_claimed: 0,
+            post_graduation_trade_cap: 0,
+            protocol_shares: 0,
+            graduated_ts: 0,
+        }
+    }
+
+    // Base units in one whole token
+    pub fn decimals_factor(&self) -> f64 {
+        10_f64.powi(self.token_decimals as i32)
+    }
+
+    // Keeps a swap fee in the sol vault and spreads it over the current shares
+    pub fn accrue_sol_fee(&mut self, fee: u64) -> Result<()> {
+        self.fee_sol_accrued = self
+            .fee_sol_accrued
+            .checked_add(fee)
+            .ok_or(CustomError::OverflowOrUnderflowOccurred)?;
+        if self.total_shares > 0 {
+            self.acc_fee_per_share = self
+                .acc_fee_per_share
+                .checked_add(fee as u128 * ACC_FEE_PRECISION / self.total_shares as u128)
+                .ok_or(CustomError::OverflowOrUnderflowOccurred)?;
+        }
+        Ok(())
+    }
+
+    // Liquidity shares for `tokens` base units, scaled to SHARE_DECIMALS so one whole token
+    // is worth the same number of shares in every pool
+    pub fn shares_for(&self, tokens: u64) -> Result<u64> {
+        let shares = tokens as u128 * 10_u128.pow(SHARE_DECIMALS as u32) / 10_u128.pow(self.token_decimals as u32);
+        u64::try_from(shares).map_err(|_| error!(CustomError::FailedToAllocateShares))
+    }
+
+    // Books a swap fee: the creator share is held for the creator, the protocol share is routed to the
+    // configured fee recipients by weight with the rounding remainder going to the liquidity providers,
+    // or into the SOL reserve with auto-compounding. Returns the referral share and each recipient's share
+    // for the caller to pay out
+    pub fn accrue_swap_fee(
+        &mut self,
+        config: &CurveConfiguration,
+        fee: u64,
+        has_referrer: bool,
+        now: i64,
+    ) -> Result<(u64, Vec<u64>)> {
+        self.total_fees_collected = self.total_fees_collected.saturating_add(fee as u128);
+        let referral_share_bps = if has_referrer { config.referral_fee_share_bps } else { 0 };
+        let (protocol_fee, creator_fee, referral_fee) =
+            split_fee(fee, config.creator_fee_share_bps, referral_share_bps)?;
+
+        let routed = config
+            .fee_recipients
+            .iter()
+            .map(|route| calculate_fee(protocol_fee, route.weight_bps as u64))
+            .collect::<Result<Vec<u64>>>()?;
+        let pool_fee = protocol_fee - routed.iter().sum::<u64>();
+        self.record_window_fee(pool_fee, now);
+        if config.auto_compound {
+            self.reserve_sol = self
+                .reserve_sol
+                .checked_add(pool_fee)
+                .ok_or(CustomError::OverflowOrUnderflowOccurred)?;
+        } else {
+            self.accrue_sol_fee(pool_fee)?;
+        }
+
+        self.creator_fee_accrued = self
+            .creator_fee_accrued
+            .checked_add(creator_fee)
+            .ok_or(CustomError::OverflowOrUnderflowOccurred)?;
+        Ok((referral_fee, routed))
+    }
+
+    // Counts an LP fee toward the APR window, restarting the window once it spans LP_APR_WINDOW_SECS
+    fn record_window_fee(&mut self, fee: u64, now: i64) {
+        if now.saturating_sub(self.window_start_ts) >= LP_APR_WINDOW_SECS {
+            self.fees_accrued_window = 0;
+            self.window_start_ts = now;
+        }
+        self.fees_accrued_window = self.fees_accrued_window.saturating_add(fee);
+    }
+
+    // Seconds the LP APR quote annualizes over, the window so far but never less than LP_APR_MIN_WINDOW_SECS
+    pub fn lp_apr_window_secs(&self, now: i64) -> i64 {
+        now.saturating_sub(self.window_start_ts).max(LP_APR_MIN_WINDOW_SECS)
+    }
+
+    // LP fee window annualized against the SOL reserve, in basis points. An empty reserve reports zero
+    pub fn lp_apr_bps(&self, now: i64) -> u64 {
+        if self.reserve_sol == 0 {
+            return 0;
+        }
+        let apr = self.fees_accrued_window as u128 * SECONDS_PER_YEAR as u128 * FEE_BPS_DENOMINATOR as u128
+            / (self.lp_apr_window_secs(now) as u128 * self.reserve_sol as u128);
+        apr.min(u64::MAX as u128) as u64
+    }
+
+    // Airdrop tokens owed for `buy_volume` out of everything funded so far, the vault balance plus
+    // what was already claimed, so later top-ups are shared the same way
+    pub fn airdrop_share(&self, buy_volume: u64, vault_balance: u64) -> Result<u64> {
+        if self.total_buy_volume == 0 {
+            return Ok(0);
+        }
+        let funded = vault_balance as u128 + self.airdrop_claimed as u128;
+        u64::try_from(funded * buy_volume as u128 / self.total_buy_volume as u128)
+            .map_err(|_| error!(CustomError::OverflowOrUnderflowOccurred))
+    }
+
+    // Accumulator value owed to `shares` at the current fee level
+    pub fn fee_debt_for(&self, shares: u64) -> Result<u128> {
+        (shares as u128)
+            .checked_mul(self.acc_fee_per_share)
+            .map(|debt| debt / ACC_FEE_PRECISION)
+            .ok_or(CustomError::OverflowOrUnderflowOccurred.into())
+    }
+
+    // Fees a provider's shares earned since its last settlement, as the accumulator books them
+    pub fn earned_lp_fees(&self, provider: &LiquidityProvider) -> Result<u128> {
+        Ok(self
+            .fee_debt_for(provider.shares)?
+            .saturating_sub(provider.fee_debt))
+    }
+
+    // Fees earned by a provider since its last settlement, never more than the vault holds as fees
+    pub fn pending_lp_fees(&self, provider: &LiquidityProvider) -> Result<u64> {
+        Ok(self.earned_lp_fees(provider)?.min(self.fee_sol_accrued as u128) as u64)
+    }
+
+    // Curve position ( in millions of tokens ) the virtual SOL reserve stands for
+    pub fn virtual_amount(&self) -> f64 {
+        (PROPORTION as f64 * self.virtual_sol_reserve as f64 / PRICE_SCALE as f64).sqrt()
+    }
+
+    // Curve position in millions of tokens, tokens bought from the pool on top of the virtual position
+    pub fn bought_amount(&self) -> f64 {
+        (self.total_supply as f64 - self.reserve_token as f64) / 1_000_000.0 / self.decimals_factor() + self.virtual_amount()
+    }
+
+    // Marginal price per million tokens ( one curve unit ) in SOL scaled by PRICE_SCALE, the curve price
+    // itself is 2 * position / PROPORTION SOL
+    pub fn spot_price(&self) -> u64 {
+        self.price_at_supply(self.total_supply.saturating_sub(self.reserve_token)) as u64
+    }
+
+    // Price to compare pools by whatever their curve, lamports per whole token scaled by PRICE_SCALE.
+    // The curve's marginal price, `spot_price` counts per million whole tokens
+    pub fn fair_price(&self) -> u128 {
+        self.price_at_supply(self.total_supply.saturating_sub(self.reserve_token)) * PRICE_SCALE as u128 / 1_000_000
+    }
+
+    // Marginal price, in the unit of `spot_price`, once `supply` tokens were sold from the curve.
+    // Depends only on the pool's curve parameters so clients can chart the curve offline
+    pub fn price_at_supply(&self, supply: u64) -> u128 {
+        let position = supply as f64 / 1_000_000.0 / self.decimals_factor() + self.virtual_amount();
+        (position * 2.0 / PROPORTION as f64 * PRICE_SCALE as f64) as u128
+    }
+
+    // Shortfall of `amount_out` against filling the whole swap at the pre-trade spot price, in basis points.
+    // A curve with nothing bought yet has no price to compare against and reports no impact
+    pub fn price_impact_bps(&self, direction: u8, amount_in: u64, amount_out: u64) -> u64 {
+        let spot_price = self.spot_price();
+        if spot_price == 0 {
+            return 0;
+        }
+        let expected = if direction == SWAP_DIRECTION_BUY {
+            amount_in as f64 * (1_000_000.0 * self.decimals_factor()) / spot_price as f64
+        } else {
+            amount_in as f64 / (1_000_000.0 * self.decimals_factor()) * spot_price as f64
+        };
+        if expected <= amount_out as f64 {
+            return 0;
+        }
+        ((expected - amount_out as f64) * 10_000.0 / expected) as u64
+    }
+
+    // Rejects a swap moving the price further than the configured backstop
+    pub fn check_price_impact(
+        &self,
+        config: &CurveConfiguration,
+        direction: u8,
+        amount_in: u64,
+        amount_out: u64,
+    ) -> Result<()> {
+        if config.max_slippage_bps == 0 {
+            return Ok(());
+        }
+        let impact_bps = self.price_impact_bps(direction, amount_in, amount_out);
+        if impact_bps > config.max_slippage_bps as u64 {
+            return err!(CustomError::SlippageExceeded);
+        }
+        Ok(())
+    }
+
+    // Lamports the creator is paid out of the raised SOL when the curve graduates
+    pub fn graduation_creator_payout(&self, config: &CurveConfiguration) -> u64 {
+        config
+            .creator_graduation_payout
+            .min(self.sol_raised)
+            .min(self.reserve_sol)
+    }
+
+    // Books the graduation the current instruction caused: the creator payout leaves the reserves and the
+    // post-graduation cap in force is kept, later configuration changes do not reopen or close the pool.
+    // Returns the payout, `complete_graduation` transfers it
+    pub fn book_graduation(&mut self, config: &CurveConfiguration) -> Result<u64> {
+        self.post_graduation_trade_cap = config.post_graduation_trade_cap;
+        let creator_payout = self.graduation_creator_payout(config);
+        self.reserve_sol = self
+            .reserve_sol
+            .checked_sub(creator_payout)
+            .ok_or(CustomError::OverflowOrUnderflowOccurred)?;
+        self.sol_raised = self
+            .sol_raised
+            .checked_sub(creator_payout)
+            .ok_or(CustomError::OverflowOrUnderflowOccurred)?;
+        Ok(creator_payout)
+    }
+
+    // SOL a buyer has to pay ( fee included ) and tokens left to sell before the spot price reaches
+    // the graduation price, zero once graduated or with graduation disabled
+    pub fn graduation_gap(&self, config: &CurveConfiguration, now: i64) -> Result<(u64, u64)> {
+        let graduation_price = self.graduation_price(config);
+        if self.completed || graduation_price == 0 {
+            return Ok((0, 0));
+        }
+        self.price_gap(config, now, graduation_price)
+    }
+
+    // Graduation price of this pool, its own target when one was set before trading or the configured one
+    pub fn graduation_price(&self, config: &CurveConfiguration) -> u64 {
+        if self.graduation_target != 0 {
+            self.graduation_target
+        } else {
+            config.graduation_price
+        }
+    }
+
+    // Whether the pool opened with a launch auction that was not filled yet, the curve stays closed until then
+    pub fn auction_pending(&self) -> bool {
+        self.auction_end_ts != 0 && !self.auction_settled
+    }
+
+    // Whether a settled auction still has commitments to claim or refund, their lamports or tokens sit in the pool
+    pub fn auction_unclaimed(&self) -> bool {
+        self.auction_settled && self.auction_sol_claimed < self.auction_sol
+    }
+
+    // Lamports the launch auction can take in, the cost of every token the curve can sell with the fee on top
+    pub fn auction_capacity(&self, config: &CurveConfiguration, now: i64) -> Result<u64> {
+        let cost = self.buy_amount_in(self.available_token())?;
+        let fee_bps = self.trade_fee_bps(config, now, cost);
+        if fee_bps >= FEE_BPS_DENOMINATOR {
+            return err!(CustomError::InvalidFee);
+        }
+        Ok((cost as u128 * FEE_BPS_DENOMINATOR as u128 / (FEE_BPS_DENOMINATOR - fee_bps) as u128) as u64)
+    }
+
+    // Counts a swap moving `sol_amount` lamports against the post-graduation cap, trades before graduation are free
+    pub fn record_post_graduation_trade(&mut self, sol_amount: u64) -> Result<()> {
+        if !self.completed {
+            return Ok(());
+        }
+        let volume = self
+            .post_graduation_volume
+            .checked_add(sol_amount)
+            .ok_or(CustomError::OverflowOrUnderflowOccurred)?;
+        if volume > self.post_graduation_trade_cap {
+            return err!(CustomError::PostGraduationCapReached);
+        }
+        self.post_graduation_volume = volume;
+        Ok(())
+    }
+
+    // Swap fee for a trade worth `sol_amount` lamports, the dynamic fee when one is configured
+    pub fn trade_fee_bps(&self, config: &CurveConfiguration, now: i64, sol_amount: u64) -> u64 {
+        if config.dynamic_fee_max_bps == 0 {
+            return config.trade_fee_bps(now, sol_amount);
+        }
+        let min_bps = config.dynamic_fee_min_bps as u64;
+        let max_bps = config.dynamic_fee_max_bps as u64;
+        let volatility = self.volatility(config, now).min(VOLATILITY_FULL_FEE_BPS);
+        min_bps + (max_bps - min_bps) * volatility / VOLATILITY_FULL_FEE_BPS
+    }
+
+    // Recorded volatility at `now`, decayed linearly to zero over the volatility window since the last swap
+    pub fn volatility(&self, config: &CurveConfiguration, now: i64) -> u64 {
+        let window = config.volatility_window_secs as u128;
+        let elapsed = now.saturating_sub(self.volatility_updated_ts).max(0) as u128;
+        if elapsed >= window {
+            return 0;
+        }
+        (self.volatility_accumulator as u128 * (window - elapsed) / window) as u64
+    }
+
+    // Adds the price move of a swap, in basis points of the spot price before it, to the decayed volatility
+    pub fn record_volatility(&mut self, config: &CurveConfiguration, price_before: u64, now: i64) {
+        let move_bps = if price_before == 0 {
+            0
+        } else {
+            (price_before.abs_diff(self.spot_price()) as u128 * FEE_BPS_DENOMINATOR as u128 / price_before as u128)
+                .min(u64::MAX as u128) as u64
+        };
+        self.volatility_accumulator = self.volatility(config, now).saturating_add(move_bps);
+        self.volatility_updated_ts = now;
+    }
+
+    // Whether anything was ever bought from the curve, every sell follows a buy
+    pub fn has_traded(&self) -> bool {
+        self.total_buy_volume != 0
+    }
+
+    // SOL a buyer has to pay ( fee included ) and tokens the curve sells before the spot price reaches
+    // `target_price`, zero once the spot price is there
+    pub fn price_gap(&self, config: &CurveConfiguration, now: i64, target_price: u64) -> Result<(u64, u64)> {
+        if self.spot_price() >= target_price {
+            return Ok((0, 0));
+        }
+        let bought_amount = self.bought_amount();
+        let target_amount = target_price as f64 * PROPORTION as f64 / 2.0 / PRICE_SCALE as f64;
+
+        let sol_in = ((target_amount.powi(2) - bought_amount.powi(2)) / PROPORTION as f64 * PRICE_SCALE as f64).ceil() as u64;
+        let tokens = ((target_amount - bought_amount) * 1_000_000.0 * self.decimals_factor()) as u64;
+        Ok((self.buy_amount_for(config, now, sol_in)?, tokens.min(self.available_token())))
+    }
+
+    // Lamports a buy has to pay ( fee included ) for `sol_in` of them to reach the curve, under the fee
+    // `fill_buy` charges that buy. A buy of at least the large trade threshold pays the large trade fee,
+    // never more than the normal one, so past the threshold the answer is the cheaper of the two
+    pub fn buy_amount_for(&self, config: &CurveConfiguration, now: i64, sol_in: u64) -> Result<u64> {
+        let amount = self.amount_with_fee(sol_in, self.trade_fee_bps(config, now, 0))?;
+        let threshold = config.large_trade_threshold;
+        if threshold != 0 && amount >= threshold {
+            let large = self.amount_with_fee(sol_in, self.trade_fee_bps(config, now, amount))?;
+            return Ok(large.max(threshold).min(amount));
+        }
+        Ok(amount)
+    }
+
+    // Smallest `amount` leaving at least `sol_in` after `calculate_fee_with_dust` takes `fee_bps` of it
+    // on top of the carried fee remainder: floor((amount * fee_bps + dust) / D) <= amount - sol_in
+    fn amount_with_fee(&self, sol_in: u64, fee_bps: u64) -> Result<u64> {
+        if fee_bps >= FEE_BPS_DENOMINATOR {
+            return err!(CustomError::InvalidFee);
+        }
+        let denominator = FEE_BPS_DENOMINATOR as u128;
+        let needed = (sol_in as u128 * denominator + self.fee_dust as u128 + 1).saturating_sub(denominator);
+        let amount = needed.div_ceil(denominator - fee_bps as u128).max(sol_in as u128);
+        u64::try_from(amount).map_err(|_| error!(CustomError::OverflowOrUnderflowOccurred))
+    }
+
+    // Completes the curve once the spot price reaches the graduation price or the SOL raised reaches the
+    // graduation SOL target, returns true when this call flipped it. A pool short of holders keeps trading,
+    // `check_holders` decides which swaps may still land on it
+    pub fn check_graduation(&mut self, config: &CurveConfiguration) -> bool {
+        if self.completed || !self.has_enough_holders(config) || !self.graduation_reached(config) {
+            return false;
+        }
+        self.completed = true;
+        true
+    }
+
+    // Whether the curve met a graduation condition, the holder minimum aside
+    pub fn graduation_reached(&self, config: &CurveConfiguration) -> bool {
+        let graduation_price = self.graduation_price(config);
+        let price_reached = graduation_price != 0 && self.spot_price() >= graduation_price;
+        let sol_raised = config.graduation_sol_target != 0 && self.sol_raised >= config.graduation_sol_target;
+        let supply_sold = self.reserved_for_migration != 0 && self.available_token() == 0;
+        price_reached || sol_raised || supply_sold
+    }
+
+    pub fn has_enough_holders(&self, config: &CurveConfiguration) -> bool {
+        self.holder_count >= config.min_graduation_holders
+    }
+
+    // A buy leaving a graduated-but-short pool open only lands when it brought a new holder, existing
+    // holders cannot push it further alone. Failing the new holder's buy too would undo their count
+    // and leave the pool stuck short forever
+    pub fn check_holders(&self, config: &CurveConfiguration, new_holder: bool) -> Result<()> {
+        if !self.completed && !new_holder && !self.has_enough_holders(config) && self.graduation_reached(config) {
+            return err!(CustomError::InsufficientHolders);
+        }
+        Ok(())
+    }
+
+    // Holders are wallets, whatever token accounts the curve pays them into. A wallet is counted once, on
+    // the first tokens it takes from the curve, returns true when this call counted it
+    pub fn count_holder(&mut self, holder: &mut UserState) -> bool {
+        if holder.counted_as_holder {
+            return false;
+        }
+        holder.counted_as_holder = true;
+        self.holder_count = self.holder_count.saturating_add(1);
+        true
+    }
+
+    pub fn credit_holder(&mut self, holder: &mut UserState, tokens: u64) -> Result<()> {
+        holder.holder_tokens = holder
+            .holder_tokens
+            .checked_add(tokens)
+            .ok_or(CustomError::OverflowOrUnderflowOccurred)?;
+        Ok(())
+    }
+
+    // A wallet leaves the count once it sold back every token the curve paid it. Tokens moved outside the
+    // curve are not seen, moving them out and buying again keeps the wallet counted once
+    pub fn debit_holder(&mut self, holder: &mut UserState, tokens: u64) {
+        holder.holder_tokens = holder.holder_tokens.saturating_sub(tokens);
+        if holder.counted_as_holder && holder.holder_tokens == 0 {
+            holder.counted_as_holder = false;
+            self.holder_count = self.holder_count.saturating_sub(1);
         }
     }
+
+    // Tokens the curve can still sell, the migration reserve excluded
+    pub fn available_token(&self) -> u64 {
+        self.reserve_token.saturating_sub(self.reserved_for_migration)
+    }
+
+    // Sell fee at `now`, decaying linearly from the launch fee to the normal fee over the window
+    pub fn sell_fee_bps(&self, normal_fee_bps: u64, now: i64) -> u64 {
+        let launch_fee_bps = self.launch_sell_fee_bps as u64;
+        let window = self.launch_fee_window_secs as u64;
+        let elapsed = now.saturating_sub(self.trade_start_ts).max(0) as u64;
+
+        if window == 0 || elapsed >= window || launch_fee_bps <= normal_fee_bps {
+            return normal_fee_bps;
+        }
+
+        launch_fee_bps - (launch_fee_bps - normal_fee_bps) * elapsed / window
+    }
+
+    // Counts a swap against the per slot limit, the counter restarts on every new slot
+    pub fn record_swap(&mut self, max_swaps_per_slot: u16, slot: u64) -> Result<()> {
+        if slot != self.current_slot {
+            self.current_slot = slot;
+            self.swaps_this_slot = 0;
+        }
+        if max_swaps_per_slot != 0 && self.swaps_this_slot >= max_swaps_per_slot {
+            return err!(CustomError::SlotSwapLimitReached);
+        }
+        self.swaps_this_slot += 1;
+        Ok(())
+    }
+
+    // Only whitelisted users can buy before the whitelist phase ends, shared with `simulate_swap`
+    pub fn check_whitelisted(&self, whitelisted: bool, now: i64) -> Result<()> {
+        if !whitelisted && now < self.whitelist_phase_end_ts {
+            return err!(CustomError::NotWhitelisted);
+        }
+        Ok(())
+    }
+
+    // Guards every swap passes before its amounts are looked at, shared with `simulate_swap`
+    pub fn check_swap_open(&self, config: &CurveConfiguration, amount: u64, slot: u64, now: i64) -> Result<()> {
+        // A pool created but not funded yet, or emptied by remove_liquidity, has nothing to trade against,
+        // whatever the protocol seeded into its SOL reserve
+        if self.total_supply == 0 {
+            return err!(CustomError::PoolNotSeeded);
+        }
+
+        // A graduated curve keeps trading only within the post-graduation cap
+        if self.completed {
+            if self.post_graduation_trade_cap == 0 {
+                return err!(CustomError::CurveComplete);
+            }
+            if self.post_graduation_volume >= self.post_graduation_trade_cap {
+                return err!(CustomError::PostGraduationCapReached);
+            }
+        }
+
+        if self.locked {
+            return err!(CustomError::ReentrancyLocked);
+        }
+
+        if config.paused {
+            return err!(CustomError::ProgramPaused);
+        }
+
+        if self.auction_pending() {
+            return err!(CustomError::AuctionActive);
+        }
+
+        if config.max_swaps_per_slot != 0
+            && slot == self.current_slot
+            && self.swaps_this_slot >= config.max_swaps_per_slot
+        {
+            return err!(CustomError::SlotSwapLimitReached);
+        }
+
+        // `current_slot` is the slot of the pool's latest trade
+        if config.min_trade_slot_spacing != 0
+            && self.current_slot != 0
+            && slot < self.current_slot + config.min_trade_slot_spacing as u64
+        {
+            return err!(CustomError::TradeTooSoon);
+        }
+
+        if amount == 0 {
+            return err!(CustomError::InvalidAmount);
+        }
+
+        if !config.is_trading_open(now) {
+            return err!(CustomError::OutsideTradingWindow);
+        }
+        Ok(())
+    }
+
+    // Prices a buy of `amount` lamports ( fee included ) with every check the buy makes, without touching
+    // the pool. Returns the lamports swapped, the fee, the fee remainder to carry on and the tokens out
+    pub fn quote_buy(
+        &self,
+        bonding_configuration_account: &CurveConfiguration,
+        amount: u64,
+        now: i64,
+    ) -> Result<(u64, u64, u64, u64)> {
+        if amount < bonding_configuration_account.min_swap_amount {
+            return err!(CustomError::AmountBelowMinimum);
+        }
+
+        let fill_limit = self.buy_fill_limit(bonding_configuration_account);
+        let (amount_in, fee, fee_dust, amount_out) =
+            self.fill_buy(bonding_configuration_account, amount, now, fill_limit)?;
+
+        self.check_price_impact(bonding_configuration_account, SWAP_DIRECTION_BUY, amount_in, amount_out)?;
+
+        // A buy drains the token side, it has to leave at least the floor for sellers to buy back from
+        if self.reserve_token - amount_out < bonding_configuration_account.min_reserve_floor.token {
+            return err!(CustomError::ReserveFloorBreached);
+        }
+
+        self.check_price_cap(bonding_configuration_account, amount_out)?;
+        Ok((amount_in, fee, fee_dust, amount_out))
+    }
+
+    // A curve at its price cap has graduated or gone runaway, either way it takes no more buys
+    pub fn check_price_cap(&self, config: &CurveConfiguration, amount_out: u64) -> Result<()> {
+        let sold_after = self.total_supply.saturating_sub(self.reserve_token).saturating_add(amount_out);
+        if self.price_at_supply(sold_after) > config.price_cap() as u128 {
+            return err!(CustomError::PriceCapReached);
+        }
+        Ok(())
+    }
+
+    // Most tokens one buy can take, what the curve has left, or up to the graduation price with partial fills.
+    // A spot price already past a lowered graduation price is not capped, the buy completes the curve
+    pub fn buy_fill_limit(&self, config: &CurveConfiguration) -> u64 {
+        let graduation_tokens = self.graduation_tokens(config);
+        if config.graduation_partial_fill && graduation_tokens > 0 {
+            graduation_tokens.min(self.available_token())
+        } else {
+            self.available_token()
+        }
+    }
+
+    // Tokens the curve sells before the spot price reaches the graduation price, rounded up.
+    // Unlimited with graduation disabled
+    pub fn graduation_tokens(&self, config: &CurveConfiguration) -> u64 {
+        let graduation_price = self.graduation_price(config);
+        if graduation_price == 0 {
+            return u64::MAX;
+        }
+        let target_amount = graduation_price as f64 * PROPORTION as f64 / 2.0 / PRICE_SCALE as f64;
+        let target_sold = ((target_amount - self.virtual_amount()) * 1_000_000.0 * self.decimals_factor()).ceil();
+        (target_sold.max(0.0) as u64).saturating_sub(self.total_supply.saturating_sub(self.reserve_token))
+    }
+
+    // Curve side of a buy of `amount` lamports ( fee included ) taking at most `fill_limit` tokens, without
+    // the swap size guards. Returns the lamports swapped, the fee, the fee remainder to carry on and the tokens out
+    pub fn fill_buy(
+        &self,
+        bonding_configuration_account: &CurveConfiguration,
+        amount: u64,
+        now: i64,
+        fill_limit: u64,
+    ) -> Result<(u64, u64, u64, u64)> {
+        let fee_bps = self.trade_fee_bps(bonding_configuration_account, now, amount);
+        let (mut fee, mut fee_dust) = calculate_fee_with_dust(amount, fee_bps, self.fee_dust)?;
+        // A misconfigured fee must not leave nothing to swap
+        if fee >= amount {
+            return err!(CustomError::FeeExceedsInput);
+        }
+        let mut amount_in = amount - fee;
+        let mut amount_out = self.buy_amount_out(amount_in)?;
+
+        // The buy that exhausts the curve, or crosses the graduation price with partial fills, only gets
+        // the tokens up to there and pays the exact cost of them. The rest of the input is never taken
+        if amount_out > fill_limit {
+            amount_out = fill_limit;
+            amount_in = self.buy_amount_in(amount_out)?.min(amount_in);
+            (fee, fee_dust) = calculate_fee_with_dust(amount_in, fee_bps, self.fee_dust)?;
+        }
+
+        if amount_out == 0 {
+            return err!(CustomError::NotEnoughTokenInVault);
+        }
+        Ok((amount_in, fee, fee_dust, amount_out))
+    }
+
+    // Prices a sell of `amount` tokens with every check the sell makes, without touching the pool.
+    // Returns the lamports out before the fee, the fee and the fee remainder to carry on
+    pub fn quote_sell(
+        &self,
+        bonding_configuration_account: &CurveConfiguration,
+        amount: u64,
+        now: i64,
+    ) -> Result<(u64, u64, u64)> {
+        if self.reserve_token < amount {
+            return err!(CustomError::TokenAmountToSellTooBig);
+        }
+
+        // Only tokens the curve sold can be sold back, anything past that has no SOL behind it
+        let sold_token = self.total_supply.saturating_sub(self.reserve_token);
+        if amount > sold_token {
+            msg!("sell of {} tokens, only {} were sold by the curve", amount, sold_token);
+            return err!(CustomError::InsufficientReserves);
+        }
+
+        let amount_out = self.sell_amount_out(amount)?;
+
+        if self.reserve_sol < amount_out {
+            msg!("sell needs {} lamports, the pool holds {}", amount_out, self.reserve_sol);
+            return err!(CustomError::InsufficientReserves);
+        }
+
+        if amount_out < bonding_configuration_account.min_swap_amount {
+            return err!(CustomError::AmountBelowMinimum);
+        }
+
+        self.check_price_impact(bonding_configuration_account, SWAP_DIRECTION_SELL, amount, amount_out)?;
+
+        if self.reserve_sol - amount_out < bonding_configuration_account.min_reserve_floor.sol {
+            return err!(CustomError::ReserveFloorBreached);
+        }
+
+        let fee_bps = self.sell_trade_fee_bps(bonding_configuration_account, now, amount_out);
+        let (fee, fee_dust) = calculate_fee_with_dust(amount_out, fee_bps, self.fee_dust)?;
+        if fee != 0 && fee >= amount_out {
+            return err!(CustomError::FeeExceedsInput);
+        }
+        Ok((amount_out, fee, fee_dust))
+    }
+
+    // Extra sell fee growing linearly with the share of the SOL reserve a sell of `amount_out` lamports takes
+    pub fn dump_tax_bps(&self, dump_tax_max_bps: u16, amount_out: u64) -> u64 {
+        if dump_tax_max_bps == 0 || self.reserve_sol == 0 {
+            return 0;
+        }
+        let share = amount_out.min(self.reserve_sol) as u128;
+        (dump_tax_max_bps as u128 * share / self.reserve_sol as u128) as u64
+    }
+
+    // Full fee on a sell paying out `amount_out` lamports: launch decay on the trade fee plus the dump tax,
+    // never more than the whole output
+    pub fn sell_trade_fee_bps(&self, config: &CurveConfiguration, now: i64, amount_out: u64) -> u64 {
+        (self.sell_fee_bps(self.trade_fee_bps(config, now, amount_out), now)
+            + self.dump_tax_bps(config.dump_tax_max_bps, amount_out))
+        .min(FEE_BPS_DENOMINATOR)
+    }
+
+    // Tokens the curve pays out for `amount_in` lamports ( fees already taken )
+    pub fn buy_amount_out(&self, amount_in: u64) -> Result<u64> {
+        let bought_amount = self.bought_amount();
+
+        let root_val = (PROPORTION as f64 * amount_in as f64 / PRICE_SCALE as f64 + bought_amount * bought_amount).sqrt();
+
+        let amount_out_f64 = (root_val - bought_amount) * 1_000_000.0 * self.decimals_factor();
+
+        // Rounded down, the pool keeps the fraction
+        let amount_out = amount_out_f64.floor() as u64;
+        check_rounding_favors_pool(amount_out_f64, amount_out);
+        Ok(amount_out)
+    }
+
+    // Lamports the curve charges for `amount_out` tokens, rounded up in favor of the pool
+    pub fn buy_amount_in(&self, amount_out: u64) -> Result<u64> {
+        let bought_amount = self.bought_amount();
+        let bought_after = (self.total_supply as f64 - self.reserve_token as f64 + amount_out as f64)
+            / 1_000_000.0
+            / self.decimals_factor()
+            + self.virtual_amount();
+
+        let amount_in_f64 =
+            (bought_after * bought_after - bought_amount * bought_amount) / PROPORTION as f64 * PRICE_SCALE as f64;
+
+        Ok(amount_in_f64.ceil() as u64)
+    }
+
+    // Lamports the curve pays out for `amount` tokens ( before fees )
+    pub fn sell_amount_out(&self, amount: u64) -> Result<u64> {
+        let bought_amount = self.bought_amount();
+
+        let result_amount = (self.total_supply as f64 - self.reserve_token as f64 - amount as f64)
+            / 1_000_000.0
+            / self.decimals_factor()
+            + self.virtual_amount();
+
+        let amount_out_f64 =
+            (bought_amount * bought_amount - result_amount * result_amount) / PROPORTION as f64 * PRICE_SCALE as f64;
+
+        // Rounded down, the pool keeps the fraction
+        let amount_out = amount_out_f64.floor() as u64;
+        check_rounding_favors_pool(amount_out_f64, amount_out);
+        Ok(amount_out)
+    }
 }
 
 pub trait LiquidityPoolAccount<'info> {
     // Updates the token reserves in the liquidity pool
     fn update_reserves(&mut self, reserve_token: u64, reserve_sol: u64) -> Result<()>;
 
-    // Allows adding liquidity by depositing an amount of two tokens and getting back pool shares
+    // Allows adding liquidity by depositing an amount of two tokens and getting back pool shares,
+    // returns the amount of tokens the pool actually received
     fn add_liquidity(
         &mut self,
         token_accounts: (
-            &mut Account<'info, Mint>,
-            &mut Account<'info, TokenAccount>,
-            &mut Account<'info, TokenAccount>,
+            &mut InterfaceAccount<'info, Mint>,
+            &mut InterfaceAccount<'info, TokenAccount>,
+            &mut InterfaceAccount<'info, TokenAccount>,
         ),
         pool_sol_vault: &mut AccountInfo<'info>,
         authority: &Signer<'info>,
-        token_program: &Program<'info, Token>,
+        token_program: &Interface<'info, TokenInterface>,
         system_program: &Program<'info, System>,
-    ) -> Result<()>;
+    ) -> Result<u64>;
 
-    // Allows removing liquidity by burning pool shares and receiving back a proportionate amount of tokens
+    // Pays out both reserves, the fee balances excepted
+    #[allow(clippy::too_many_arguments)]
     fn remove_liquidity(
         &mut self,
+        bonding_configuration_account: &Account<'info, CurveConfiguration>,
         token_accounts: (
-            &mut Account<'info, Mint>,
-            &mut Account<'info, TokenAccount>,
-            &mut Account<'info, TokenAccount>,
+            &mut InterfaceAccount<'info, Mint>,
+            &mut InterfaceAccount<'info, TokenAccount>,
+            &mut InterfaceAccount<'info, TokenAccount>,
         ),
         pool_sol_account: &mut AccountInfo<'info>,
         authority: &Signer<'info>,
         bump: u8,
-        token_program: &Program<'info, Token>,
+        token_program: &Interface<'info, TokenInterface>,
         system_program: &Program<'info, System>,
     ) -> Result<()>;
 
+    // Returns the lamports the buyer paid ( fee included ) and the fee
+    #[allow(clippy::too_many_arguments)]
     fn buy(
         &mut self,
-        // bonding_configuration_account: &Account<'info, CurveConfiguration>,
+        bonding_configuration_account: &Account<'info, CurveConfiguration>,
         token_accounts: (
-            &mut Account<'info, Mint>,
-            &mut Account<'info, TokenAccount>,
-            &mut Account<'info, TokenAccount>,
+            &mut InterfaceAccount<'info, Mint>,
+            &mut InterfaceAccount<'info, TokenAccount>,
+            &mut InterfaceAccount<'info, TokenAccount>,
         ),
         pool_sol_vault: &mut AccountInfo<'info>,
         amount: u64,
+        bump: u8,
+        now: i64,
         authority: &Signer<'info>,
-        token_program: &Program<'info, Token>,
+        referrer: Option<&AccountInfo<'info>>,
+        fee_recipients: &[AccountInfo<'info>],
+        token_program: &Interface<'info, TokenInterface>,
         system_program: &Program<'info, System>,
-    ) -> Result<()>;
+    ) -> Result<(u64, u64)>;
 
+    // Returns the lamports the seller received ( after the fee ) and the fee
+    #[allow(clippy::too_many_arguments)]
     fn sell(
         &mut self,
-        // bonding_configuration_account: &Account<'info, CurveConfiguration>,
+        bonding_configuration_account: &Account<'info, CurveConfiguration>,
         token_accounts: (
-            &mut Account<'info, Mint>,
-            &mut Account<'info, TokenAccount>,
-            &mut Account<'info, TokenAccount>,
+            &mut InterfaceAccount<'info, Mint>,
+            &mut InterfaceAccount<'info, TokenAccount>,
+            &mut InterfaceAccount<'info, TokenAccount>,
         ),
         pool_sol_vault: &mut AccountInfo<'info>,
         amount: u64,
         bump: u8,
+        now: i64,
         authority: &Signer<'info>,
-        token_program: &Program<'info, Token>,
+        referrer: Option<&AccountInfo<'info>>,
+        fee_recipients: &[AccountInfo<'info>],
+        token_program: &Interface<'info, TokenInterface>,
         system_program: &Program<'info, System>,
-    ) -> Result<()>;
+    ) -> Result<(u64, u64)>;
+
+    // Pays out the graduation the current instruction caused and emits PoolCompleted. Every path that can
+    // complete the curve goes through here, returns the creator payout
+    fn complete_graduation(
+        &mut self,
+        bonding_configuration_account: &CurveConfiguration,
+        pool_sol_vault: &mut AccountInfo<'info>,
+        creator: &AccountInfo<'info>,
+        bump: u8,
+        system_program: &Program<'info, System>,
+        timestamp: i64,
+    ) -> Result<u64>;
+
+    // Sends the protocol seed still in the SOL reserve back to the treasury with the LP fees its shares
+    // earned, and burns those shares. Returns the lamports sent
+    fn return_protocol_seed(
+        &mut self,
+        pool_sol_vault: &mut AccountInfo<'info>,
+        protocol_treasury: &AccountInfo<'info>,
+        bump: u8,
+        system_program: &Program<'info, System>,
+        timestamp: i64,
+    ) -> Result<u64>;
 
     fn transfer_token_from_pool(
         &self,
-        from: &Account<'info, TokenAccount>,
-        to: &Account<'info, TokenAccount>,
+        mint: &InterfaceAccount<'info, Mint>,
+        from: &InterfaceAccount<'info, TokenAccount>,
+        to: &InterfaceAccount<'info, TokenAccount>,
         amount: u64,
-        token_program: &Program<'info, Token>,
+        token_program: &Interface<'info, TokenInterface>,
     ) -> Result<()>;
 
     fn transfer_token_to_pool(
         &self,
-        from: &Account<'info, TokenAccount>,
-        to: &Account<'info, TokenAccount>,
+        mint: &InterfaceAccount<'info, Mint>,
+        from: &InterfaceAccount<'info, TokenAccount>,
+        to: &InterfaceAccount<'info, TokenAccount>,
         amount: u64,
         authority: &Signer<'info>,
-        token_program: &Program<'info, Token>,
+        token_program: &Interface<'info, TokenInterface>,
+    ) -> Result<()>;
+
+    fn burn_token_from_pool(
+        &self,
+        mint: &InterfaceAccount<'info, Mint>,
+        from: &InterfaceAccount<'info, TokenAccount>,
+        amount: u64,
+        token_program: &Interface<'info, TokenInterface>,
+    ) -> Result<()>;
+
+    fn pay_fee_recipients(
+        &self,
+        bonding_configuration_account: &Account<'info, CurveConfiguration>,
+        fee_recipients: &[AccountInfo<'info>],
+        routed: &[u64],
+        pool_sol_vault: &mut AccountInfo<'info>,
+        bump: u8,
+        system_program: &Program<'info, System>,
     ) -> Result<()>;
 
     fn transfer_sol_to_pool(
@@ -157,7 +1418,7 @@ pub trait LiquidityPoolAccount<'info> {
     fn transfer_sol_from_pool(
         &self,
         from: &mut AccountInfo<'info>,
-        to: &Signer<'info>,
+        to: &AccountInfo<'info>,
         amount: u64,
         bump: u8,
         system_program: &Program<'info, System>,
@@ -174,16 +1435,18 @@ impl<'info> LiquidityPoolAccount<'info> for Account<'info, LiquidityPool> {
     fn add_liquidity(
         &mut self,
         token_accounts: (
-            &mut Account<'info, Mint>,
-            &mut Account<'info, TokenAccount>,
-            &mut Account<'info, TokenAccount>,
+            &mut InterfaceAccount<'info, Mint>,
+            &mut InterfaceAccount<'info, TokenAccount>,
+            &mut InterfaceAccount<'info, TokenAccount>,
         ),
         pool_sol_vault: &mut AccountInfo<'info>,
         authority: &Signer<'info>,
-        token_program: &Program<'info, Token>,
+        token_program: &Interface<'info, TokenInterface>,
         system_program: &Program<'info, System>,
-    ) -> Result<()> {
+    ) -> Result<u64> {
+        let balance_before = token_accounts.1.amount;
         self.transfer_token_to_pool(
+            token_accounts.0,
             token_accounts.2,
             token_accounts.1,
             token_accounts.0.supply,
@@ -191,208 +1454,368 @@ impl<'info> LiquidityPoolAccount<'info> for Account<'info, LiquidityPool> {
             token_program,
         )?;
 
+        // Mints that take a fee on transfer deliver less than the nominal amount
+        token_accounts.1.reload()?;
+        let received = token_accounts
+            .1
+            .amount
+            .checked_sub(balance_before)
+            .ok_or(CustomError::OverflowOrUnderflowOccurred)?;
+
         self.transfer_sol_to_pool(
             authority,
             pool_sol_vault,
             INITIAL_LAMPORTS_FOR_POOL,
             system_program,
         )?;
-        self.total_supply = 1_000_000_000_000_000_000;
-        self.update_reserves(token_accounts.0.supply, INITIAL_LAMPORTS_FOR_POOL)?;
-
-        Ok(())
+        // The first funding keeps the SOL the protocol seeded at creation in the reserve
+        let seeded_sol = if self.total_supply == 0 { self.reserve_sol } else { 0 };
+        // One billion whole tokens
+        self.total_supply = 10_u64
+            .checked_pow(self.token_decimals as u32)
+            .and_then(|unit| unit.checked_mul(1_000_000_000))
+            .ok_or(CustomError::OverflowOrUnderflowOccurred)?;
+        self.update_reserves(received, INITIAL_LAMPORTS_FOR_POOL + seeded_sol)?;
+        self.trade_start_ts = Clock::get()?.unix_timestamp;
+
+        Ok(received)
     }
 
     fn remove_liquidity(
         &mut self,
+        bonding_configuration_account: &Account<'info, CurveConfiguration>,
         token_accounts: (
-            &mut Account<'info, Mint>,
-            &mut Account<'info, TokenAccount>,
-            &mut Account<'info, TokenAccount>,
+            &mut InterfaceAccount<'info, Mint>,
+            &mut InterfaceAccount<'info, TokenAccount>,
+            &mut InterfaceAccount<'info, TokenAccount>,
         ),
         pool_sol_vault: &mut AccountInfo<'info>,
         authority: &Signer<'info>,
         bump: u8,
-        token_program: &Program<'info, Token>,
+        token_program: &Interface<'info, TokenInterface>,
         system_program: &Program<'info, System>,
     ) -> Result<()> {
+        // Tokens already owed as fees stay in the pool, the withdraw fee is added to them
+        let withdrawn = token_accounts
+            .1
+            .amount
+            .checked_sub(self.fee_token_accrued)
+            .ok_or(CustomError::OverflowOrUnderflowOccurred)?;
+        let fee = calculate_fee(withdrawn, bonding_configuration_account.withdraw_fee_bps as u64)?;
+
         self.transfer_token_from_pool(
+            token_accounts.0,
             token_accounts.1,
             token_accounts.2,
-            token_accounts.1.amount as u64,
+            withdrawn - fee,
             token_program,
         )?;
-        // let amount = self.to_account_info().lamports() - self.get_lamports();
-        let amount = pool_sol_vault.to_account_info().lamports() as u64;
+        self.fee_token_accrued = self
+            .fee_token_accrued
+            .checked_add(fee)
+            .ok_or(CustomError::OverflowOrUnderflowOccurred)?;
+
+        // LP and creator fees stay in the vault for their owners to claim, with rent exemption on top so the
+        // claims can empty them
+        let owed = self
+            .fee_sol_accrued
+            .checked_add(self.creator_fee_accrued)
+            .ok_or(CustomError::OverflowOrUnderflowOccurred)?;
+        let kept = if owed == 0 { 0 } else { owed.saturating_add(Rent::get()?.minimum_balance(0)) };
+        let amount = pool_sol_vault.lamports().saturating_sub(kept);
         self.transfer_sol_from_pool(pool_sol_vault, authority, amount, bump, system_program)?;
 
+        // The remover takes everything else in the vault (including any rounding dust left by the
+        // curve math), so the reserves always end at exactly zero
+        self.total_supply = 0;
+        self.reserved_for_migration = 0;
+        self.update_reserves(0, 0)?;
+
         Ok(())
     }
 
     fn buy(
         &mut self,
-        // _bonding_configuration_account: &Account<'info, CurveConfiguration>,
+        bonding_configuration_account: &Account<'info, CurveConfiguration>,
         token_accounts: (
-            &mut Account<'info, Mint>,
-            &mut Account<'info, TokenAccount>,
-            &mut Account<'info, TokenAccount>,
+            &mut InterfaceAccount<'info, Mint>,
+            &mut InterfaceAccount<'info, TokenAccount>,
+            &mut InterfaceAccount<'info, TokenAccount>,
         ),
         pool_sol_vault: &mut AccountInfo<'info>,
         amount: u64,
+        bump: u8,
+        now: i64,
         authority: &Signer<'info>,
-        token_program: &Program<'info, Token>,
+        referrer: Option<&AccountInfo<'info>>,
+        fee_recipients: &[AccountInfo<'info>],
+        token_program: &Interface<'info, TokenInterface>,
         system_program: &Program<'info, System>,
-    ) -> Result<()> {
-        if amount == 0 {
-            return err!(CustomError::InvalidAmount);
-        }
+    ) -> Result<(u64, u64)> {
+        let slot = Clock::get()?.slot;
+        self.check_swap_open(bonding_configuration_account, amount, slot, now)?;
+        self.record_swap(bonding_configuration_account.max_swaps_per_slot, slot)?;
 
         msg!("Trying to buy from the pool");
 
-        // let sol_reserve_before = self.reserve_sol;
-        // msg!("sol_reserve_before {}", sol_reserve_before);
-        // let sol_reserve_after = self.reserve_sol + amount;
-        // msg!("sol_reserve_after {}", sol_reserve_after);
-
-        // let sprt_token_before = ((sol_reserve_before as f64) * 2.0).sqrt();
-        // msg!("sprt_token_before {}", sprt_token_before);
-
-        // let sprt_token_after = ((sol_reserve_after as f64) * 2.0).sqrt();
-        // msg!("sprt_token_after {}", sprt_token_after);
-
-        // let amount_out =
-        //     ((sprt_token_after - sprt_token_before) * INITIAL_PRICE_DIVIDER as f64).round() as u64;
-        // msg!("amount_out {}", amount_out);
-        let bought_amount = (self.total_supply as f64 - self.reserve_token as f64) / 1_000_000.0 / 1_000_000_000.0;
-        msg!("bought_amount {}", bought_amount);
-
-        let root_val = (PROPORTION as f64 * amount as f64 / 1_000_000_000.0 + bought_amount * bought_amount).sqrt();
-        msg!("root_val {}", root_val);
-
-        let amount_out_f64 = (root_val - bought_amount as f64) * 1_000_000.0 * 1_000_000_000.0;
-        msg!("amount_out_f64 {}", amount_out_f64);
-
-        let amount_out = amount_out_f64.round() as u64;
-        msg!("amount_out {}", amount_out);
-
-        if amount_out > self.reserve_token {
-            return err!(CustomError::NotEnoughTokenInVault);
-        }
-
-        self.reserve_sol += amount;
+        let price_before = self.spot_price();
+        let (amount_in, fee, fee_dust, amount_out) =
+            self.quote_buy(bonding_configuration_account, amount, now)?;
+        self.record_post_graduation_trade(
+            amount_in
+                .checked_add(fee)
+                .ok_or(CustomError::OverflowOrUnderflowOccurred)?,
+        )?;
+        // The float curve can leave a buy filled exactly to the graduation price a hair under it
+        let filled_to_graduation = bonding_configuration_account.graduation_partial_fill
+            && amount_out == self.graduation_tokens(bonding_configuration_account);
+
+        self.reserve_sol = self
+            .reserve_sol
+            .checked_add(amount_in)
+            .ok_or(CustomError::OverflowOrUnderflowOccurred)?;
         self.reserve_token -= amount_out;
+        self.sol_raised = self
+            .sol_raised
+            .checked_add(amount_in)
+            .ok_or(CustomError::OverflowOrUnderflowOccurred)?;
+        let (referral_fee, routed) = self.accrue_swap_fee(bonding_configuration_account, fee, referrer.is_some(), now)?;
+        self.fee_dust = fee_dust;
+        self.record_volatility(bonding_configuration_account, price_before, now);
+
+        self.transfer_sol_to_pool(authority, pool_sol_vault, amount_in + fee, system_program)?;
+        if let Some(referrer) = referrer.filter(|_| referral_fee > 0) {
+            self.transfer_sol_from_pool(pool_sol_vault, referrer, referral_fee, bump, system_program)?;
+        }
+        self.pay_fee_recipients(
+            bonding_configuration_account,
+            fee_recipients,
+            &routed,
+            pool_sol_vault,
+            bump,
+            system_program,
+        )?;
 
-        self.transfer_sol_to_pool(authority, pool_sol_vault, amount, system_program)?;
-
+        // Burned tokens leave the pool like bought ones but never reach the buyer
+        let burned = bonding_configuration_account.buy_burn_amount(amount_out)?;
         self.transfer_token_from_pool(
+            token_accounts.0,
             token_accounts.1,
             token_accounts.2,
-            amount_out,
+            amount_out - burned,
             token_program,
         )?;
-        Ok(())
+        if burned > 0 {
+            self.burn_token_from_pool(token_accounts.0, token_accounts.1, burned, token_program)?;
+        }
+
+        if self.check_graduation(bonding_configuration_account) {
+            msg!("Curve completed at spot price {}", self.spot_price());
+        } else if filled_to_graduation && self.has_enough_holders(bonding_configuration_account) {
+            self.completed = true;
+            msg!("Curve completed by a fill to the graduation price");
+        }
+        Ok((amount_in + fee, fee))
     }
 
     fn sell(
         &mut self,
+        bonding_configuration_account: &Account<'info, CurveConfiguration>,
         token_accounts: (
-            &mut Account<'info, Mint>,
-            &mut Account<'info, TokenAccount>,
-            &mut Account<'info, TokenAccount>,
+            &mut InterfaceAccount<'info, Mint>,
+            &mut InterfaceAccount<'info, TokenAccount>,
+            &mut InterfaceAccount<'info, TokenAccount>,
         ),
         pool_sol_vault: &mut AccountInfo<'info>,
         amount: u64,
         bump: u8,
+        now: i64,
         authority: &Signer<'info>,
-        token_program: &Program<'info, Token>,
+        referrer: Option<&AccountInfo<'info>>,
+        fee_recipients: &[AccountInfo<'info>],
+        token_program: &Interface<'info, TokenInterface>,
         system_program: &Program<'info, System>,
-    ) -> Result<()> {
-        if amount == 0 {
-            return err!(CustomError::InvalidAmount);
-        }
+    ) -> Result<(u64, u64)> {
+        let slot = Clock::get()?.slot;
+        self.check_swap_open(bonding_configuration_account, amount, slot, now)?;
+        self.record_swap(bonding_configuration_account.max_swaps_per_slot, slot)?;
 
-        if self.reserve_token < amount {
-            return err!(CustomError::TokenAmountToSellTooBig);
+        let price_before = self.spot_price();
+        let balance_before = token_accounts.1.amount;
+        self.transfer_token_to_pool(
+            token_accounts.0,
+            token_accounts.2,
+            token_accounts.1,
+            amount,
+            authority,
+            token_program,
+        )?;
+        // Mints that take a fee on transfer deliver less than the seller sent, the curve prices what arrived
+        token_accounts.1.reload()?;
+        let received = token_accounts
+            .1
+            .amount
+            .checked_sub(balance_before)
+            .ok_or(CustomError::OverflowOrUnderflowOccurred)?;
+
+        let (amount_out, fee, fee_dust) =
+            self.quote_sell(bonding_configuration_account, received, now)?;
+        self.record_post_graduation_trade(amount_out)?;
+
+        self.reserve_token += received;
+        self.reserve_sol -= amount_out;
+        self.sol_raised = self.sol_raised.saturating_sub(amount_out);
+        let (referral_fee, routed) = self.accrue_swap_fee(bonding_configuration_account, fee, referrer.is_some(), now)?;
+        self.fee_dust = fee_dust;
+        self.record_volatility(bonding_configuration_account, price_before, now);
+
+        self.transfer_sol_from_pool(pool_sol_vault, authority, amount_out - fee, bump, system_program)?;
+        if let Some(referrer) = referrer.filter(|_| referral_fee > 0) {
+            self.transfer_sol_from_pool(pool_sol_vault, referrer, referral_fee, bump, system_program)?;
         }
+        self.pay_fee_recipients(
+            bonding_configuration_account,
+            fee_recipients,
+            &routed,
+            pool_sol_vault,
+            bump,
+            system_program,
+        )?;
 
-        // let amount_out: u64 = {
-        //     // Divide the values by 10^9 and convert to f64
-        //     let reserve_token_f64 = (self.reserve_token as f64) / 1_000_000_000.0;
-        //     let amount_f64 = (amount as f64) / 1_000_000_000.0;
-
-        //     msg!("reserve_token_f64: {}", reserve_token_f64);
-        //     msg!("amount_f64: {}", amount_f64);
-
-        //     let reserve_token_after_f64 = reserve_token_f64 + amount_f64;
-        //     let sold_after_f64 = self.total_supply as f64 - reserve_token_after_f64;
-        //     let sold_f64 = self.total_supply as f64 - reserve_token_f64;
-
-        //     msg!("reserve_token_after_f64: {}", reserve_token_after_f64);
-
-        //     let amount_dif = sold_f64 * sold_f64 - sold_after_f64 * sold_after_f64;
-        //     msg!("amount_dif: {}", amount_dif);
-
-        //     let amount_out_f64 = amount_dif / 2.0;
-        //     msg!("amount_out: {}", amount_out_f64);
-
-        //     // Convert the result back to u64 and multiply by 10^9
-        //     let result = (amount_out_f64 * 1_000_000_000.0 / INITIAL_PRICE_DIVIDER as f64).round() as u64;
-        //     msg!("result: {}", result);
-
-        //     // Handle potential overflow or underflow
-        //     if result > u64::MAX {
-        //         return err!(CustomError::OverflowOrUnderflowOccurred);
-        //     }
-        //     result
-        // };
-
-        let bought_amount = (self.total_supply as f64 - self.reserve_token as f64) / 1_000_000.0 / 1_000_000_000.0;
-        msg!("bought_amount: {}", bought_amount);
+        Ok((amount_out - fee, fee))
+    }
 
-        let result_amount =
-            (self.total_supply as f64 - self.reserve_token as f64 - amount as f64) / 1_000_000.0 / 1_000_000_000.0;
-        msg!("result_amount: {}", result_amount);
+    fn complete_graduation(
+        &mut self,
+        bonding_configuration_account: &CurveConfiguration,
+        pool_sol_vault: &mut AccountInfo<'info>,
+        creator: &AccountInfo<'info>,
+        bump: u8,
+        system_program: &Program<'info, System>,
+        timestamp: i64,
+    ) -> Result<u64> {
+        self.graduated_ts = timestamp;
+        let creator_payout = self.book_graduation(bonding_configuration_account)?;
+        if creator_payout > 0 {
+            self.transfer_sol_from_pool(pool_sol_vault, creator, creator_payout, bump, system_program)?;
+        }
 
-        let amount_out_f64 =
-            (bought_amount * bought_amount - result_amount * result_amount) / PROPORTION as f64 * 1_000_000_000.0;
-        msg!("amount_out_f64: {}", amount_out_f64);
+        emit!(PoolCompleted {
+            pool: self.key(),
+            creator: self.creator,
+            creator_payout,
+            reserve_token: self.reserve_token,
+            reserve_sol: self.reserve_sol,
+            timestamp,
+        });
+        Ok(creator_payout)
+    }
 
-        let amount_out = amount_out_f64.round() as u64;
-        msg!("amount_out: {}", amount_out);
+    fn return_protocol_seed(
+        &mut self,
+        pool_sol_vault: &mut AccountInfo<'info>,
+        protocol_treasury: &AccountInfo<'info>,
+        bump: u8,
+        system_program: &Program<'info, System>,
+        timestamp: i64,
+    ) -> Result<u64> {
+        // The seed shares were credited at creation, before any fee, so they carry no fee debt
+        let fees = u64::try_from(self.fee_debt_for(self.protocol_shares)?)
+            .map_err(|_| error!(CustomError::OverflowOrUnderflowOccurred))?
+            .min(self.fee_sol_accrued);
+        let seed = self.protocol_seed_sol.min(self.reserve_sol);
+        self.reserve_sol -= seed;
+        self.fee_sol_accrued -= fees;
+        self.total_shares = self
+            .total_shares
+            .checked_sub(self.protocol_shares)
+            .ok_or(CustomError::FailedToDeallocateShares)?;
+        if self.protocol_shares > 0 {
+            self.lp_count = self.lp_count.saturating_sub(1);
+        }
+        self.protocol_shares = 0;
+        self.protocol_seed_sol = 0;
 
-        if self.reserve_sol < amount_out {
-            return err!(CustomError::NotEnoughSolInVault);
+        let amount = seed + fees;
+        if amount > 0 {
+            self.transfer_sol_from_pool(pool_sol_vault, protocol_treasury, amount, bump, system_program)?;
         }
+        emit!(ProtocolSeedReturned {
+            pool: self.key(),
+            seed,
+            fees,
+            timestamp,
+        });
+        Ok(amount)
+    }
 
-        self.transfer_token_to_pool(
-            token_accounts.2,
-            token_accounts.1,
-            amount as u64,
-            authority,
-            token_program,
+    fn transfer_token_from_pool(
+        &self,
+        mint: &InterfaceAccount<'info, Mint>,
+        from: &InterfaceAccount<'info, TokenAccount>,
+        to: &InterfaceAccount<'info, TokenAccount>,
+        amount: u64,
+        token_program: &Interface<'info, TokenInterface>,
+    ) -> Result<()> {
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                token_program.to_account_info(),
+                token_interface::TransferChecked {
+                    from: from.to_account_info(),
+                    mint: mint.to_account_info(),
+                    to: to.to_account_info(),
+                    authority: self.to_account_info(),
+                },
+                &[&[
+                    LiquidityPool::POOL_SEED_PREFIX.as_bytes(),
+                    self.token.key().as_ref(),
+                    &[self.bump],
+                ]],
+            ),
+            amount,
+            mint.decimals,
         )?;
+        Ok(())
+    }
 
-        self.reserve_token += amount;
-        self.reserve_sol -= amount_out;
-
-        self.transfer_sol_from_pool(pool_sol_vault, authority, amount_out, bump, system_program)?;
+    // Recipient accounts must be passed in the configured order
+    fn pay_fee_recipients(
+        &self,
+        bonding_configuration_account: &Account<'info, CurveConfiguration>,
+        fee_recipients: &[AccountInfo<'info>],
+        routed: &[u64],
+        pool_sol_vault: &mut AccountInfo<'info>,
+        bump: u8,
+        system_program: &Program<'info, System>,
+    ) -> Result<()> {
+        let routes = &bonding_configuration_account.fee_recipients;
+        if fee_recipients.len() != routes.len()
+            || fee_recipients.iter().zip(routes.iter()).any(|(account, route)| account.key() != route.recipient)
+        {
+            return err!(CustomError::InvalidFeeRecipients);
+        }
 
+        for (recipient, amount) in fee_recipients.iter().zip(routed.iter()) {
+            if *amount > 0 {
+                self.transfer_sol_from_pool(pool_sol_vault, recipient, *amount, bump, system_program)?;
+            }
+        }
         Ok(())
     }
 
-    fn transfer_token_from_pool(
+    fn burn_token_from_pool(
         &self,
-        from: &Account<'info, TokenAccount>,
-        to: &Account<'info, TokenAccount>,
+        mint: &InterfaceAccount<'info, Mint>,
+        from: &InterfaceAccount<'info, TokenAccount>,
         amount: u64,
-        token_program: &Program<'info, Token>,
+        token_program: &Interface<'info, TokenInterface>,
     ) -> Result<()> {
-        token::transfer(
+        token_interface::burn(
             CpiContext::new_with_signer(
                 token_program.to_account_info(),
-                token::Transfer {
+                token_interface::Burn {
+                    mint: mint.to_account_info(),
                     from: from.to_account_info(),
-                    to: to.to_account_info(),
                     authority: self.to_account_info(),
                 },
                 &[&[
@@ -408,22 +1831,25 @@ impl<'info> LiquidityPoolAccount<'info> for Account<'info, LiquidityPool> {
 
     fn transfer_token_to_pool(
         &self,
-        from: &Account<'info, TokenAccount>,
-        to: &Account<'info, TokenAccount>,
+        mint: &InterfaceAccount<'info, Mint>,
+        from: &InterfaceAccount<'info, TokenAccount>,
+        to: &InterfaceAccount<'info, TokenAccount>,
         amount: u64,
         authority: &Signer<'info>,
-        token_program: &Program<'info, Token>,
+        token_program: &Interface<'info, TokenInterface>,
     ) -> Result<()> {
-        token::transfer(
+        token_interface::transfer_checked(
             CpiContext::new(
                 token_program.to_account_info(),
-                token::Transfer {
+                token_interface::TransferChecked {
                     from: from.to_account_info(),
+                    mint: mint.to_account_info(),
                     to: to.to_account_info(),
                     authority: authority.to_account_info(),
                 },
             ),
             amount,
+            mint.decimals,
         )?;
         Ok(())
     }
@@ -431,7 +1857,7 @@ impl<'info> LiquidityPoolAccount<'info> for Account<'info, LiquidityPool> {
     fn transfer_sol_from_pool(
         &self,
         from: &mut AccountInfo<'info>,
-        to: &Signer<'info>,
+        to: &AccountInfo<'info>,
         amount: u64,
         bump: u8,
         system_program: &Program<'info, System>,
@@ -481,54 +1907,6 @@ impl<'info> LiquidityPoolAccount<'info> for Account<'info, LiquidityPool> {
     }
 }
 
-fn calculate_amount_out(reserve_token_with_decimal: u64, amount_with_decimal: u64) -> Result<u64> {
-    // Convert to f64 for decimal calculations
-    let reserve_token = (reserve_token_with_decimal as f64) / 1_000_000_000.0;
-    let amount = (amount_with_decimal as f64) / 1_000_000_000.0;
-
-    msg!(
-        "Starting calculation with reserve_token: {}, amount: {}",
-        reserve_token,
-        amount
-    );
-
-    let two_reserve_token = reserve_token * 2.0;
-    msg!("two_reserve_token: {}", two_reserve_token);
-
-    let one_added = two_reserve_token + 1.0;
-    msg!("one_added: {}", one_added);
-
-    let squared = one_added * one_added;
-    msg!("squared: {}", squared);
-
-    // Use `amount` directly as it's already a decimal in f64
-    let amount_added = squared + amount * 8.0;
-    msg!("amount_added: {}", amount_added);
-
-    // Square root calculation
-    let sqrt_result = amount_added.sqrt();
-    msg!("sqrt_result: {}", sqrt_result);
-
-    // Check if sqrt_result is valid
-    if sqrt_result < 0.0 {
-        msg!("Error: Negative sqrt_result");
-        return err!(CustomError::NegativeNumber);
-    }
-
-    let subtract_one = sqrt_result - one_added;
-    msg!("subtract_one: {}", subtract_one);
-
-    let amount_out = subtract_one / 2.0;
-    msg!("amount_out: {}", amount_out);
-
-    // Convert the final result back to u64 with appropriate scaling
-    let amount_out_decimal =
-        (amount_out * 1_000_000_000.0 * INITIAL_PRICE_DIVIDER as f64).round() as u64;
-    msg!("amount_out_decimal: {}", amount_out_decimal);
-
-    Ok(amount_out_decimal)
-}
-
 ///////////////////////////////////////////////////////////////
 ///////////////////////////////////////////////////////////////
 //
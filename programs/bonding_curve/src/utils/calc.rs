@@ -1,3 +1,6 @@
+use crate::consts::FEE_BPS_DENOMINATOR;
+use crate::errors::CustomError;
+use anchor_lang::prelude::*;
 use std::ops::{Div, Mul};
 
 pub fn convert_to_float(value: u64, decimals: u8) -> f64 {
@@ -7,3 +10,64 @@ pub fn convert_to_float(value: u64, decimals: u8) -> f64 {
 pub fn convert_from_float(value: f64, decimals: u8) -> u64 {
     value.mul(f64::powf(10.0, decimals as f64)) as u64
 }
+
+// Development check that a rounded swap output is never more than the exact curve output, so rounding
+// can only leave value in the pool. Compiled in with the `rounding-checks` feature, a no-op otherwise
+#[cfg(feature = "rounding-checks")]
+pub fn check_rounding_favors_pool(exact: f64, rounded: u64) {
+    assert!(rounded as f64 <= exact, "rounding paid {} over the curve output {}", rounded, exact);
+}
+
+#[cfg(not(feature = "rounding-checks"))]
+#[inline(always)]
+pub fn check_rounding_favors_pool(_exact: f64, _rounded: u64) {}
+
+// Splits a swap fee between protocol, creator and referrer. The creator and referral shares round down
+// and the protocol takes whatever is left, so the three parts always add up to `fee`
+pub fn split_fee(fee: u64, creator_share_bps: u16, referral_share_bps: u16) -> Result<(u64, u64, u64)> {
+    if creator_share_bps as u64 + referral_share_bps as u64 > FEE_BPS_DENOMINATOR {
+        return err!(CustomError::InvalidFee);
+    }
+    let creator_fee = calculate_fee(fee, creator_share_bps as u64)?;
+    let referral_fee = calculate_fee(fee, referral_share_bps as u64)?;
+    Ok((fee - creator_fee - referral_fee, creator_fee, referral_fee))
+}
+
+// Fee in basis points taken from `amount`, rounded down
+pub fn calculate_fee(amount: u64, fee_bps: u64) -> Result<u64> {
+    let fee = (amount as u128)
+        .checked_mul(fee_bps as u128)
+        .ok_or(CustomError::OverflowOrUnderflowOccurred)?
+        / FEE_BPS_DENOMINATOR as u128;
+    u64::try_from(fee).map_err(|_| error!(CustomError::OverflowOrUnderflowOccurred))
+}
+
+// Fee like `calculate_fee`, adding the remainder carried over from earlier fees ( `dust`, in
+// 1 / FEE_BPS_DENOMINATOR units ) before rounding down. Returns the fee and the remainder to carry on
+pub fn calculate_fee_with_dust(amount: u64, fee_bps: u64, dust: u64) -> Result<(u64, u64)> {
+    let scaled = (amount as u128)
+        .checked_mul(fee_bps as u128)
+        .and_then(|scaled| scaled.checked_add(dust as u128))
+        .ok_or(CustomError::OverflowOrUnderflowOccurred)?;
+    let fee = u64::try_from(scaled / FEE_BPS_DENOMINATOR as u128)
+        .map_err(|_| error!(CustomError::OverflowOrUnderflowOccurred))?;
+    Ok((fee, (scaled % FEE_BPS_DENOMINATOR as u128) as u64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rounding_down_passes_the_check() {
+        check_rounding_favors_pool(1_234.9, 1_234);
+        check_rounding_favors_pool(1_234.0, 1_234);
+    }
+
+    #[cfg(feature = "rounding-checks")]
+    #[test]
+    #[should_panic(expected = "rounding paid 1235 over the curve output 1234.9")]
+    fn rounding_over_the_curve_trips_the_check() {
+        check_rounding_favors_pool(1_234.9, 1_235);
+    }
+}
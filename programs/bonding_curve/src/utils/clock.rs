@@ -0,0 +1,37 @@
+use anchor_lang::prelude::*;
+
+use crate::state::TestClock;
+
+// Where the time-based rules read the current time from
+pub trait TimeSource {
+    fn unix_timestamp(&self) -> Result<i64>;
+}
+
+// The cluster clock, the only source release builds accept
+pub struct SysvarClock;
+
+impl TimeSource for SysvarClock {
+    fn unix_timestamp(&self) -> Result<i64> {
+        Ok(Clock::get()?.unix_timestamp)
+    }
+}
+
+impl TimeSource for TestClock {
+    #[cfg(feature = "testing")]
+    fn unix_timestamp(&self) -> Result<i64> {
+        Ok(self.unix_timestamp)
+    }
+
+    #[cfg(not(feature = "testing"))]
+    fn unix_timestamp(&self) -> Result<i64> {
+        err!(crate::errors::CustomError::TestingOnly)
+    }
+}
+
+// Current time from the injected test clock when an instruction was given one, the cluster clock otherwise
+pub fn current_timestamp(test_clock: Option<&TestClock>) -> Result<i64> {
+    match test_clock {
+        Some(test_clock) => test_clock.unix_timestamp(),
+        None => SysvarClock.unix_timestamp(),
+    }
+}
@@ -0,0 +1,55 @@
+use anchor_lang::{
+    prelude::*,
+    solana_program::{instruction::Instruction, program::invoke},
+};
+
+use crate::errors::CustomError;
+
+// Anchor discriminator of `on_swap`, the first 8 bytes of sha256("global:on_swap")
+pub const POST_SWAP_HOOK_DISCRIMINATOR: [u8; 8] = [201, 131, 123, 189, 167, 226, 124, 81];
+
+// Payload handed to the post-swap hook program
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct PostSwapHookArgs {
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub direction: u8,
+    pub amount_in: u64,
+    pub amount_out: u64,
+}
+
+// Calls `on_swap` on the hook program. The first remaining account must be the
+// hook program itself, the rest are passed through as the hook's accounts
+pub fn invoke_post_swap_hook<'info>(
+    hook_program: Pubkey,
+    remaining_accounts: &[AccountInfo<'info>],
+    args: PostSwapHookArgs,
+) -> Result<()> {
+    let (program_account, hook_accounts) = remaining_accounts
+        .split_first()
+        .ok_or(CustomError::InvalidPostSwapProgram)?;
+    if program_account.key() != hook_program || !program_account.executable {
+        return err!(CustomError::InvalidPostSwapProgram);
+    }
+
+    let mut data = POST_SWAP_HOOK_DISCRIMINATOR.to_vec();
+    args.serialize(&mut data)?;
+
+    let instruction = Instruction {
+        program_id: hook_program,
+        accounts: hook_accounts
+            .iter()
+            .map(|account| AccountMeta {
+                pubkey: account.key(),
+                is_signer: account.is_signer,
+                is_writable: account.is_writable,
+            })
+            .collect(),
+        data,
+    };
+
+    let mut account_infos = hook_accounts.to_vec();
+    account_infos.push(program_account.clone());
+    invoke(&instruction, &account_infos)?;
+    Ok(())
+}
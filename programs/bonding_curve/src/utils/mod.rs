@@ -1,2 +1,9 @@
 pub mod calc;
+pub mod clock;
+pub mod hook;
+pub mod reward;
+
 pub use calc::*;
+pub use clock::*;
+pub use hook::*;
+pub use reward::*;
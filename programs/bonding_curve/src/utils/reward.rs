@@ -0,0 +1,41 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface};
+
+use crate::state::CurveConfiguration;
+
+// Pays the configured reward token to a trader out of the vault owned by the configuration.
+// Nothing is paid when no reward accounts are passed, and an empty vault pays what it has left.
+pub fn pay_swap_reward<'info>(
+    config: &Account<'info, CurveConfiguration>,
+    reward_mint: Option<&InterfaceAccount<'info, Mint>>,
+    reward_vault: Option<&InterfaceAccount<'info, TokenAccount>>,
+    user_reward_account: Option<&InterfaceAccount<'info, TokenAccount>>,
+    token_program: &Interface<'info, TokenInterface>,
+) -> Result<u64> {
+    let (Some(reward_mint), Some(reward_vault), Some(user_reward_account)) =
+        (reward_mint, reward_vault, user_reward_account)
+    else {
+        return Ok(0);
+    };
+
+    let amount = config.reward_per_swap.min(reward_vault.amount);
+    if amount == 0 {
+        return Ok(0);
+    }
+
+    token_interface::transfer_checked(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            token_interface::TransferChecked {
+                from: reward_vault.to_account_info(),
+                mint: reward_mint.to_account_info(),
+                to: user_reward_account.to_account_info(),
+                authority: config.to_account_info(),
+            },
+            &[&[CurveConfiguration::SEED.as_bytes(), &[config.bump]]],
+        ),
+        amount,
+        reward_mint.decimals,
+    )?;
+    Ok(amount)
+}
@@ -0,0 +1,63 @@
+use anchor_lang::prelude::*;
+
+declare_id!("537rbC1vJpXdXBcuZu3Hcim3Q5LMci22fWvdNpGeEbFG");
+
+// Records every post-swap callback it receives from the bonding curve
+#[program]
+pub mod swap_hook_mock {
+    use super::*;
+
+    pub fn on_swap(ctx: Context<OnSwap>, args: PostSwapHookArgs) -> Result<()> {
+        let record = &mut ctx.accounts.hook_record;
+        record.calls += 1;
+        record.pool = args.pool;
+        record.user = args.user;
+        record.direction = args.direction;
+        record.amount_in = args.amount_in;
+        record.amount_out = args.amount_out;
+        Ok(())
+    }
+}
+
+// Same layout as `bonding_curve::utils::PostSwapHookArgs`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct PostSwapHookArgs {
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub direction: u8,
+    pub amount_in: u64,
+    pub amount_out: u64,
+}
+
+#[account]
+pub struct HookRecord {
+    pub calls: u64,
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub direction: u8,
+    pub amount_in: u64,
+    pub amount_out: u64,
+}
+
+impl HookRecord {
+    pub const SEED: &'static str = "hook_record";
+
+    // Discriminator (8) + u64 (8) + Pubkey (32) + Pubkey (32) + u8 (1) + u64 (8) + u64 (8)
+    pub const ACCOUNT_SIZE: usize = 8 + 8 + 32 + 32 + 1 + 8 + 8;
+}
+
+#[derive(Accounts)]
+pub struct OnSwap<'info> {
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = HookRecord::ACCOUNT_SIZE,
+        seeds = [HookRecord::SEED.as_bytes()],
+        bump,
+    )]
+    pub hook_record: Account<'info, HookRecord>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
@@ -1,8 +1,9 @@
 import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
 import { BondingCurve } from "../target/types/bonding_curve"
-import { Connection, PublicKey, Keypair, SystemProgram, Transaction, sendAndConfirmTransaction, ComputeBudgetProgram, SYSVAR_RENT_PUBKEY } from "@solana/web3.js"
-import { createMint, getOrCreateAssociatedTokenAccount, mintTo, getAssociatedTokenAddress } from "@solana/spl-token"
+import { SwapHookMock } from "../target/types/swap_hook_mock"
+import { Connection, PublicKey, Keypair, SystemProgram, Transaction, TransactionInstruction, sendAndConfirmTransaction, ComputeBudgetProgram, SYSVAR_RENT_PUBKEY } from "@solana/web3.js"
+import { createMint, createAccount, getOrCreateAssociatedTokenAccount, mintTo, transfer, getAssociatedTokenAddress, getAssociatedTokenAddressSync, getAccount, getMintLen, ExtensionType, TOKEN_2022_PROGRAM_ID, createInitializeTransferFeeConfigInstruction, createInitializeMintInstruction } from "@solana/spl-token"
 import { expect } from "chai";
 import { BN } from "bn.js";
 import keys from '../keys/users.json'
@@ -15,14 +16,48 @@ const curveSeed = "CurveConfiguration"
 const POOL_SEED_PREFIX = "liquidity_pool"
 const LIQUIDITY_SEED = "LiqudityProvider"
 const SOL_VAULT_PREFIX = "liquidity_sol_vault"
+const USER_STATE_SEED = "user_state"
+const CREATOR_VESTING_SEED = "creator_vesting"
+const CREATOR_POOLS_SEED = "creator_pools"
+const WHITELIST_SEED = "whitelist"
+const AUCTION_COMMITMENT_SEED = "auction_commitment"
+const TEST_CLOCK_SEED = "test_clock"
 function sleep(ms: number) {
   return new Promise(resolve => setTimeout(resolve, ms));
 }
 
+async function sendInstructions(ixs: TransactionInstruction[], signers: Keypair[]) {
+  const tx = new Transaction()
+    .add(
+      ComputeBudgetProgram.setComputeUnitLimit({ units: 200_000 }),
+      ...ixs
+    )
+  tx.feePayer = signers[0].publicKey
+  tx.recentBlockhash = (await connection.getLatestBlockhash()).blockhash
+  return await sendAndConfirmTransaction(connection, tx, signers)
+}
+
+async function getTransactionLogs(signature: string) {
+  const tx = await connection.getTransaction(signature, { commitment: "confirmed", maxSupportedTransactionVersion: 0 })
+  return tx?.meta?.logMessages ?? []
+}
+
+async function expectCustomError(promise: Promise<unknown>, code: string) {
+  try {
+    await promise
+  } catch (error) {
+    const logs: string[] = error.logs ?? []
+    expect(`${error}\n${logs.join("\n")}`).to.include(code)
+    return
+  }
+  expect.fail(`Expected transaction to fail with ${code}`)
+}
+
 describe("bonding_curve", () => {
   anchor.setProvider(anchor.AnchorProvider.env());
 
   const program = anchor.workspace.BondingCurve as Program<BondingCurve>;
+  const hookProgram = anchor.workspace.SwapHookMock as Program<SwapHookMock>;
 
   // custom setting 
   const user = Keypair.fromSecretKey(new Uint8Array(keys))
@@ -38,6 +73,497 @@ describe("bonding_curve", () => {
 
   console.log("Admin's wallet address is : ", user.publicKey.toBase58())
 
+  const getPdas = (mint: PublicKey, tokenProgram = TOKEN_PROGRAM_ID) => {
+    const [curveConfig] = PublicKey.findProgramAddressSync(
+      [Buffer.from(curveSeed)],
+      program.programId
+    )
+    const [poolPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from(POOL_SEED_PREFIX), mint.toBuffer()],
+      program.programId
+    )
+    const [poolSolVault, solVaultBump] = PublicKey.findProgramAddressSync(
+      [Buffer.from(SOL_VAULT_PREFIX), mint.toBuffer()],
+      program.programId
+    )
+    const poolToken = getAssociatedTokenAddressSync(mint, poolPda, true, tokenProgram)
+    return { curveConfig, poolPda, poolSolVault, solVaultBump, poolToken }
+  }
+
+  const getUserState = (mint: PublicKey, owner: PublicKey) => {
+    const [userState] = PublicKey.findProgramAddressSync(
+      [Buffer.from(USER_STATE_SEED), getPdas(mint).poolPda.toBuffer(), owner.toBuffer()],
+      program.programId
+    )
+    return userState
+  }
+
+  const getCreatorPools = (creator: PublicKey) => {
+    const [creatorPools] = PublicKey.findProgramAddressSync(
+      [Buffer.from(CREATOR_POOLS_SEED), creator.toBuffer()],
+      program.programId
+    )
+    return creatorPools
+  }
+
+  // Time rules are driven through the test clock rather than waited out: instructions given `testClock`
+  // read it instead of the cluster clock. Run with TESTING_FEATURE=1 against a program built with
+  // `--features testing`, the steps that move time are skipped otherwise
+  const [testClock] = PublicKey.findProgramAddressSync([Buffer.from(TEST_CLOCK_SEED)], program.programId)
+  const setTestClockIx = (unixTimestamp: BN | number) => program.methods
+    .setTestClock(new BN(unixTimestamp.toString()))
+    .accounts({
+      dexConfigurationAccount: getPdas(PublicKey.default).curveConfig,
+      testClock,
+      authority: user.publicKey,
+      systemProgram: SystemProgram.programId
+    })
+    .instruction()
+  const setTestClock = async (unixTimestamp: BN | number) => sendInstructions([await setTestClockIx(unixTimestamp)], [user])
+
+  // Rules keyed on the slot wait for the cluster to produce it, the test clock only carries the time
+  const waitForSlot = async (slot: number) => {
+    while ((await connection.getSlot()) < slot) await sleep(100)
+  }
+  const waitForNextSlot = async () => waitForSlot((await connection.getSlot()) + 1)
+
+  const createPoolIx = (
+    mint: PublicKey,
+    payer: PublicKey,
+    launchSellFeeBps = 0,
+    launchFeeWindowSecs = 0,
+    metadataUri = "",
+    tokenProgram = TOKEN_PROGRAM_ID
+  ) => {
+    const { curveConfig, poolPda, poolToken, poolSolVault } = getPdas(mint, tokenProgram)
+    const [protocolTreasury] = PublicKey.findProgramAddressSync([Buffer.from("protocol_treasury")], program.programId)
+    return program.methods
+      .createPool(launchSellFeeBps, launchFeeWindowSecs, metadataUri)
+      .accounts({
+        dexConfigurationAccount: curveConfig,
+        creatorPools: getCreatorPools(payer),
+        pool: poolPda,
+        tokenMint: mint,
+        poolTokenAccount: poolToken,
+        protocolTreasury,
+        poolSolVault,
+        payer,
+        tokenProgram,
+        rent: SYSVAR_RENT_PUBKEY,
+        associatedTokenProgram: ASSOCIATED_PROGRAM_ID,
+        systemProgram: SystemProgram.programId
+      })
+      .instruction()
+  }
+
+  const addLiquidityIx = (mint: PublicKey, provider: PublicKey, tokenProgram = TOKEN_PROGRAM_ID) => {
+    const { curveConfig, poolPda, poolSolVault, poolToken } = getPdas(mint, tokenProgram)
+    const [liquidityProviderAccount] = PublicKey.findProgramAddressSync(
+      [Buffer.from(LIQUIDITY_SEED), poolPda.toBuffer(), provider.toBuffer()],
+      program.programId
+    )
+    return program.methods
+      .addLiquidity()
+      .accounts({
+        dexConfigurationAccount: curveConfig,
+        pool: poolPda,
+        liquidityProviderAccount,
+        poolSolVault,
+        tokenMint: mint,
+        poolTokenAccount: poolToken,
+        userTokenAccount: getAssociatedTokenAddressSync(mint, provider, false, tokenProgram),
+        user: provider,
+        tokenProgram,
+        associatedTokenProgram: ASSOCIATED_PROGRAM_ID,
+        rent: SYSVAR_RENT_PUBKEY,
+        systemProgram: SystemProgram.programId
+      })
+      .instruction()
+  }
+
+  // Reward and referrer accounts are only passed by tests exercising them
+  const getWhitelistEntry = (mint: PublicKey, owner: PublicKey) => {
+    const [whitelistEntry] = PublicKey.findProgramAddressSync(
+      [Buffer.from(WHITELIST_SEED), getPdas(mint).poolPda.toBuffer(), owner.toBuffer()],
+      program.programId
+    )
+    return whitelistEntry
+  }
+
+  const buyAccounts = async (
+    mint: PublicKey,
+    buyer: PublicKey,
+    reward: PublicKey | null = null,
+    referrer: PublicKey | null = null,
+    clock: PublicKey | null = null
+  ) => {
+    const { curveConfig, poolPda, poolSolVault, poolToken } = getPdas(mint)
+    const { creator } = await program.account.liquidityPool.fetch(poolPda)
+    const whitelistEntry = getWhitelistEntry(mint, buyer)
+    return {
+      pool: poolPda,
+      tokenMint: mint,
+      poolSolVault,
+      poolTokenAccount: poolToken,
+      userTokenAccount: getAssociatedTokenAddressSync(mint, buyer),
+      dexConfigurationAccount: curveConfig,
+      userState: getUserState(mint, buyer),
+      creator,
+      rewardMint: reward,
+      rewardVault: reward && getAssociatedTokenAddressSync(reward, curveConfig, true),
+      userRewardAccount: reward && getAssociatedTokenAddressSync(reward, buyer),
+      referrer,
+      recipientTokenAccount: null,
+      recipientState: null,
+      whitelistEntry: (await connection.getAccountInfo(whitelistEntry)) ? whitelistEntry : null,
+      testClock: clock,
+      user: buyer,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      associatedTokenProgram: ASSOCIATED_PROGRAM_ID,
+      rent: SYSVAR_RENT_PUBKEY,
+      systemProgram: SystemProgram.programId
+    }
+  }
+
+  const buyIx = async (
+    mint: PublicKey,
+    buyer: PublicKey,
+    amount: BN,
+    reward: PublicKey | null = null,
+    referrer: PublicKey | null = null,
+    clock: PublicKey | null = null
+  ) => program.methods
+    .buy(amount)
+    .accounts(await buyAccounts(mint, buyer, reward, referrer, clock))
+    .instruction()
+
+  const sellIx = (
+    mint: PublicKey,
+    seller: PublicKey,
+    amount: BN,
+    reward: PublicKey | null = null,
+    referrer: PublicKey | null = null,
+    clock: PublicKey | null = null
+  ) => {
+    const { curveConfig, poolPda, poolSolVault, solVaultBump, poolToken } = getPdas(mint)
+    return program.methods
+      .sell(amount, solVaultBump)
+      .accounts({
+        pool: poolPda,
+        tokenMint: mint,
+        poolSolVault,
+        poolTokenAccount: poolToken,
+        userTokenAccount: getAssociatedTokenAddressSync(mint, seller),
+        dexConfigurationAccount: curveConfig,
+        userState: getUserState(mint, seller),
+        rewardMint: reward,
+      rewardVault: reward && getAssociatedTokenAddressSync(reward, curveConfig, true),
+        userRewardAccount: reward && getAssociatedTokenAddressSync(reward, seller),
+        referrer,
+        testClock: clock,
+        user: seller,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_PROGRAM_ID,
+        rent: SYSVAR_RENT_PUBKEY,
+        systemProgram: SystemProgram.programId
+      })
+      .instruction()
+  }
+
+  const simulateSwapPathIx = (amountIn: BN, legs: { mint: PublicKey, direction: number }[]) => {
+    const { curveConfig } = getPdas(legs[0].mint)
+    return program.methods
+      .simulateSwapPath(amountIn, Buffer.from(legs.map((leg) => leg.direction)))
+      .accounts({ dexConfigurationAccount: curveConfig })
+      .remainingAccounts(legs.map((leg) => ({ pubkey: getPdas(leg.mint).poolPda, isSigner: false, isWritable: false })))
+      .instruction()
+  }
+
+  const quoteGraduation = async (mint: PublicKey) => {
+    const { curveConfig, poolPda } = getPdas(mint)
+    const ix = await program.methods
+      .quoteGraduation()
+      .accounts({ dexConfigurationAccount: curveConfig, pool: poolPda })
+      .instruction()
+    const sig = await sendInstructions([ix], [user])
+    return (await getEvents(sig)).find((e) => e.name === "GraduationQuote").data
+  }
+
+  const setVirtualReserveIx = (mint: PublicKey, virtualSolReserve: BN) => {
+    const { curveConfig, poolPda } = getPdas(mint)
+    return program.methods
+      .setVirtualReserve(virtualSolReserve)
+      .accounts({ dexConfigurationAccount: curveConfig, pool: poolPda, authority: user.publicKey })
+      .instruction()
+  }
+
+  const removeLiquidityIx = (mint: PublicKey, provider: PublicKey) => {
+    const { curveConfig, poolPda, poolSolVault, solVaultBump, poolToken } = getPdas(mint)
+    const [liquidityProviderAccount] = PublicKey.findProgramAddressSync(
+      [Buffer.from(LIQUIDITY_SEED), poolPda.toBuffer(), provider.toBuffer()],
+      program.programId
+    )
+    return program.methods
+      .removeLiquidity(solVaultBump)
+      .accounts({
+        dexConfigurationAccount: curveConfig,
+        pool: poolPda,
+        liquidityProviderAccount,
+        tokenMint: mint,
+        poolTokenAccount: poolToken,
+        userTokenAccount: getAssociatedTokenAddressSync(mint, provider),
+        poolSolVault,
+        protocolTreasury: getProtocolTreasury(),
+        user: provider,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_PROGRAM_ID,
+        rent: SYSVAR_RENT_PUBKEY,
+        systemProgram: SystemProgram.programId
+      })
+      .instruction()
+  }
+
+  const claimLpFeesIx = (mint: PublicKey, provider: PublicKey, clock: PublicKey | null = null) => {
+    const { poolPda, poolSolVault, solVaultBump } = getPdas(mint)
+    const [liquidityProviderAccount] = PublicKey.findProgramAddressSync(
+      [Buffer.from(LIQUIDITY_SEED), poolPda.toBuffer(), provider.toBuffer()],
+      program.programId
+    )
+    return program.methods
+      .claimLpFees(solVaultBump)
+      .accounts({
+        pool: poolPda,
+        liquidityProviderAccount,
+        tokenMint: mint,
+        poolSolVault,
+        testClock: clock,
+        user: provider,
+        systemProgram: SystemProgram.programId
+      })
+      .instruction()
+  }
+
+  const withdrawProtocolTokensIx = (mint: PublicKey, recipientTokenAccount: PublicKey, amount: BN) => {
+    const { curveConfig, poolPda, poolToken } = getPdas(mint)
+    return program.methods
+      .withdrawProtocolTokens(amount)
+      .accounts({
+        dexConfigurationAccount: curveConfig,
+        pool: poolPda,
+        tokenMint: mint,
+        poolTokenAccount: poolToken,
+        recipientTokenAccount,
+        authority: user.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_PROGRAM_ID
+      })
+      .instruction()
+  }
+
+  const adminSwapIx = (mint: PublicKey, authority: PublicKey, amount: BN, direction: number) => {
+    const { curveConfig, poolPda, poolSolVault, solVaultBump, poolToken } = getPdas(mint)
+    return program.methods
+      .adminSwap(amount, direction, solVaultBump)
+      .accounts({
+        dexConfigurationAccount: curveConfig,
+        pool: poolPda,
+        tokenMint: mint,
+        poolTokenAccount: poolToken,
+        poolSolVault,
+        authorityTokenAccount: getAssociatedTokenAddressSync(mint, authority),
+        authority,
+        rent: SYSVAR_RENT_PUBKEY,
+        systemProgram: SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_PROGRAM_ID
+      })
+      .instruction()
+  }
+
+  const getProtocolTreasury = () =>
+    PublicKey.findProgramAddressSync([Buffer.from("protocol_treasury")], program.programId)[0]
+
+  const buybackIx = (mint: PublicKey, authority: PublicKey, amount: BN, burn: boolean) => {
+    const { curveConfig, poolPda, poolToken, poolSolVault } = getPdas(mint)
+    return program.methods
+      .buyback(amount, burn)
+      .accounts({
+        dexConfigurationAccount: curveConfig,
+        pool: poolPda,
+        tokenMint: mint,
+        poolTokenAccount: poolToken,
+        protocolTreasury: getProtocolTreasury(),
+        poolSolVault,
+        creator: user.publicKey,
+        authority,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId
+      })
+      .instruction()
+  }
+
+  const getCommitment = (mint: PublicKey, owner: PublicKey) => PublicKey.findProgramAddressSync(
+    [Buffer.from(AUCTION_COMMITMENT_SEED), getPdas(mint).poolPda.toBuffer(), owner.toBuffer()],
+    program.programId
+  )[0]
+
+  const commitAuctionIx = (mint: PublicKey, owner: PublicKey, lamports: number, clock: PublicKey | null = null) => {
+    const { curveConfig, poolPda, poolSolVault } = getPdas(mint)
+    return program.methods
+      .commitAuction(new BN(lamports))
+      .accounts({
+        dexConfigurationAccount: curveConfig,
+        pool: poolPda,
+        poolSolVault,
+        commitment: getCommitment(mint, owner),
+        whitelistEntry: null,
+        testClock: clock,
+        user: owner,
+        systemProgram: SystemProgram.programId
+      })
+      .instruction()
+  }
+
+  const settleAuctionIx = (mint: PublicKey, clock: PublicKey | null = null) => {
+    const { curveConfig, poolPda, poolSolVault } = getPdas(mint)
+    return program.methods
+      .settleAuction()
+      .accounts({
+        dexConfigurationAccount: curveConfig,
+        pool: poolPda,
+        poolSolVault,
+        creator: user.publicKey,
+        testClock: clock,
+        systemProgram: SystemProgram.programId
+      })
+      .instruction()
+  }
+
+  const claimAuctionIx = (mint: PublicKey, owner: PublicKey) => {
+    const { poolPda, poolToken } = getPdas(mint)
+    return program.methods
+      .claimAuction()
+      .accounts({
+        pool: poolPda,
+        tokenMint: mint,
+        poolTokenAccount: poolToken,
+        userTokenAccount: getAssociatedTokenAddressSync(mint, owner),
+        commitment: getCommitment(mint, owner),
+        userState: getUserState(mint, owner),
+        user: owner,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_PROGRAM_ID,
+        systemProgram: SystemProgram.programId
+      })
+      .instruction()
+  }
+
+  const refundAuctionIx = (mint: PublicKey, owner: PublicKey) => {
+    const { poolPda, poolSolVault } = getPdas(mint)
+    return program.methods
+      .refundAuction()
+      .accounts({
+        pool: poolPda,
+        poolSolVault,
+        commitment: getCommitment(mint, owner),
+        user: owner,
+        systemProgram: SystemProgram.programId
+      })
+      .instruction()
+  }
+
+  const getVesting = (mint: PublicKey) => {
+    const [creatorVesting] = PublicKey.findProgramAddressSync(
+      [Buffer.from(CREATOR_VESTING_SEED), getPdas(mint).poolPda.toBuffer()],
+      program.programId
+    )
+    return creatorVesting
+  }
+
+  const createVestingIx = (mint: PublicKey, total: BN, cliffSecs: number, durationSecs: number) => {
+    const creatorVesting = getVesting(mint)
+    return program.methods
+      .createVesting(total, cliffSecs, durationSecs)
+      .accounts({
+        pool: getPdas(mint).poolPda,
+        creatorVesting,
+        tokenMint: mint,
+        vestingVault: getAssociatedTokenAddressSync(mint, creatorVesting, true),
+        creatorTokenAccount: getAssociatedTokenAddressSync(mint, user.publicKey),
+        testClock: null,
+        creator: user.publicKey,
+        rent: SYSVAR_RENT_PUBKEY,
+        systemProgram: SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_PROGRAM_ID
+      })
+      .instruction()
+  }
+
+  const claimVestedIx = (mint: PublicKey, clock: PublicKey | null = null) => {
+    const creatorVesting = getVesting(mint)
+    return program.methods
+      .claimVested()
+      .accounts({
+        pool: getPdas(mint).poolPda,
+        creatorVesting,
+        tokenMint: mint,
+        vestingVault: getAssociatedTokenAddressSync(mint, creatorVesting, true),
+        beneficiaryTokenAccount: getAssociatedTokenAddressSync(mint, user.publicKey),
+        testClock: clock,
+        beneficiary: user.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_PROGRAM_ID
+      })
+      .instruction()
+  }
+
+  const updateConfigurationIx = (params: object) => {
+    const [curveConfig] = PublicKey.findProgramAddressSync(
+      [Buffer.from(curveSeed)],
+      program.programId
+    )
+    return program.methods
+      .updateConfiguration(params)
+      .accounts({ dexConfigurationAccount: curveConfig, authority: user.publicKey })
+      .instruction()
+  }
+
+  const getEvents = async (signature: string) => {
+    const parser = new anchor.EventParser(program.programId, new anchor.BorshCoder(program.idl))
+    return Array.from(parser.parseLogs(await getTransactionLogs(signature)))
+  }
+
+  // Largest borsh size of an IDL type, `vecLens` caps each vec or string field by name
+  const layoutSize = (idl: anchor.Idl, ty: any, vecLens: Record<string, number> = {}, field = ""): number => {
+    const primitives: Record<string, number> = {
+      bool: 1, u8: 1, i8: 1, u16: 2, i16: 2, u32: 4, i32: 4, f32: 4,
+      u64: 8, i64: 8, f64: 8, u128: 16, i128: 16, publicKey: 32,
+    }
+    if (ty === "string" || ty === "bytes") return 4 + (vecLens[field] ?? 0)
+    if (typeof ty === "string") return primitives[ty]
+    if (ty.option) return 1 + layoutSize(idl, ty.option, vecLens, field)
+    if (ty.array) return ty.array[1] * layoutSize(idl, ty.array[0], vecLens, field)
+    if (ty.vec) return 4 + (vecLens[field] ?? 0) * layoutSize(idl, ty.vec, vecLens, field)
+    const defined = idl.types.find((t) => t.name === ty.defined)
+    return (defined.type as any).fields.reduce((sum, f) => sum + layoutSize(idl, f.type, vecLens, f.name), 0)
+  }
+
+  const accountSize = (idl: anchor.Idl, name: string, vecLens: Record<string, number> = {}) => {
+    const account = idl.accounts.find((a) => a.name === name)
+    return 8 + account.type.fields.reduce((sum, f) => sum + layoutSize(idl, f.type, vecLens, f.name), 0)
+  }
+
+  // Creates a fresh mint owned by `user`, a pool for it and seeds the pool with the whole supply
+  const setupPool = async (launchSellFeeBps = 0, launchFeeWindowSecs = 0, supply = amount) => {
+    const mint = await createMint(connection, user, user.publicKey, null, tokenDecimal)
+    const ata = (await getOrCreateAssociatedTokenAccount(connection, user, mint, user.publicKey)).address
+    await mintTo(connection, user, mint, ata, user.publicKey, BigInt(supply.toString()))
+    await sendInstructions([await createPoolIx(mint, user.publicKey, launchSellFeeBps, launchFeeWindowSecs)], [user])
+    await sendInstructions([await addLiquidityIx(mint, user.publicKey)], [user])
+    return mint
+  }
+
   it("Airdrop to admin wallet", async () => {
     console.log(`Requesting airdrop to admin for 1SOL : ${user.publicKey.toBase58()}`)
     // 1 - Request Airdrop
@@ -79,7 +605,7 @@ describe("bonding_curve", () => {
     console.log("Trying to create and mint token1 to user's wallet")
 
     try {
-      mint1 = await createMint(connection, user, user.publicKey, user.publicKey, tokenDecimal)
+      mint1 = await createMint(connection, user, user.publicKey, null, tokenDecimal)
       console.log('mint1 address: ', mint1.toBase58());
       tokenAta1 = (await getOrCreateAssociatedTokenAccount(connection, user, mint1, user.publicKey)).address
       console.log('token1 account address: ', tokenAta1.toBase58());
@@ -148,6 +674,18 @@ describe("bonding_curve", () => {
     }
   });
 
+  it("Configuration stores its canonical bump", async () => {
+    const [curveConfig, bump] = PublicKey.findProgramAddressSync(
+      [Buffer.from(curveSeed)],
+      program.programId
+    )
+    const config = await program.account.curveConfiguration.fetch(curveConfig)
+    expect(config.bump).to.equal(bump)
+
+    // Instructions resolve the configuration through the stored bump
+    await sendInstructions([await updateConfigurationIx({})], [user])
+  })
+
   it("create pool", async () => {
     try {
       const [poolPda] = PublicKey.findProgramAddressSync(
@@ -162,8 +700,10 @@ describe("bonding_curve", () => {
           ComputeBudgetProgram.setComputeUnitLimit({ units: 200_000 }),
           ComputeBudgetProgram.setComputeUnitPrice({ microLamports: 200_000 }),
           await program.methods
-            .createPool()
+            .createPool(0, 0, "")
             .accounts({
+              dexConfigurationAccount: getPdas(mint1).curveConfig,
+              creatorPools: getCreatorPools(user.publicKey),
               pool: poolPda,
               tokenMint: mint1,
               poolTokenAccount: poolToken,
@@ -185,9 +725,60 @@ describe("bonding_curve", () => {
     }
   })
 
+  it("Reject pools for mints with a freeze authority", async () => {
+    const mint = await createMint(connection, user, user.publicKey, user.publicKey, tokenDecimal)
+    await expectCustomError(
+      sendInstructions([await createPoolIx(mint, user.publicKey)], [user]),
+      "InvalidMint"
+    )
+  })
+
+  it("Pool caches the token decimals and prices swaps with them", async () => {
+    const mint = await setupPool()
+    const { poolPda } = getPdas(mint)
+    const mintInfo = await connection.getParsedAccountInfo(mint)
+    const pool = await program.account.liquidityPool.fetch(poolPda)
+    expect(pool.tokenDecimals).to.equal((mintInfo.value.data as any).parsed.info.decimals)
+
+    // 0.1 SOL less the 1% fee buys sqrt(1280 * 0.099) million tokens
+    await sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 8))], [user])
+    const balance = Number((await connection.getTokenAccountBalance(getAssociatedTokenAddressSync(mint, user.publicKey))).value.amount)
+    expect(balance / 10 ** (6 + pool.tokenDecimals)).to.be.closeTo(Math.sqrt(1280 * 0.099), 1e-6)
+  })
+
+  it("Pool token account exists and is owned by the pool after creation", async () => {
+    const [poolPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from(POOL_SEED_PREFIX), mint1.toBuffer()],
+      program.programId
+    )
+    const poolToken = await getAssociatedTokenAddress(mint1, poolPda, true)
+    const poolTokenAccount = await getAccount(connection, poolToken)
+    expect(poolTokenAccount.owner.toBase58()).to.equal(poolPda.toBase58())
+    expect(poolTokenAccount.mint.toBase58()).to.equal(mint1.toBase58())
+  })
+
+  it("create_pool emits PoolCreated", async () => {
+    const mint = await createMint(connection, user, user.publicKey, null, tokenDecimal)
+    const { poolPda } = getPdas(mint)
+    const sig = await sendInstructions([await createPoolIx(mint, user.publicKey, 3000, 60)], [user])
+
+    const event = (await getEvents(sig)).find((e) => e.name === "PoolCreated")
+    const pool = await program.account.liquidityPool.fetch(poolPda)
+    expect(event.data.pool.toBase58()).to.equal(poolPda.toBase58())
+    expect(event.data.creator.toBase58()).to.equal(pool.creator.toBase58())
+    expect(event.data.token.toBase58()).to.equal(mint.toBase58())
+    expect(event.data.bump).to.equal(pool.bump)
+    expect(event.data.launchSellFeeBps).to.equal(3000)
+    expect(event.data.launchFeeWindowSecs).to.equal(60)
+    expect(event.data.timestamp.toNumber()).to.be.greaterThan(0)
+  })
+
   it("add liquidity", async () => {
     try {
-
+      const [curveConfig] = PublicKey.findProgramAddressSync(
+        [Buffer.from(curveSeed)],
+        program.programId
+      )
       const [poolPda] = PublicKey.findProgramAddressSync(
         [Buffer.from(POOL_SEED_PREFIX), mint1.toBuffer()],
         program.programId
@@ -214,9 +805,11 @@ describe("bonding_curve", () => {
           ComputeBudgetProgram.setComputeUnitLimit({ units: 200_000 }),
           ComputeBudgetProgram.setComputeUnitPrice({ microLamports: 200_000 }),
           await program.methods
-            .addLiquidity()
+            .addLiquidity(10_000)
             .accounts({
+              dexConfigurationAccount: curveConfig,
               pool: poolPda,
+              liquidityProviderAccount,
               poolSolVault: poolSolVault,
               tokenMint: mint1,
               poolTokenAccount: poolToken,
@@ -243,6 +836,68 @@ describe("bonding_curve", () => {
     }
   })
 
+  it("Reject new liquidity providers beyond the configured cap", async () => {
+    const [curveConfig] = PublicKey.findProgramAddressSync(
+      [Buffer.from(curveSeed)],
+      program.programId
+    )
+    const [poolPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from(POOL_SEED_PREFIX), mint1.toBuffer()],
+      program.programId
+    )
+    const [poolSolVault] = PublicKey.findProgramAddressSync(
+      [Buffer.from(SOL_VAULT_PREFIX), mint1.toBuffer()],
+      program.programId
+    )
+    const [liquidityProviderAccount] = PublicKey.findProgramAddressSync(
+      [Buffer.from(LIQUIDITY_SEED), poolPda.toBuffer(), user2.publicKey.toBuffer()],
+      program.programId
+    )
+    const poolToken = await getAssociatedTokenAddress(mint1, poolPda, true)
+    const user2Ata = (await getOrCreateAssociatedTokenAccount(connection, user, mint1, user2.publicKey)).address
+    await connection.confirmTransaction(await connection.requestAirdrop(user2.publicKey, 10 ** 9), "confirmed")
+
+    await sendInstructions([
+      await program.methods
+        .updateConfiguration({ maxLps: 1 })
+        .accounts({ dexConfigurationAccount: curveConfig, authority: user.publicKey })
+        .instruction()
+    ], [user])
+
+    const pool = await program.account.liquidityPool.fetch(poolPda)
+    expect(pool.lpCount).to.equal(1)
+
+    await expectCustomError(
+      sendInstructions([
+        await program.methods
+          .addLiquidity(10_000)
+          .accounts({
+            dexConfigurationAccount: curveConfig,
+            pool: poolPda,
+            liquidityProviderAccount,
+            poolSolVault,
+            tokenMint: mint1,
+            poolTokenAccount: poolToken,
+            userTokenAccount: user2Ata,
+            user: user2.publicKey,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            associatedTokenProgram: ASSOCIATED_PROGRAM_ID,
+            rent: SYSVAR_RENT_PUBKEY,
+            systemProgram: SystemProgram.programId
+          })
+          .instruction()
+      ], [user2]),
+      "MaxLpsReached"
+    )
+
+    await sendInstructions([
+      await program.methods
+        .updateConfiguration({ maxLps: 0 })
+        .accounts({ dexConfigurationAccount: curveConfig, authority: user.publicKey })
+        .instruction()
+    ], [user])
+  })
+
   it("Buy token", async () => {
     try {
       const [curveConfig] = PublicKey.findProgramAddressSync(
@@ -263,6 +918,10 @@ describe("bonding_curve", () => {
         [Buffer.from(SOL_VAULT_PREFIX), mint1.toBuffer()],
         program.programId
       )
+      const [userState] = PublicKey.findProgramAddressSync(
+        [Buffer.from(USER_STATE_SEED), poolPda.toBuffer(), user.publicKey.toBuffer()],
+        program.programId
+      )
       const tx = new Transaction()
         .add(
           ComputeBudgetProgram.setComputeUnitLimit({ units: 200_000 }),
@@ -276,6 +935,16 @@ describe("bonding_curve", () => {
               poolTokenAccount: poolToken,
               userTokenAccount: userAta1,
               dexConfigurationAccount: curveConfig,
+              userState,
+              creator: user.publicKey,
+              rewardMint: null,
+              rewardVault: null,
+              userRewardAccount: null,
+              referrer: null,
+              recipientTokenAccount: null,
+              recipientState: null,
+              whitelistEntry: null,
+              testClock: null,
               user: user.publicKey,
               tokenProgram: TOKEN_PROGRAM_ID,
               associatedTokenProgram: ASSOCIATED_PROGRAM_ID,
@@ -295,6 +964,32 @@ describe("bonding_curve", () => {
     }
   })
 
+  it("Swaps refuse a program that is not a token program", async () => {
+    const mint = await setupPool()
+    // The associated token program in the token program slot, and the token program swapped in for it
+    for (const programs of [
+      { tokenProgram: ASSOCIATED_PROGRAM_ID },
+      { associatedTokenProgram: TOKEN_PROGRAM_ID },
+    ]) {
+      const ix = await program.methods
+        .buy(new BN(10 ** 7))
+        .accounts({ ...(await buyAccounts(mint, user.publicKey)), ...programs })
+        .instruction()
+      await expectCustomError(sendInstructions([ix], [user]), "InvalidProgramId")
+    }
+  })
+
+  it("Buy rounds the output down and keeps the pool whole", async () => {
+    const { poolPda, poolToken } = getPdas(mint1)
+    const before = await program.account.liquidityPool.fetch(poolPda)
+    await sendInstructions([await buyIx(mint1, user.publicKey, new BN(10 ** 7 + 1))], [user])
+    const after = await program.account.liquidityPool.fetch(poolPda)
+    const poolBalance = (await connection.getTokenAccountBalance(poolToken)).value.amount
+    expect(after.reserveToken.toString()).to.equal(poolBalance)
+    const charged = after.reserveSol.sub(before.reserveSol).add(after.feeSolAccrued.sub(before.feeSolAccrued))
+    expect(charged.toNumber()).to.equal(10 ** 7 + 1)
+  })
+
   it("Sell token", async () => {
     try {
       const [curveConfig] = PublicKey.findProgramAddressSync(
@@ -315,6 +1010,10 @@ describe("bonding_curve", () => {
         [Buffer.from(SOL_VAULT_PREFIX), mint1.toBuffer()],
         program.programId
       )
+      const [userState] = PublicKey.findProgramAddressSync(
+        [Buffer.from(USER_STATE_SEED), poolPda.toBuffer(), user.publicKey.toBuffer()],
+        program.programId
+      )
       const tx = new Transaction()
         .add(
           ComputeBudgetProgram.setComputeUnitLimit({ units: 200_000 }),
@@ -328,6 +1027,12 @@ describe("bonding_curve", () => {
               poolTokenAccount: poolToken,
               userTokenAccount: userAta1,
               dexConfigurationAccount: curveConfig,
+              userState,
+              rewardMint: null,
+              rewardVault: null,
+              userRewardAccount: null,
+              referrer: null,
+              testClock: null,
               user: user.publicKey,
               tokenProgram: TOKEN_PROGRAM_ID,
               associatedTokenProgram: ASSOCIATED_PROGRAM_ID,
@@ -348,54 +1053,2640 @@ describe("bonding_curve", () => {
   })
 
 
-  it("Remove liquidity", async () => {
-    try {
+  it("Liquidity shares follow the amount the pool received", async () => {
+    const mint = await setupPool()
+    const { poolPda, poolToken } = getPdas(mint)
+    const [liquidityProviderAccount] = PublicKey.findProgramAddressSync(
+      [Buffer.from(LIQUIDITY_SEED), poolPda.toBuffer(), user.publicKey.toBuffer()],
+      program.programId
+    )
+    const provider = await program.account.liquidityProvider.fetch(liquidityProviderAccount)
+    const pool = await program.account.liquidityPool.fetch(poolPda)
+    const received = (await connection.getTokenAccountBalance(poolToken)).value.amount
+    expect(provider.shares.toString()).to.equal(received)
+    expect(pool.reserveToken.toString()).to.equal(received)
+  })
 
-      const [poolPda] = PublicKey.findProgramAddressSync(
-        [Buffer.from(POOL_SEED_PREFIX), mint1.toBuffer()],
-        program.programId
-      )
+  it("A Token-2022 transfer fee leaves the pool pricing what arrived", async () => {
+    // 1% of every transfer is withheld in the receiving account
+    const mintKeypair = Keypair.generate()
+    const mint = mintKeypair.publicKey
+    const mintLen = getMintLen([ExtensionType.TransferFeeConfig])
+    await sendInstructions([
+      SystemProgram.createAccount({
+        fromPubkey: user.publicKey,
+        newAccountPubkey: mint,
+        space: mintLen,
+        lamports: await connection.getMinimumBalanceForRentExemption(mintLen),
+        programId: TOKEN_2022_PROGRAM_ID,
+      }),
+      createInitializeTransferFeeConfigInstruction(mint, user.publicKey, user.publicKey, 100, BigInt(amount.toString()), TOKEN_2022_PROGRAM_ID),
+      createInitializeMintInstruction(mint, tokenDecimal, user.publicKey, null, TOKEN_2022_PROGRAM_ID),
+    ], [user, mintKeypair])
+    const ata = (await getOrCreateAssociatedTokenAccount(connection, user, mint, user.publicKey, false, undefined, undefined, TOKEN_2022_PROGRAM_ID)).address
+    await mintTo(connection, user, mint, ata, user.publicKey, BigInt(amount.toString()), [], undefined, TOKEN_2022_PROGRAM_ID)
+    await sendInstructions([await createPoolIx(mint, user.publicKey, 0, 0, "", TOKEN_2022_PROGRAM_ID)], [user])
+    await sendInstructions([await addLiquidityIx(mint, user.publicKey, TOKEN_2022_PROGRAM_ID)], [user])
 
-      const poolToken = await getAssociatedTokenAddress(
-        mint1, poolPda, true
-      )
-      const userAta1 = await getAssociatedTokenAddress(
-        mint1, user.publicKey
-      )
-      const [poolSolVault, bump] = PublicKey.findProgramAddressSync(
-        [Buffer.from(SOL_VAULT_PREFIX), mint1.toBuffer()],
-        program.programId
-      )
+    const { curveConfig, poolPda, poolSolVault, solVaultBump, poolToken } = getPdas(mint, TOKEN_2022_PROGRAM_ID)
+    const [liquidityProviderAccount] = PublicKey.findProgramAddressSync(
+      [Buffer.from(LIQUIDITY_SEED), poolPda.toBuffer(), user.publicKey.toBuffer()],
+      program.programId
+    )
+    const poolBalance = async () => new BN((await connection.getTokenAccountBalance(poolToken)).value.amount)
+    const deposited = await poolBalance()
+    expect(deposited.toString()).to.equal(amount.sub(amount.divn(100)).toString())
+    expect((await program.account.liquidityProvider.fetch(liquidityProviderAccount)).shares.toString()).to.equal(deposited.toString())
+    expect((await program.account.liquidityPool.fetch(poolPda)).reserveToken.toString()).to.equal(deposited.toString())
 
-      const tx = new Transaction()
-        .add(
-          ComputeBudgetProgram.setComputeUnitLimit({ units: 200_000 }),
-          ComputeBudgetProgram.setComputeUnitPrice({ microLamports: 200_000 }),
-          await program.methods
-            .removeLiquidity(bump)
-            .accounts({
-              pool: poolPda,
-              tokenMint: mint1,
-              poolTokenAccount: poolToken,
-              userTokenAccount: userAta1,
-              poolSolVault,
-              user: user.publicKey,
-              tokenProgram: TOKEN_PROGRAM_ID,
-              associatedTokenProgram: ASSOCIATED_PROGRAM_ID,
-              rent: SYSVAR_RENT_PUBKEY,
-              systemProgram: SystemProgram.programId
-            })
-            .instruction()
-        )
-      tx.feePayer = user.publicKey
-      tx.recentBlockhash = (await connection.getLatestBlockhash()).blockhash
-      console.log(await connection.simulateTransaction(tx))
-      const sig = await sendAndConfirmTransaction(connection, tx, [user], { skipPreflight: true })
-      console.log("Successfully added liquidity : ", `https://solscan.io/tx/${sig}?cluster=devnet`)
-    } catch (error) {
-      console.log("Error in removing liquidity", error)
+    // Buying pays out of the reserves, the buyer receives the amount less the fee
+    const token2022Accounts = {
+      poolTokenAccount: poolToken,
+      userTokenAccount: ata,
+      tokenProgram: TOKEN_2022_PROGRAM_ID,
+    }
+    await sendInstructions([
+      await program.methods
+        .buy(new BN(10 ** 8))
+        .accounts({ ...(await buyAccounts(mint, user.publicKey)), ...token2022Accounts })
+        .instruction()
+    ], [user])
+    const bought = await program.account.liquidityPool.fetch(poolPda)
+    expect(bought.reserveToken.toString()).to.equal((await poolBalance()).toString())
+
+    // Selling prices the tokens the pool received, not the amount the seller sent
+    const sent = new BN(10 ** 6).mul(new BN(10 ** tokenDecimal))
+    const sig = await sendInstructions([
+      await program.methods
+        .sell(sent, solVaultBump)
+        .accounts({
+          pool: poolPda,
+          tokenMint: mint,
+          poolSolVault,
+          dexConfigurationAccount: curveConfig,
+          userState: getUserState(mint, user.publicKey),
+          rewardMint: null,
+          rewardVault: null,
+          userRewardAccount: null,
+          referrer: null,
+          testClock: null,
+          user: user.publicKey,
+          associatedTokenProgram: ASSOCIATED_PROGRAM_ID,
+          rent: SYSVAR_RENT_PUBKEY,
+          systemProgram: SystemProgram.programId,
+          ...token2022Accounts,
+        })
+        .instruction()
+    ], [user])
+    const sold = await program.account.liquidityPool.fetch(poolPda)
+    const received = sold.reserveToken.sub(bought.reserveToken)
+    expect(received.toString()).to.equal(sent.sub(sent.divn(100)).toString())
+    expect(sold.reserveToken.toString()).to.equal((await poolBalance()).toString())
+    const swap = (await getEvents(sig)).find((e) => e.name === "SwapEvent").data
+    expect(swap.amountIn.toString()).to.equal(received.toString())
+  })
+
+  it("Providers accrue shares times seconds held", async () => {
+    const mint = await setupPool()
+    const [liquidityProviderAccount] = PublicKey.findProgramAddressSync(
+      [Buffer.from(LIQUIDITY_SEED), getPdas(mint).poolPda.toBuffer(), user.publicKey.toBuffer()],
+      program.programId
+    )
+    const funded = await program.account.liquidityProvider.fetch(liquidityProviderAccount)
+    expect(funded.sharesSeconds.toNumber()).to.equal(0)
+
+    if (!process.env.TESTING_FEATURE) {
+      return
+    }
+    // Claiming brings the counter up to date without changing the shares
+    let previous = funded
+    for (const elapsed of [2, 4]) {
+      await setTestClock(previous.sharesUpdatedTs.addn(elapsed))
+      await sendInstructions([await claimLpFeesIx(mint, user.publicKey, testClock)], [user])
+      const provider = await program.account.liquidityProvider.fetch(liquidityProviderAccount)
+      expect(provider.sharesUpdatedTs.sub(previous.sharesUpdatedTs).toNumber()).to.equal(elapsed)
+      expect(provider.sharesSeconds.sub(previous.sharesSeconds).toString()).to.equal(funded.shares.muln(elapsed).toString())
+      previous = provider
     }
   })
-});
 
+  it("Splitting a provider moves shares and their unclaimed fees", async () => {
+    const mint = await setupPool()
+    const { curveConfig, poolPda } = getPdas(mint)
+    const providerAccount = (owner: PublicKey) => PublicKey.findProgramAddressSync(
+      [Buffer.from(LIQUIDITY_SEED), poolPda.toBuffer(), owner.toBuffer()],
+      program.programId
+    )[0]
+    const splitProviderIx = (recipient: PublicKey, shares: BN) => program.methods
+      .splitProvider(shares)
+      .accounts({
+        dexConfigurationAccount: curveConfig,
+        pool: poolPda,
+        liquidityProviderAccount: providerAccount(user.publicKey),
+        recipientProviderAccount: providerAccount(recipient),
+        user: user.publicKey,
+        recipient,
+        systemProgram: SystemProgram.programId
+      })
+      .instruction()
+    const claimed = async (provider: Keypair) => {
+      const sig = await sendInstructions([await claimLpFeesIx(mint, provider.publicKey)], [provider])
+      const event = (await getEvents(sig)).find((e) => e.name === "LpFeesClaimed")
+      return event ? (event.data.amount as BN).toNumber() : 0
+    }
+
+    await sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 8))], [user])
+    const before = await program.account.liquidityPool.fetch(poolPda)
+    const { shares } = await program.account.liquidityProvider.fetch(providerAccount(user.publicKey))
+    const half = shares.divn(2)
+
+    await expectCustomError(
+      sendInstructions([await splitProviderIx(user2.publicKey, shares.addn(1))], [user, user2]),
+      "InsufficientShares"
+    )
+    await expectCustomError(
+      sendInstructions([await splitProviderIx(user.publicKey, half)], [user]),
+      "SplitToSelf"
+    )
+    await sendInstructions([await splitProviderIx(user2.publicKey, half)], [user, user2])
+
+    const kept = await program.account.liquidityProvider.fetch(providerAccount(user.publicKey))
+    const moved = await program.account.liquidityProvider.fetch(providerAccount(user2.publicKey))
+    const after = await program.account.liquidityPool.fetch(poolPda)
+    expect(kept.shares.add(moved.shares).toString()).to.equal(shares.toString())
+    expect(moved.shares.toString()).to.equal(half.toString())
+    expect(after.totalShares.toString()).to.equal(before.totalShares.toString())
+    expect(after.lpCount).to.equal(before.lpCount + 1)
+
+    // Fees earned before the split are shared like the shares
+    const fees = before.feeSolAccrued.toNumber()
+    expect(await claimed(user)).to.be.closeTo(fees / 2, 1)
+    expect(await claimed(user2)).to.be.closeTo(fees / 2, 1)
+  })
+
+  it("close_liquidity_provider pays the pending fees and closes the provider", async () => {
+    const mint = await setupPool()
+    const { poolPda, poolSolVault } = getPdas(mint)
+    const [liquidityProviderAccount] = PublicKey.findProgramAddressSync(
+      [Buffer.from(LIQUIDITY_SEED), poolPda.toBuffer(), user.publicKey.toBuffer()],
+      program.programId
+    )
+    await sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 8))], [user])
+    const before = await program.account.liquidityPool.fetch(poolPda)
+    const { shares } = await program.account.liquidityProvider.fetch(liquidityProviderAccount)
+    const vaultBefore = await connection.getBalance(poolSolVault)
+
+    const ix = await program.methods
+      .closeLiquidityProvider()
+      .accounts({
+        pool: poolPda,
+        liquidityProviderAccount,
+        tokenMint: mint,
+        poolSolVault,
+        user: user.publicKey,
+        systemProgram: SystemProgram.programId
+      })
+      .instruction()
+    const sig = await sendInstructions([ix], [user])
+    const event = (await getEvents(sig)).find((e) => e.name === "ProviderExited").data
+    const after = await program.account.liquidityPool.fetch(poolPda)
+
+    // The only provider is owed every fee the pool accrued
+    expect(event.shares.toString()).to.equal(shares.toString())
+    expect(event.fees.toNumber()).to.be.closeTo(before.feeSolAccrued.toNumber(), 1)
+    expect(vaultBefore - await connection.getBalance(poolSolVault)).to.equal(event.fees.toNumber())
+    expect(after.totalShares.toString()).to.equal(before.totalShares.sub(shares).toString())
+    expect(after.lpCount).to.equal(before.lpCount - 1)
+    expect(await connection.getAccountInfo(liquidityProviderAccount)).to.equal(null)
+  })
+
+  it("Reject adding liquidity without the whole deposit", async () => {
+    const mint = await setupPool()
+    // user2 has a token account but holds none of the mint
+    await getOrCreateAssociatedTokenAccount(connection, user2, mint, user2.publicKey)
+    await expectCustomError(
+      sendInstructions([await addLiquidityIx(mint, user2.publicKey)], [user2]),
+      "InvalidAmount"
+    )
+
+    // The first deposit into an unfunded pool is refused the same way when part of the supply is elsewhere
+    const unfunded = await createMint(connection, user, user.publicKey, null, tokenDecimal)
+    const ata = (await getOrCreateAssociatedTokenAccount(connection, user, unfunded, user.publicKey)).address
+    await mintTo(connection, user, unfunded, ata, user.publicKey, BigInt(amount.toString()))
+    const elsewhere = (await getOrCreateAssociatedTokenAccount(connection, user, unfunded, user2.publicKey)).address
+    await transfer(connection, user, ata, elsewhere, user, BigInt(1))
+    await sendInstructions([await createPoolIx(unfunded, user.publicKey, 0, 0)], [user])
+    await expectCustomError(
+      sendInstructions([await addLiquidityIx(unfunded, user.publicKey)], [user]),
+      "InvalidAmount"
+    )
+  })
+
+  it("Swap and liquidity events carry reserve snapshots", async () => {
+    const mint = await createMint(connection, user, user.publicKey, null, tokenDecimal)
+    const ata = (await getOrCreateAssociatedTokenAccount(connection, user, mint, user.publicKey)).address
+    await mintTo(connection, user, mint, ata, user.publicKey, BigInt(amount.toString()))
+    await sendInstructions([await createPoolIx(mint, user.publicKey)], [user])
+    const { poolPda } = getPdas(mint)
+
+    const addSig = await sendInstructions([await addLiquidityIx(mint, user.publicKey)], [user])
+    const added = (await getEvents(addSig)).find((e) => e.name === "LiquidityAdded").data
+    expect(added.reserveTokenBefore.toNumber()).to.equal(0)
+    expect(added.totalSupplyBefore.toNumber()).to.equal(0)
+
+    const buySig = await sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 8))], [user])
+    const swap = (await getEvents(buySig)).find((e) => e.name === "SwapEvent").data
+    const pool = await program.account.liquidityPool.fetch(poolPda)
+    expect(swap.reserveTokenBefore.toString()).to.equal(added.reserveTokenAfter.toString())
+    expect(swap.reserveSolBefore.toString()).to.equal(added.reserveSolAfter.toString())
+    expect(swap.reserveTokenAfter.toString()).to.equal(pool.reserveToken.toString())
+    expect(swap.reserveSolAfter.toString()).to.equal(pool.reserveSol.toString())
+    expect(swap.reserveTokenBefore.sub(swap.amountOut).toString()).to.equal(pool.reserveToken.toString())
+    expect(swap.totalSupply.toString()).to.equal(pool.totalSupply.toString())
+  })
+
+  it("Two-hop quote matches the realized sell then buy", async () => {
+    const mintA = await setupPool()
+    const mintB = await setupPool()
+    const poolA = getPdas(mintA).poolPda
+    await sendInstructions([await buyIx(mintA, user.publicKey, new BN(10 ** 9))], [user])
+    const tokensIn = new BN((await connection.getTokenAccountBalance(getAssociatedTokenAddressSync(mintA, user.publicKey))).value.amount)
+
+    const quote = async (legs: { mint: PublicKey, direction: number }[]) => {
+      const sig = await sendInstructions([await simulateSwapPathIx(tokensIn, legs)], [user])
+      return (await getEvents(sig)).find((e) => e.name === "SwapPathQuote").data.amountOut as BN
+    }
+    const solOut = await quote([{ mint: mintA, direction: 1 }])
+    const tokensOut = await quote([{ mint: mintA, direction: 1 }, { mint: mintB, direction: 0 }])
+
+    const before = await program.account.liquidityPool.fetch(poolA)
+    await sendInstructions([await sellIx(mintA, user.publicKey, tokensIn)], [user])
+    const after = await program.account.liquidityPool.fetch(poolA)
+    const received = before.reserveSol.sub(after.reserveSol).sub(after.feeSolAccrued.sub(before.feeSolAccrued))
+    expect(received.toString()).to.equal(solOut.toString())
+
+    await sendInstructions([await buyIx(mintB, user.publicKey, received)], [user])
+    const balanceB = (await connection.getTokenAccountBalance(getAssociatedTokenAddressSync(mintB, user.publicKey))).value.amount
+    expect(balanceB).to.equal(tokensOut.toString())
+  })
+
+  it("Buy past the end of the curve fills the remaining supply", async () => {
+    // 100k tokens left on the curve cost about 0.16 SOL
+    const mint = await setupPool(0, 0, new BN(10).pow(new BN(14)))
+    const { poolPda } = getPdas(mint)
+    const before = await program.account.liquidityPool.fetch(poolPda)
+    await sendInstructions([await buyIx(mint, user.publicKey, new BN(5 * 10 ** 8))], [user])
+    const after = await program.account.liquidityPool.fetch(poolPda)
+
+    expect(after.reserveToken.toNumber()).to.equal(0)
+    const balance = (await connection.getTokenAccountBalance(getAssociatedTokenAddressSync(mint, user.publicKey))).value.amount
+    expect(balance).to.equal(before.reserveToken.toString())
+    const charged = after.reserveSol.sub(before.reserveSol).add(after.feeSolAccrued.sub(before.feeSolAccrued))
+    expect(charged.toNumber()).to.be.lessThan(5 * 10 ** 8)
+  })
+
+  it("Buys stop at the migration reserve and graduate the curve", async () => {
+    // 20% of the 100k funded tokens are held back for migration
+    await sendInstructions([await updateConfigurationIx({ migrationReserveBps: 2000 })], [user])
+    const mint = await setupPool(0, 0, new BN(10).pow(new BN(14)))
+    await sendInstructions([await updateConfigurationIx({ migrationReserveBps: 0 })], [user])
+    const { poolPda } = getPdas(mint)
+    const reserved = new BN(2).mul(new BN(10).pow(new BN(13)))
+    expect((await program.account.liquidityPool.fetch(poolPda)).reservedForMigration.toString()).to.equal(reserved.toString())
+
+    await sendInstructions([await buyIx(mint, user.publicKey, new BN(5 * 10 ** 8))], [user])
+    const pool = await program.account.liquidityPool.fetch(poolPda)
+    expect(pool.reserveToken.toString()).to.equal(reserved.toString())
+    expect(pool.completed).to.equal(true)
+    await expectCustomError(
+      sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 7))], [user]),
+      "CurveComplete"
+    )
+  })
+
+  it("Launch sell fee decays to the normal fee", async () => {
+    const mint = await setupPool(5000, 20)
+    const { poolPda } = getPdas(mint)
+    await sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 9))], [user])
+
+    // Fee charged by a sell, in basis points of the curve output
+    const sellFeeBps = async (clock: PublicKey | null = null) => {
+      const before = await program.account.liquidityPool.fetch(poolPda)
+      await sendInstructions([await sellIx(mint, user.publicKey, new BN(10 ** 9).mul(new BN(10 ** tokenDecimal)), null, null, clock)], [user])
+      const after = await program.account.liquidityPool.fetch(poolPda)
+      const fee = after.feeSolAccrued.sub(before.feeSolAccrued)
+      const amountOut = before.reserveSol.sub(after.reserveSol)
+      return fee.muln(10_000).div(amountOut).toNumber()
+    }
+
+    expect(await sellFeeBps()).to.be.closeTo(5000, 500)
+    if (!process.env.TESTING_FEATURE) {
+      return
+    }
+    // Halfway through the window the fee is halfway down, at the end of it the normal fee is left
+    const { tradeStartTs } = await program.account.liquidityPool.fetch(poolPda)
+    await setTestClock(tradeStartTs.addn(10))
+    expect(await sellFeeBps(testClock)).to.be.closeTo(2550, 1)
+    await setTestClock(tradeStartTs.addn(20))
+    expect(await sellFeeBps(testClock)).to.be.closeTo(100, 1)
+  })
+
+  it("Swap fee follows the configured schedule", async () => {
+    const mint = await setupPool()
+    const { poolPda } = getPdas(mint)
+    const buyFeeBps = async (clock: PublicKey | null = null) => {
+      const before = await program.account.liquidityPool.fetch(poolPda)
+      await sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 8), null, null, clock)], [user])
+      const after = await program.account.liquidityPool.fetch(poolPda)
+      return after.feeSolAccrued.sub(before.feeSolAccrued).muln(10_000).divn(10 ** 8).toNumber()
+    }
+
+    const now = await connection.getBlockTime(await connection.getSlot())
+    await expectCustomError(
+      sendInstructions([await updateConfigurationIx({
+        feeSchedule: [{ startTs: new BN(now + 5), feeBps: 500 }, { startTs: new BN(now), feeBps: 200 }]
+      })], [user]),
+      "InvalidFeeSchedule"
+    )
+    await sendInstructions([await updateConfigurationIx({
+      feeSchedule: [{ startTs: new BN(now - 1), feeBps: 200 }, { startTs: new BN(now + 5), feeBps: 500 }]
+    })], [user])
+
+    expect(await buyFeeBps()).to.equal(200)
+    // The second tier starts on the test clock, not five seconds later on the validator
+    if (process.env.TESTING_FEATURE) {
+      await setTestClock(now + 4)
+      expect(await buyFeeBps(testClock)).to.equal(200)
+      await setTestClock(now + 5)
+      expect(await buyFeeBps(testClock)).to.equal(500)
+    }
+    await sendInstructions([await updateConfigurationIx({ feeSchedule: [] })], [user])
+  })
+
+  it("Large trades pay the reduced fee", async () => {
+    const mint = await setupPool()
+    const { poolPda } = getPdas(mint)
+    const buyFeeBps = async (lamports: number) => {
+      const before = await program.account.liquidityPool.fetch(poolPda)
+      await sendInstructions([await buyIx(mint, user.publicKey, new BN(lamports))], [user])
+      const after = await program.account.liquidityPool.fetch(poolPda)
+      return after.feeSolAccrued.sub(before.feeSolAccrued).muln(10_000).divn(lamports).toNumber()
+    }
+
+    await sendInstructions([await updateConfigurationIx({ largeTradeThreshold: new BN(10 ** 8), largeTradeFeeBps: 30 })], [user])
+    expect(await buyFeeBps(10 ** 8 - 1)).to.equal(99)
+    expect(await buyFeeBps(10 ** 8)).to.equal(30)
+    await sendInstructions([await updateConfigurationIx({ largeTradeThreshold: new BN(0) })], [user])
+  })
+
+  it("Dynamic fee rises with recent volatility and decays back", async () => {
+    const mint = await setupPool()
+    const buyFeeBps = async (lamports: number, clock: PublicKey | null = null) => {
+      const sig = await sendInstructions([await buyIx(mint, user.publicKey, new BN(lamports), null, null, clock)], [user])
+      const { fee } = (await getEvents(sig)).find((e) => e.name === "SwapEvent").data
+      return fee.muln(10_000).divn(lamports).toNumber()
+    }
+
+    await sendInstructions([await updateConfigurationIx({ dynamicFeeMinBps: 100, dynamicFeeMaxBps: 1000, volatilityWindowSecs: 8 })], [user])
+    expect(await buyFeeBps(10 ** 7)).to.be.closeTo(100, 1)
+
+    // A buy multiplying the spot price leaves the pool at the maximum fee
+    await buyFeeBps(10 ** 9)
+    expect(await buyFeeBps(10 ** 7)).to.be.closeTo(1000, 1)
+
+    // A window after the last move on the test clock, the accumulator has decayed away
+    if (process.env.TESTING_FEATURE) {
+      const { volatilityUpdatedTs } = await program.account.liquidityPool.fetch(getPdas(mint).poolPda)
+      await setTestClock(volatilityUpdatedTs.addn(8))
+      expect(await buyFeeBps(10 ** 7, testClock)).to.be.closeTo(100, 1)
+    }
+    await sendInstructions([await updateConfigurationIx({ dynamicFeeMinBps: 0, dynamicFeeMaxBps: 0, volatilityWindowSecs: 0 })], [user])
+  })
+
+  it("Dump tax grows with the share of the SOL reserve a sell takes", async () => {
+    const mint = await setupPool()
+    const userAta = getAssociatedTokenAddressSync(mint, user.publicKey)
+    await sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 9))], [user])
+    const bought = new BN((await getAccount(connection, userAta)).amount.toString())
+    await sendInstructions([await updateConfigurationIx({ dumpTaxMaxBps: 5000 })], [user])
+    await waitForNextSlot()
+
+    const sellFeeBps = async (tokens: BN) => {
+      const sig = await sendInstructions([await sellIx(mint, user.publicKey, tokens)], [user])
+      const swap = (await getEvents(sig)).find((e) => e.name === "SwapEvent").data
+      const gross = swap.amountOut.add(swap.fee)
+      const expected = 100 + gross.muln(5000).div(swap.reserveSolBefore).toNumber()
+      const charged = swap.fee.muln(10_000).div(gross).toNumber()
+      expect(charged).to.be.within(expected - 1, expected)
+      return charged
+    }
+
+    const small = await sellFeeBps(bought.divn(100))
+    const large = await sellFeeBps(bought.divn(2))
+    // A hundredth of the position barely moves the tax, half of it takes most of the reserve
+    expect(small).to.be.lessThan(400)
+    expect(large).to.be.greaterThan(small + 1000)
+    await sendInstructions([await updateConfigurationIx({ dumpTaxMaxBps: 0 })], [user])
+  })
+
+  it("Swaps pay the reward token until the vault runs dry", async () => {
+    const mint = await setupPool()
+    const { curveConfig } = getPdas(mint)
+    const rewardMint = await createMint(connection, user, user.publicKey, null, tokenDecimal)
+    const vault = (await getOrCreateAssociatedTokenAccount(connection, user, rewardMint, curveConfig, true)).address
+    const userReward = (await getOrCreateAssociatedTokenAccount(connection, user, rewardMint, user.publicKey)).address
+    await mintTo(connection, user, rewardMint, vault, user.publicKey, 150)
+    await sendInstructions([await updateConfigurationIx({ rewardMint, rewardPerSwap: new BN(100) })], [user])
+
+    const rewardBalance = async () => Number((await getAccount(connection, userReward)).amount)
+    await sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 7), rewardMint)], [user])
+    expect(await rewardBalance()).to.equal(100)
+    await sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 7), rewardMint)], [user])
+    expect(await rewardBalance()).to.equal(150)
+
+    // The empty vault pays nothing but the swap still goes through
+    const sig = await sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 7), rewardMint)], [user])
+    expect((await getEvents(sig)).some((e) => e.name === "SwapEvent")).to.equal(true)
+    expect(await rewardBalance()).to.equal(150)
+    await sendInstructions([await updateConfigurationIx({ rewardMint: PublicKey.default, rewardPerSwap: new BN(0) })], [user])
+  })
+
+  it("Swaps beyond the per slot limit are rejected", async () => {
+    const mint = await setupPool()
+    await sendInstructions([await updateConfigurationIx({ maxSwapsPerSlot: 1 })], [user])
+
+    // Both buys land in one transaction, so in one slot
+    await expectCustomError(
+      sendInstructions([
+        await buyIx(mint, user.publicKey, new BN(10 ** 7)),
+        await buyIx(mint, user.publicKey, new BN(2 * 10 ** 7)),
+      ], [user]),
+      "SlotSwapLimitReached"
+    )
+
+    await sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 7))], [user])
+    await waitForNextSlot()
+    await sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 7))], [user])
+    await sendInstructions([await updateConfigurationIx({ maxSwapsPerSlot: 0 })], [user])
+  })
+
+  it("Trades on a pool are spaced by the minimum slot spacing", async () => {
+    const mint = await setupPool()
+    const spacing = 10
+    await sendInstructions([await updateConfigurationIx({ minTradeSlotSpacing: spacing })], [user])
+
+    const sig = await sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 7))], [user])
+    const tradeSlot = (await connection.getTransaction(sig, { commitment: "confirmed", maxSupportedTransactionVersion: 0 })).slot
+    // The next slots belong to the previous trade, whoever trades
+    await expectCustomError(
+      sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 7))], [user]),
+      "TradeTooSoon"
+    )
+
+    await waitForSlot(tradeSlot + spacing)
+    await sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 7))], [user])
+    await sendInstructions([await updateConfigurationIx({ minTradeSlotSpacing: 0 })], [user])
+  })
+
+  it("Pausing records and emits its reason", async () => {
+    const mint = await setupPool()
+    const { curveConfig } = getPdas(mint)
+    const accounts = { dexConfigurationAccount: curveConfig, testClock: null, authority: user.publicKey }
+    const setPausedIx = (paused: boolean, reason: number) =>
+      program.methods.setPaused(paused, reason).accounts(accounts).instruction()
+
+    await expectCustomError(sendInstructions([await setPausedIx(true, 5)], [user]), "InvalidPauseReason")
+
+    // 2 is a security pause
+    const sig = await sendInstructions([await setPausedIx(true, 2)], [user])
+    const event = (await getEvents(sig)).find((e) => e.name === "PauseUpdated").data
+    expect(event.paused).to.equal(true)
+    expect(event.pauseReason).to.equal(2)
+    expect((await program.account.curveConfiguration.fetch(curveConfig)).pauseReason).to.equal(2)
+
+    // Unpausing clears the reason
+    await sendInstructions([await setPausedIx(false, 0)], [user])
+    const config = await program.account.curveConfiguration.fetch(curveConfig)
+    expect(config.paused).to.equal(false)
+    expect(config.pauseReason).to.equal(0)
+  })
+
+  it("Buy delivers the tokens to a separate recipient", async () => {
+    const mint = await setupPool()
+    const otherMint = await setupPool()
+    const recipient = await getOrCreateAssociatedTokenAccount(connection, user, mint, user2.publicKey)
+    const wrongMint = await getOrCreateAssociatedTokenAccount(connection, user, otherMint, user2.publicKey)
+    const buyerAta = getAssociatedTokenAddressSync(mint, user.publicKey)
+    const buyerBefore = (await getAccount(connection, buyerAta)).amount
+
+    await expectCustomError(
+      sendInstructions([
+        await program.methods
+          .buy(new BN(10 ** 8))
+          .accounts({ ...(await buyAccounts(mint, user.publicKey)), recipientTokenAccount: wrongMint.address, recipientState: getUserState(mint, user2.publicKey) })
+          .instruction()
+      ], [user]),
+      "InvalidMint"
+    )
+
+    await expectCustomError(
+      sendInstructions([
+        await program.methods
+          .buy(new BN(10 ** 8))
+          .accounts({ ...(await buyAccounts(mint, user.publicKey)), recipientTokenAccount: getPdas(mint).poolToken, recipientState: getUserState(mint, getPdas(mint).poolPda) })
+          .instruction()
+      ], [user]),
+      "DuplicateTokenAccount"
+    )
+
+    // The recipient's sell guards have to see the buy
+    await expectCustomError(
+      sendInstructions([
+        await program.methods
+          .buy(new BN(10 ** 8))
+          .accounts({ ...(await buyAccounts(mint, user.publicKey)), recipientTokenAccount: recipient.address })
+          .instruction()
+      ], [user]),
+      "RecipientStateMissing"
+    )
+
+    const sig = await sendInstructions([
+      await program.methods
+        .buy(new BN(10 ** 8))
+        .accounts({
+          ...(await buyAccounts(mint, user.publicKey)),
+          recipientTokenAccount: recipient.address,
+          recipientState: getUserState(mint, user2.publicKey)
+        })
+        .instruction()
+    ], [user])
+    const { amountIn, amountOut, recipient: eventRecipient } = (await getEvents(sig)).find((e) => e.name === "SwapEvent").data
+    expect(eventRecipient.toBase58()).to.equal(user2.publicKey.toBase58())
+    expect((await getAccount(connection, recipient.address)).amount).to.equal(BigInt(amountOut.toString()))
+    expect((await getAccount(connection, buyerAta)).amount).to.equal(buyerBefore)
+
+    // The buy is stamped on the recipient, who now holds the tokens, and not on the payer
+    const recipientState = await program.account.userState.fetch(getUserState(mint, user2.publicKey))
+    expect(recipientState.lastBuyTs.toNumber()).to.be.greaterThan(0)
+    expect(recipientState.buyVolume.toString()).to.equal(amountIn.toString())
+    expect((await program.account.userState.fetch(getUserState(mint, user.publicKey))).buyVolume.toNumber()).to.equal(0)
+  })
+
+  it("Pool counts the lifetime fees of its swaps", async () => {
+    const mint = await setupPool()
+    const { poolPda } = getPdas(mint)
+    const userAta = getAssociatedTokenAddressSync(mint, user.publicKey)
+    let fees = new BN(0)
+
+    for (const lamports of [10 ** 7, 3 * 10 ** 7, 10 ** 8]) {
+      const sig = await sendInstructions([await buyIx(mint, user.publicKey, new BN(lamports))], [user])
+      fees = fees.add((await getEvents(sig)).find((e) => e.name === "SwapEvent").data.fee)
+    }
+    await waitForNextSlot()
+    const bought = new BN((await getAccount(connection, userAta)).amount.toString())
+    for (const tokens of [bought.divn(4), bought.divn(2)]) {
+      const sig = await sendInstructions([await sellIx(mint, user.publicKey, tokens)], [user])
+      fees = fees.add((await getEvents(sig)).find((e) => e.name === "SwapEvent").data.fee)
+    }
+
+    const { totalFeesCollected } = await program.account.liquidityPool.fetch(poolPda)
+    expect(totalFeesCollected.toString()).to.equal(fees.toString())
+  })
+
+  it("Protocol seeds new pools from its treasury", async () => {
+    const treasury = getProtocolTreasury()
+    const seed = 10 ** 8
+    await sendInstructions([
+      SystemProgram.transfer({ fromPubkey: user.publicKey, toPubkey: treasury, lamports: 3 * seed })
+    ], [user])
+    await sendInstructions([await updateConfigurationIx({ protocolSeedAmount: new BN(seed), protocolSeedBudget: new BN(2 * seed) })], [user])
+
+    const newMint = async () => {
+      const mint = await createMint(connection, user, user.publicKey, null, tokenDecimal)
+      const ata = (await getOrCreateAssociatedTokenAccount(connection, user, mint, user.publicKey)).address
+      await mintTo(connection, user, mint, ata, user.publicKey, BigInt(amount.toString()))
+      return mint
+    }
+    const mint = await newMint()
+    const { curveConfig, poolPda, poolSolVault } = getPdas(mint)
+    const treasuryBefore = await connection.getBalance(treasury)
+    const sig = await sendInstructions([await createPoolIx(mint, user.publicKey)], [user])
+
+    // The seed is valued at the launch price of 800_000 lamports per whole token, the pool holds the shares
+    const shares = new BN(seed).mul(new BN(10 ** 9)).divn(800_000)
+    const event = (await getEvents(sig)).find((e) => e.name === "ProtocolLiquiditySeeded").data
+    expect(event.amount.toNumber()).to.equal(seed)
+    expect(event.shares.toString()).to.equal(shares.toString())
+    expect(treasuryBefore - await connection.getBalance(treasury)).to.equal(seed)
+    expect(await connection.getBalance(poolSolVault)).to.equal(seed)
+    let pool = await program.account.liquidityPool.fetch(poolPda)
+    expect(pool.protocolShares.toString()).to.equal(shares.toString())
+    expect(pool.totalShares.toString()).to.equal(shares.toString())
+    expect(pool.protocolSeedSol.toNumber()).to.equal(seed)
+    expect((await program.account.curveConfiguration.fetch(curveConfig)).protocolSeedBudget.toNumber()).to.equal(seed)
+
+    // The seed alone is nothing to trade against
+    await expectCustomError(
+      sendInstructions([await buyIx(mint, user2.publicKey, new BN(10 ** 6))], [user2]),
+      "PoolNotSeeded"
+    )
+
+    // The first funding keeps the seed in the reserve, removing the liquidity sends it back to the treasury
+    await sendInstructions([await addLiquidityIx(mint, user.publicKey)], [user])
+    expect((await program.account.liquidityPool.fetch(poolPda)).reserveSol.toNumber()).to.equal(seed + 10 ** 7)
+    const treasuryFunded = await connection.getBalance(treasury)
+    await sendInstructions([await removeLiquidityIx(mint, user.publicKey)], [user])
+    expect(await connection.getBalance(treasury) - treasuryFunded).to.equal(seed)
+    pool = await program.account.liquidityPool.fetch(poolPda)
+    expect(pool.protocolSeedSol.toNumber()).to.equal(0)
+    expect(pool.protocolShares.toNumber()).to.equal(0)
+
+    // The authority takes the seed back from a pool nobody funded, the budget then runs out
+    const unfunded = await newMint()
+    await sendInstructions([await createPoolIx(unfunded, user.publicKey)], [user])
+    const unfundedPdas = getPdas(unfunded)
+    expect((await program.account.liquidityPool.fetch(unfundedPdas.poolPda)).protocolSeedSol.toNumber()).to.equal(seed)
+    const treasuryUnfunded = await connection.getBalance(treasury)
+    await sendInstructions([
+      await program.methods
+        .redeemProtocolSeed()
+        .accounts({
+          dexConfigurationAccount: curveConfig,
+          pool: unfundedPdas.poolPda,
+          poolSolVault: unfundedPdas.poolSolVault,
+          protocolTreasury: treasury,
+          authority: user.publicKey,
+          systemProgram: SystemProgram.programId
+        })
+        .instruction()
+    ], [user])
+    expect(await connection.getBalance(treasury) - treasuryUnfunded).to.equal(seed)
+
+    const unseeded = await newMint()
+    await sendInstructions([await createPoolIx(unseeded, user.publicKey)], [user])
+    expect((await program.account.liquidityPool.fetch(getPdas(unseeded).poolPda)).protocolSeedSol.toNumber()).to.equal(0)
+    await sendInstructions([await updateConfigurationIx({ protocolSeedAmount: new BN(0) })], [user])
+  })
+
+  it("Swap fee splits always add up to the total fee", async () => {
+    const mint = await setupPool()
+    const { poolPda, poolSolVault, solVaultBump } = getPdas(mint)
+    await sendInstructions([await updateConfigurationIx({ creatorFeeShareBps: 3333, referralFeeShareBps: 1111 })], [user])
+
+    const amounts = [1_000_003, 1_234_567, 2_999_999, 5_000_001, 7_777_777, 9_999_991, 13_131_313, 20_000_009]
+    for (const [index, lamports] of amounts.entries()) {
+      // Every other buy has no referrer, its referral share stays with the protocol
+      const referrer = index % 2 === 0 ? user2.publicKey : null
+      const before = await program.account.liquidityPool.fetch(poolPda)
+      const referrerBefore = await connection.getBalance(user2.publicKey)
+      const sig = await sendInstructions([await buyIx(mint, user.publicKey, new BN(lamports), null, referrer)], [user])
+      const after = await program.account.liquidityPool.fetch(poolPda)
+      const { fee } = (await getEvents(sig)).find((e) => e.name === "SwapEvent").data
+
+      const protocolFee = after.feeSolAccrued.sub(before.feeSolAccrued)
+      const creatorFee = after.creatorFeeAccrued.sub(before.creatorFeeAccrued)
+      const referralFee = new BN((await connection.getBalance(user2.publicKey)) - referrerBefore)
+      expect(protocolFee.add(creatorFee).add(referralFee).toString()).to.equal(fee.toString())
+      expect(creatorFee.toString()).to.equal(fee.muln(3333).divn(10_000).toString())
+      expect(referralFee.toString()).to.equal(referrer ? fee.muln(1111).divn(10_000).toString() : "0")
+    }
+
+    const accrued = (await program.account.liquidityPool.fetch(poolPda)).creatorFeeAccrued
+    const sig = await sendInstructions([
+      await program.methods
+        .claimCreatorFees(solVaultBump)
+        .accounts({ pool: poolPda, poolSolVault, creator: user.publicKey, systemProgram: SystemProgram.programId })
+        .instruction()
+    ], [user])
+    expect((await getEvents(sig)).find((e) => e.name === "CreatorFeesClaimed").data.amount.toString()).to.equal(accrued.toString())
+    expect((await program.account.liquidityPool.fetch(poolPda)).creatorFeeAccrued.toNumber()).to.equal(0)
+    await sendInstructions([await updateConfigurationIx({ creatorFeeShareBps: 0, referralFeeShareBps: 0 })], [user])
+  })
+
+  it("Buy burn destroys its share of every buy", async () => {
+    const mint = await setupPool()
+    const { poolPda } = getPdas(mint)
+    const userAta = getAssociatedTokenAddressSync(mint, user.publicKey)
+    await sendInstructions([await updateConfigurationIx({ buyBurnBps: 1000 })], [user])
+
+    for (const lamports of [10 ** 7, 3 * 10 ** 7]) {
+      const supplyBefore = (await connection.getTokenSupply(mint)).value.amount
+      const balanceBefore = (await getAccount(connection, userAta)).amount
+      const reserveBefore = (await program.account.liquidityPool.fetch(poolPda)).reserveToken
+      const sig = await sendInstructions([await buyIx(mint, user.publicKey, new BN(lamports))], [user])
+
+      const gross = reserveBefore.sub((await program.account.liquidityPool.fetch(poolPda)).reserveToken)
+      const burned = gross.divn(10)
+      const supplyAfter = (await connection.getTokenSupply(mint)).value.amount
+      const received = (await getAccount(connection, userAta)).amount - balanceBefore
+      expect(new BN(supplyBefore).sub(new BN(supplyAfter)).toString()).to.equal(burned.toString())
+      expect(received.toString()).to.equal(gross.sub(burned).toString())
+      const swap = (await getEvents(sig)).find((e) => e.name === "SwapEvent").data
+      expect(swap.amountOut.toString()).to.equal(received.toString())
+    }
+    await sendInstructions([await updateConfigurationIx({ buyBurnBps: 0 })], [user])
+  })
+
+  it("Protocol fee is routed to the weighted fee recipients", async () => {
+    const mint = await setupPool()
+    const { poolPda } = getPdas(mint)
+    const recipients = [Keypair.generate().publicKey, Keypair.generate().publicKey, user2.publicKey]
+    const weights = [5000, 3000, 2000]
+    // Fresh recipients need a rent exempt balance before receiving fee dust
+    await sendInstructions(recipients.slice(0, 2).map((pubkey) =>
+      SystemProgram.transfer({ fromPubkey: user.publicKey, toPubkey: pubkey, lamports: 10 ** 7 })
+    ), [user])
+    await sendInstructions([await updateConfigurationIx({
+      feeRecipients: recipients.map((recipient, index) => ({ recipient, weightBps: weights[index] }))
+    })], [user])
+
+    const recipientMetas = recipients.map((pubkey) => ({ pubkey, isSigner: false, isWritable: true }))
+    const ix = await buyIx(mint, user.publicKey, new BN(10 ** 8))
+    ix.keys.push(...recipientMetas)
+    const balancesBefore = await Promise.all(recipients.map((pubkey) => connection.getBalance(pubkey)))
+    const accruedBefore = (await program.account.liquidityPool.fetch(poolPda)).feeSolAccrued
+    const sig = await sendInstructions([ix], [user])
+    const { fee } = (await getEvents(sig)).find((e) => e.name === "SwapEvent").data
+
+    let routed = new BN(0)
+    for (const [index, pubkey] of recipients.entries()) {
+      const share = fee.muln(weights[index]).divn(10_000)
+      expect((await connection.getBalance(pubkey)) - balancesBefore[index]).to.equal(share.toNumber())
+      routed = routed.add(share)
+    }
+    // The rounding remainder stays with the liquidity providers
+    const accrued = (await program.account.liquidityPool.fetch(poolPda)).feeSolAccrued.sub(accruedBefore)
+    expect(accrued.toString()).to.equal(fee.sub(routed).toString())
+
+    // Missing recipient accounts are rejected
+    await expectCustomError(
+      sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 7))], [user]),
+      "InvalidFeeRecipients"
+    )
+    await sendInstructions([await updateConfigurationIx({ feeRecipients: [] })], [user])
+  })
+
+  it("Global slippage cap rejects a swap moving the price too far", async () => {
+    const mint = await setupPool()
+    // 0.01 SOL in gives the curve a spot price to measure impact against
+    await sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 7))], [user])
+    await sendInstructions([await updateConfigurationIx({ maxSlippageBps: 1000 })], [user])
+
+    // 0.001 SOL moves the price about 2.6%, 1 SOL about 80%
+    await sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 6))], [user])
+    await expectCustomError(
+      sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 9))], [user]),
+      "SlippageExceeded"
+    )
+    await sendInstructions([await updateConfigurationIx({ maxSlippageBps: 0 })], [user])
+  })
+
+  it("Reject a swap whose fee would consume the whole input", async () => {
+    const mint = await setupPool()
+    const now = await connection.getBlockTime(await connection.getSlot())
+    await sendInstructions([await updateConfigurationIx({ feeSchedule: [{ startTs: new BN(now - 1), feeBps: 10_000 }] })], [user])
+    await expectCustomError(
+      sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 7))], [user]),
+      "FeeExceedsInput"
+    )
+    await sendInstructions([await updateConfigurationIx({ feeSchedule: [] })], [user])
+  })
+
+  it("Swaps below the minimum amount are rejected", async () => {
+    const mint = await setupPool()
+    await sendInstructions([await updateConfigurationIx({ minSwapAmount: new BN(10 ** 6) })], [user])
+    await expectCustomError(
+      sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 6 - 1))], [user]),
+      "AmountBelowMinimum"
+    )
+    await sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 6))], [user])
+
+    await sendInstructions([await updateConfigurationIx({ minSwapAmount: new BN(0) })], [user])
+    await sendInstructions([await buyIx(mint, user.publicKey, new BN(1000))], [user])
+  })
+
+  it("Fee remainders accrue until they add up to a lamport", async () => {
+    const mint = await setupPool()
+    const { poolPda } = getPdas(mint)
+    const before = await program.account.liquidityPool.fetch(poolPda)
+
+    // 1% of 1050 lamports is 10.5, floor rounding alone would collect 10 per swap
+    for (let i = 0; i < 5; i++) {
+      await sendInstructions([
+        await buyIx(mint, user.publicKey, new BN(1050)),
+        await buyIx(mint, user.publicKey, new BN(1050))
+      ], [user])
+    }
+
+    const after = await program.account.liquidityPool.fetch(poolPda)
+    expect(after.feeSolAccrued.sub(before.feeSolAccrued).toNumber()).to.equal(105)
+    expect(after.feeDust.toNumber()).to.equal(0)
+  })
+
+  it("Trades are limited to the daily trading window", async () => {
+    const mint = await setupPool()
+    const day = 86_400
+    const secondOfDay = (await connection.getBlockTime(await connection.getSlot())) % day
+    const setWindow = (start: number, end: number) =>
+      sendInstructions([await updateConfigurationIx({
+        tradingWindowStart: ((start % day) + day) % day,
+        tradingWindowEnd: ((end % day) + day) % day
+      })], [user])
+    const buy = async () => sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 7))], [user])
+
+    await setWindow(secondOfDay - 600, secondOfDay + 600)
+    await buy()
+
+    await setWindow(secondOfDay + 600, secondOfDay + 1200)
+    await expectCustomError(buy(), "OutsideTradingWindow")
+
+    // Start after end wraps past midnight: open from now + 600 through now - 600 of the next day
+    await setWindow(secondOfDay + 600, secondOfDay - 600)
+    await expectCustomError(buy(), "OutsideTradingWindow")
+    // Open everywhere but between now + 60 and now + 600
+    await setWindow(secondOfDay + 600, secondOfDay + 60)
+    await buy()
 
+    await setWindow(0, 0)
+  })
+
+  it("Sell is blocked until the minimum hold time passes", async () => {
+    const mint = await setupPool()
+    await sendInstructions([await updateConfigurationIx({ minHoldSecs: 5 })], [user])
+    await sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 8))], [user])
+    const tokens = new BN((await connection.getTokenAccountBalance(getAssociatedTokenAddressSync(mint, user.publicKey))).value.amount)
+
+    await expectCustomError(
+      sendInstructions([await sellIx(mint, user.publicKey, tokens)], [user]),
+      "MinHoldTimeNotMet"
+    )
+    if (process.env.TESTING_FEATURE) {
+      const { lastBuyTs } = await program.account.userState.fetch(getUserState(mint, user.publicKey))
+      await setTestClock(lastBuyTs.addn(5))
+      await sendInstructions([await sellIx(mint, user.publicKey, tokens, null, null, testClock)], [user])
+    }
+    await sendInstructions([await updateConfigurationIx({ minHoldSecs: 0 })], [user])
+  })
+
+  // Run with TESTING_FEATURE=1 against a program built with `--features testing`
+  it("Hold time follows an injected test clock", async () => {
+    const mint = await setupPool()
+
+    if (!process.env.TESTING_FEATURE) {
+      await expectCustomError(sendInstructions([await setTestClockIx(0)], [user]), "TestingOnly")
+      return
+    }
+
+    const hold = 24 * 60 * 60
+    await sendInstructions([await updateConfigurationIx({ minHoldSecs: hold })], [user])
+    await sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 8))], [user])
+    const { lastBuyTs } = await program.account.userState.fetch(getUserState(mint, user.publicKey))
+    const tokens = new BN((await connection.getTokenAccountBalance(getAssociatedTokenAddressSync(mint, user.publicKey))).value.amount)
+    const sellWithClockIx = () => sellIx(mint, user.publicKey, tokens, null, null, testClock)
+
+    await setTestClock(lastBuyTs.addn(hold - 1))
+    await expectCustomError(sendInstructions([await sellWithClockIx()], [user]), "MinHoldTimeNotMet")
+
+    // A day later on the test clock, no waiting on the validator
+    const later = lastBuyTs.toNumber() + hold
+    await setTestClock(later)
+    const sellSig = await sendInstructions([await sellWithClockIx()], [user])
+    expect((await getEvents(sellSig)).find((e) => e.name === "SwapEvent").data.timestamp.toNumber()).to.equal(later)
+
+    // A buy given the clock reads it too, for its event and the hold time it starts
+    const buySig = await sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 7), null, null, testClock)], [user])
+    expect((await getEvents(buySig)).find((e) => e.name === "SwapEvent").data.timestamp.toNumber()).to.equal(later)
+    expect((await program.account.userState.fetch(getUserState(mint, user.publicKey))).lastBuyTs.toNumber()).to.equal(later)
+    await sendInstructions([await updateConfigurationIx({ minHoldSecs: 0 })], [user])
+  })
+
+  it("Swaps only accept the user's associated token account", async () => {
+    const mint = await setupPool()
+    await sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 8))], [user])
+    const ata = getAssociatedTokenAddressSync(mint, user.publicKey)
+    // Owned by the user and of the right mint, but not at the associated address
+    const other = await createAccount(connection, user, mint, user.publicKey, Keypair.generate())
+    const tokens = BigInt((await connection.getTokenAccountBalance(ata)).value.amount)
+    await transfer(connection, user, ata, other, user, tokens / BigInt(2))
+    const withTokenAccount = (ix: TransactionInstruction) => {
+      ix.keys = ix.keys.map((key) => key.pubkey.equals(ata) ? { ...key, pubkey: other } : key)
+      return ix
+    }
+
+    await expectCustomError(
+      sendInstructions([withTokenAccount(await buyIx(mint, user.publicKey, new BN(10 ** 7)))], [user]),
+      "AccountNotAssociatedTokenAccount"
+    )
+    await expectCustomError(
+      sendInstructions([withTokenAccount(await sellIx(mint, user.publicKey, new BN((tokens / BigInt(4)).toString())))], [user]),
+      "AccountNotAssociatedTokenAccount"
+    )
+  })
+
+  it("Reject a swap whose mint does not match the pool", async () => {
+    const mintA = await setupPool()
+    const mintB = await setupPool()
+    const ix = await buyIx(mintA, user.publicKey, new BN(10 ** 7))
+    const poolA = getPdas(mintA).poolPda
+    const poolB = getPdas(mintB).poolPda
+    ix.keys.find((key) => key.pubkey.equals(poolA)).pubkey = poolB
+
+    // The pool seeds bind the mint, a pool for another mint fails their check
+    await expectCustomError(sendInstructions([ix], [user]), "ConstraintSeeds")
+  })
+
+  it("Swaps on a pool without liquidity fail with PoolNotSeeded", async () => {
+    const mint = await createMint(connection, user, user.publicKey, null, tokenDecimal)
+    const ata = (await getOrCreateAssociatedTokenAccount(connection, user, mint, user.publicKey)).address
+    await mintTo(connection, user, mint, ata, user.publicKey, BigInt(amount.toString()))
+    await sendInstructions([await createPoolIx(mint, user.publicKey)], [user])
+
+    await expectCustomError(
+      sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 7))], [user]),
+      "PoolNotSeeded"
+    )
+    await sendInstructions([await addLiquidityIx(mint, user.publicKey)], [user])
+    await sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 7))], [user])
+  })
+
+  it("Virtual reserve is adjustable until the pool is funded", async () => {
+    const mint = await createMint(connection, user, user.publicKey, null, tokenDecimal)
+    const ata = (await getOrCreateAssociatedTokenAccount(connection, user, mint, user.publicKey)).address
+    await mintTo(connection, user, mint, ata, user.publicKey, BigInt(amount.toString()))
+    await sendInstructions([await createPoolIx(mint, user.publicKey)], [user])
+    const { poolPda } = getPdas(mint)
+
+    const sig = await sendInstructions([await setVirtualReserveIx(mint, new BN(10 ** 9))], [user])
+    const event = (await getEvents(sig)).find((e) => e.name === "CurveParamsUpdated").data
+    expect(event.pool.toBase58()).to.equal(poolPda.toBase58())
+    expect(event.virtualSolReserveBefore.toNumber()).to.equal(0)
+    expect(event.virtualSolReserve.toNumber()).to.equal(10 ** 9)
+    expect((await program.account.liquidityPool.fetch(poolPda)).virtualSolReserve.toNumber()).to.equal(10 ** 9)
+
+    await sendInstructions([await addLiquidityIx(mint, user.publicKey)], [user])
+    await expectCustomError(
+      sendInstructions([await setVirtualReserveIx(mint, new BN(0))], [user]),
+      "ReservesNotEmpty"
+    )
+  })
+
+  it("Reaching the graduation price completes the curve", async () => {
+    const mint = await setupPool()
+    const { poolPda } = getPdas(mint)
+    // 0.01 SOL in prices the curve around 5.5M lamports per million tokens, 0.1 SOL around 17.6M
+    await sendInstructions([await updateConfigurationIx({ graduationPrice: new BN(10 ** 7) })], [user])
+    await sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 7))], [user])
+    expect((await program.account.liquidityPool.fetch(poolPda)).completed).to.equal(false)
+
+    await sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 8))], [user])
+    expect((await program.account.liquidityPool.fetch(poolPda)).completed).to.equal(true)
+    await expectCustomError(
+      sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 7))], [user]),
+      "CurveComplete"
+    )
+    await sendInstructions([await updateConfigurationIx({ graduationPrice: new BN(0) })], [user])
+  })
+
+  it("Graduation triggers on the net SOL raised reaching the SOL target", async () => {
+    const mint = await setupPool()
+    const { poolPda } = getPdas(mint)
+    const buy = async (lamports: number) => {
+      const sig = await sendInstructions([await buyIx(mint, user.publicKey, new BN(lamports))], [user])
+      return (await getEvents(sig)).find((e) => e.name === "SwapEvent").data
+    }
+    await sendInstructions([await updateConfigurationIx({ graduationSolTarget: new BN(5 * 10 ** 7) })], [user])
+
+    // The fee stays out of the raised SOL
+    const first = await buy(3 * 10 ** 7)
+    expect(first.fee.toNumber()).to.be.greaterThan(0)
+    let pool = await program.account.liquidityPool.fetch(poolPda)
+    expect(pool.solRaised.toString()).to.equal(first.amountIn.sub(first.fee).toString())
+    expect(pool.completed).to.equal(false)
+
+    const second = await buy(3 * 10 ** 7)
+    pool = await program.account.liquidityPool.fetch(poolPda)
+    expect(pool.solRaised.toString()).to.equal(first.amountIn.sub(first.fee).add(second.amountIn).sub(second.fee).toString())
+    expect(pool.completed).to.equal(true)
+    await sendInstructions([await updateConfigurationIx({ graduationSolTarget: new BN(0) })], [user])
+  })
+
+  it("Graduation waits for the minimum number of holders", async () => {
+    const mint = await setupPool()
+    const { poolPda } = getPdas(mint)
+    const third = Keypair.generate()
+    await sendInstructions([
+      SystemProgram.transfer({ fromPubkey: user.publicKey, toPubkey: third.publicKey, lamports: 10 ** 8 })
+    ], [user])
+    await sendInstructions([await updateConfigurationIx({ graduationSolTarget: new BN(5 * 10 ** 7), minGraduationHolders: 3 })], [user])
+    const holderCount = async () => (await program.account.liquidityPool.fetch(poolPda)).holderCount
+
+    // One wallet raises past the SOL target alone, the curve stays open and refuses it more
+    await sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 8))], [user])
+    let pool = await program.account.liquidityPool.fetch(poolPda)
+    expect(pool.solRaised.toNumber()).to.be.greaterThan(5 * 10 ** 7)
+    expect(pool.holderCount).to.equal(1)
+    expect(pool.completed).to.equal(false)
+    await expectCustomError(
+      sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 6))], [user]),
+      "InsufficientHolders"
+    )
+
+    // Moving the tokens out of the wallet does not make it a new holder when it buys again
+    const userAta = getAssociatedTokenAddressSync(mint, user.publicKey)
+    const outside = await getOrCreateAssociatedTokenAccount(connection, user, mint, Keypair.generate().publicKey)
+    await transfer(connection, user, userAta, outside.address, user, (await getAccount(connection, userAta)).amount)
+    await expectCustomError(
+      sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 6))], [user]),
+      "InsufficientHolders"
+    )
+
+    // A second wallet gets in, a second token account of that wallet does not count it twice
+    await sendInstructions([await buyIx(mint, user2.publicKey, new BN(10 ** 6))], [user2])
+    expect(await holderCount()).to.equal(2)
+    const user2Other = await createAccount(connection, user, mint, user2.publicKey, Keypair.generate())
+    await expectCustomError(
+      sendInstructions([
+        await program.methods
+          .buy(new BN(10 ** 6))
+          .accounts({
+            ...(await buyAccounts(mint, user2.publicKey)),
+            recipientTokenAccount: user2Other,
+            recipientState: getUserState(mint, user2.publicKey)
+          })
+          .instruction()
+      ], [user2]),
+      "InsufficientHolders"
+    )
+
+    // Selling back what the curve paid drops the wallet, buying back counts it again
+    const user2Ata = getAssociatedTokenAddressSync(mint, user2.publicKey)
+    const balance = new BN((await getAccount(connection, user2Ata)).amount.toString())
+    await waitForNextSlot()
+    await sendInstructions([sellIx(mint, user2.publicKey, balance)], [user2])
+    expect(await holderCount()).to.equal(1)
+    await sendInstructions([await buyIx(mint, user2.publicKey, new BN(10 ** 6))], [user2])
+    expect(await holderCount()).to.equal(2)
+
+    // The third signer completes the curve
+    await sendInstructions([await buyIx(mint, third.publicKey, new BN(10 ** 6))], [third])
+    pool = await program.account.liquidityPool.fetch(poolPda)
+    expect(pool.holderCount).to.equal(3)
+    expect(pool.completed).to.equal(true)
+    await sendInstructions([await updateConfigurationIx({ graduationSolTarget: new BN(0), minGraduationHolders: 0 })], [user])
+  })
+
+  it("A graduated curve keeps trading up to the post-graduation cap", async () => {
+    const mint = await setupPool()
+    const { poolPda } = getPdas(mint)
+    await sendInstructions([await updateConfigurationIx({ graduationPrice: new BN(10 ** 7), postGraduationTradeCap: new BN(2 * 10 ** 7) })], [user])
+    await sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 8))], [user])
+    expect((await program.account.liquidityPool.fetch(poolPda)).completed).to.equal(true)
+
+    // The pool keeps the cap it graduated under, changing the configured one does not move it
+    await sendInstructions([await updateConfigurationIx({ postGraduationTradeCap: new BN(0) })], [user])
+    expect((await program.account.liquidityPool.fetch(poolPda)).postGraduationTradeCap.toNumber()).to.equal(2 * 10 ** 7)
+
+    // Buys count what the buyer pays, fee included
+    await sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 7))], [user])
+    await expectCustomError(
+      sendInstructions([await buyIx(mint, user.publicKey, new BN(2 * 10 ** 7))], [user]),
+      "PostGraduationCapReached"
+    )
+    await sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 7))], [user])
+    const pool = await program.account.liquidityPool.fetch(poolPda)
+    expect(pool.postGraduationVolume.toNumber()).to.equal(2 * 10 ** 7)
+    expect(pool.completed).to.equal(true)
+    await expectCustomError(
+      sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 7))], [user]),
+      "PostGraduationCapReached"
+    )
+    await sendInstructions([await updateConfigurationIx({ graduationPrice: new BN(0), postGraduationTradeCap: new BN(0) })], [user])
+  })
+
+  it("A buy crossing the graduation price only pays for the fill up to it", async () => {
+    const mint = await setupPool()
+    const { poolPda, poolSolVault } = getPdas(mint)
+    const graduationPrice = 10 ** 7
+    await sendInstructions([
+      await updateConfigurationIx({ graduationPrice: new BN(graduationPrice), graduationPartialFill: true })
+    ], [user])
+    const { solRemaining } = await quoteGraduation(mint)
+    const vaultBefore = await connection.getBalance(poolSolVault)
+
+    // Ten times what graduation needs, the excess never leaves the buyer
+    const sig = await sendInstructions([await buyIx(mint, user.publicKey, solRemaining.muln(10))], [user])
+    const swap = (await getEvents(sig)).find((e) => e.name === "SwapEvent").data
+    expect(swap.amountIn.toNumber()).to.be.closeTo(solRemaining.toNumber(), 2)
+    expect(await connection.getBalance(poolSolVault) - vaultBefore).to.equal(swap.amountIn.toNumber())
+
+    const pool = await program.account.liquidityPool.fetch(poolPda)
+    expect(pool.completed).to.equal(true)
+    const sold = pool.totalSupply.sub(pool.reserveToken).toNumber() / 10 ** (6 + tokenDecimal)
+    const spot = Math.sqrt(1280 * pool.virtualSolReserve.toNumber() / 10 ** 9) + sold
+    expect(spot * 2 / 1280 * 10 ** 9 / graduationPrice).to.be.closeTo(1, 1e-6)
+    await sendInstructions([
+      await updateConfigurationIx({ graduationPrice: new BN(0), graduationPartialFill: false })
+    ], [user])
+  })
+
+  it("Buys past the price cap are rejected", async () => {
+    const mint = await setupPool()
+    const { poolPda } = getPdas(mint)
+    // 0.1 SOL in prices the curve around 17.6M lamports per million tokens
+    await sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 8))], [user])
+    await sendInstructions([await updateConfigurationIx({ maxPrice: new BN(2 * 10 ** 7) })], [user])
+
+    await sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 7))], [user])
+    const reserveToken = (await program.account.liquidityPool.fetch(poolPda)).reserveToken
+    await expectCustomError(
+      sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 9))], [user]),
+      "PriceCapReached"
+    )
+    expect((await program.account.liquidityPool.fetch(poolPda)).reserveToken.toString()).to.equal(reserveToken.toString())
+    await sendInstructions([await updateConfigurationIx({ maxPrice: new BN(0) })], [user])
+  })
+
+  it("Swaps and removals cannot take a reserve below the floor", async () => {
+    const mint = await setupPool()
+    const { poolPda } = getPdas(mint)
+    await sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 8))], [user])
+    const pool = await program.account.liquidityPool.fetch(poolPda)
+    const tokens = new BN((await connection.getTokenAccountBalance(getAssociatedTokenAddressSync(mint, user.publicKey))).value.amount)
+    const setFloor = (token: BN, sol: BN) => sendInstructions([
+      await updateConfigurationIx({ minReserveFloor: { token, sol } })
+    ], [user])
+
+    await setFloor(pool.reserveToken.subn(1000), new BN(0))
+    await expectCustomError(
+      sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 7))], [user]),
+      "ReserveFloorBreached"
+    )
+
+    await setFloor(new BN(0), pool.reserveSol.subn(1000))
+    await waitForNextSlot()
+    await expectCustomError(
+      sendInstructions([await sellIx(mint, user.publicKey, tokens)], [user]),
+      "ReserveFloorBreached"
+    )
+    await expectCustomError(
+      sendInstructions([await removeLiquidityIx(mint, user.publicKey)], [user]),
+      "ReserveFloorBreached"
+    )
+    expect((await program.account.liquidityPool.fetch(poolPda)).reserveSol.toString()).to.equal(pool.reserveSol.toString())
+
+    await setFloor(new BN(0), new BN(0))
+    await sendInstructions([await sellIx(mint, user.publicKey, tokens)], [user])
+  })
+
+  it("Market cap quote is the spot price times the tokens sold", async () => {
+    const mint = await setupPool()
+    const { poolPda } = getPdas(mint)
+    const quote = async () => {
+      const ix = await program.methods.quoteMarketCap().accounts({ pool: poolPda }).instruction()
+      const sig = await sendInstructions([ix], [user])
+      return (await getEvents(sig)).find((e) => e.name === "MarketCapQuote").data
+    }
+
+    // Nothing sold, nothing priced
+    const fresh = await quote()
+    expect(fresh.tokensSold.toNumber()).to.equal(0)
+    expect(fresh.marketCap.toNumber()).to.equal(0)
+
+    await sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 8))], [user])
+    const pool = await program.account.liquidityPool.fetch(poolPda)
+    const traded = await quote()
+    expect(traded.tokensSold.toString()).to.equal(pool.totalSupply.sub(pool.reserveToken).toString())
+    // Spot price is per million whole tokens
+    const expected = traded.spotPrice.mul(traded.tokensSold).div(new BN(10).pow(new BN(6 + tokenDecimal)))
+    expect(traded.marketCap.toString()).to.equal(expected.toString())
+    expect(traded.marketCap.toNumber()).to.be.greaterThan(0)
+  })
+
+  it("Fair price is the marginal price per whole token", async () => {
+    const mint = await setupPool()
+    const { poolPda } = getPdas(mint)
+    const quote = async () => {
+      const ix = await program.methods.quoteFairPrice().accounts({ pool: poolPda }).instruction()
+      const sig = await sendInstructions([ix], [user])
+      return (await getEvents(sig)).find((e) => e.name === "FairPriceQuote").data
+    }
+
+    // The linear curve prices nothing sold at zero
+    expect((await quote()).fairPrice.toNumber()).to.equal(0)
+
+    let previous = new BN(0)
+    for (const lamports of [10 ** 7, 10 ** 8]) {
+      await sendInstructions([await buyIx(mint, user.publicKey, new BN(lamports))], [user])
+      const { fairPrice, spotPrice } = await quote()
+      // Spot price is per million whole tokens, the fair price per whole token scaled by PRICE_SCALE
+      expect(fairPrice.toString()).to.equal(spotPrice.muln(1000).toString())
+      expect(fairPrice.gt(previous)).to.equal(true)
+      previous = fairPrice
+    }
+  })
+
+  it("Price at a supply matches the marginal price a swap pays there", async () => {
+    const mint = await setupPool()
+    const { poolPda } = getPdas(mint)
+    // Client side copy of LiquidityPool::price_at_supply
+    const priceAtSupply = (pool, supply: number) => {
+      const virtualAmount = Math.sqrt(1280 * pool.virtualSolReserve.toNumber() / 10 ** 9)
+      return Math.floor((supply / 10 ** (6 + pool.tokenDecimals) + virtualAmount) * 2 / 1280 * 10 ** 9)
+    }
+
+    await sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 9))], [user])
+    const pool = await program.account.liquidityPool.fetch(poolPda)
+    const supply = pool.totalSupply.sub(pool.reserveToken).toNumber()
+    const sig = await sendInstructions([await program.methods.quoteMarketCap().accounts({ pool: poolPda }).instruction()], [user])
+    const { spotPrice } = (await getEvents(sig)).find((e) => e.name === "MarketCapQuote").data
+    expect(spotPrice.toNumber()).to.be.closeTo(priceAtSupply(pool, supply), 1)
+
+    // A small buy at that supply pays about the marginal price per million tokens
+    const buySig = await sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 5))], [user])
+    const swap = (await getEvents(buySig)).find((e) => e.name === "SwapEvent").data
+    const paid = swap.amountIn.sub(swap.fee).toNumber() / swap.amountOut.toNumber() * 10 ** (6 + pool.tokenDecimals)
+    expect(paid / priceAtSupply(pool, supply)).to.be.closeTo(1, 1e-3)
+  })
+
+  it("Graduation target is set per pool until its first buy", async () => {
+    const mint = await setupPool()
+    const { curveConfig, poolPda } = getPdas(mint)
+    const setTargetIx = (graduationPrice: number) => program.methods
+      .setGraduationTarget(new BN(graduationPrice))
+      .accounts({ dexConfigurationAccount: curveConfig, pool: poolPda, authority: user.publicKey })
+      .instruction()
+
+    // Spot price 1e7 lamports per million tokens, 6.4 million tokens in
+    await sendInstructions([await setTargetIx(10 ** 7)], [user])
+    expect((await program.account.liquidityPool.fetch(poolPda)).graduationTarget.toNumber()).to.equal(10 ** 7)
+    expect((await quoteGraduation(mint)).tokenRemaining.toNumber() / 10 ** 15).to.be.closeTo(6.4, 1e-6)
+
+    await sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 7))], [user])
+    await expectCustomError(sendInstructions([await setTargetIx(2 * 10 ** 7)], [user]), "CurveAlreadyActive")
+    expect((await program.account.liquidityPool.fetch(poolPda)).graduationTarget.toNumber()).to.equal(10 ** 7)
+  })
+
+  it("Graduation quote returns the SOL left to reach the graduation price", async () => {
+    const mint = await setupPool()
+    const { poolPda } = getPdas(mint)
+    await sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 7))], [user])
+    await sendInstructions([await updateConfigurationIx({ graduationPrice: new BN(10 ** 7) })], [user])
+
+    // Spot price 1e7 lamports per million tokens is reached at 6.4 million tokens bought
+    const pool = await program.account.liquidityPool.fetch(poolPda)
+    const bought = pool.totalSupply.sub(pool.reserveToken).toNumber() / 10 ** 15
+    const gap = (6.4 ** 2 - bought ** 2) / 1280 * 10 ** 9 * 10_000 / 9_900
+    const quote = await quoteGraduation(mint)
+    expect(quote.solRemaining.toNumber()).to.be.closeTo(gap, 10)
+    expect(quote.tokenRemaining.toNumber() / 10 ** 15).to.be.closeTo(6.4 - bought, 1e-6)
+
+    await sendInstructions([await buyIx(mint, user.publicKey, quote.solRemaining.addn(10))], [user])
+    expect((await program.account.liquidityPool.fetch(poolPda)).completed).to.equal(true)
+    expect((await quoteGraduation(mint)).solRemaining.toNumber()).to.equal(0)
+    await sendInstructions([await updateConfigurationIx({ graduationPrice: new BN(0) })], [user])
+  })
+
+  it("Graduation quote charges the fee the graduating buy pays", async () => {
+    const mint = await setupPool()
+    const { poolPda } = getPdas(mint)
+    await sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 7))], [user])
+    await sendInstructions([await updateConfigurationIx({
+      graduationPrice: new BN(10 ** 7),
+      largeTradeThreshold: new BN(10 ** 6),
+      largeTradeFeeBps: 30
+    })], [user])
+
+    // The graduating buy is a large trade, it pays 0.3% rather than the 1% swap fee
+    const pool = await program.account.liquidityPool.fetch(poolPda)
+    const bought = pool.totalSupply.sub(pool.reserveToken).toNumber() / 10 ** 15
+    const gap = (6.4 ** 2 - bought ** 2) / 1280 * 10 ** 9 * 10_000 / 9_970
+    const quote = await quoteGraduation(mint)
+    expect(quote.solRemaining.toNumber()).to.be.closeTo(gap, 10)
+
+    const sig = await sendInstructions([await buyIx(mint, user.publicKey, quote.solRemaining.addn(10))], [user])
+    const swap = (await getEvents(sig)).find((e) => e.name === "SwapEvent").data
+    expect(swap.fee.muln(10_000).div(swap.amountIn).toNumber()).to.be.closeTo(30, 1)
+    expect((await program.account.liquidityPool.fetch(poolPda)).completed).to.equal(true)
+    await sendInstructions([await updateConfigurationIx({ graduationPrice: new BN(0), largeTradeThreshold: new BN(0) })], [user])
+  })
+
+  it("Graduation simulation matches what the graduating buy pays out", async () => {
+    const mint = await setupPool()
+    const { curveConfig, poolPda } = getPdas(mint)
+    await sendInstructions([await updateConfigurationIx({
+      graduationPrice: new BN(10 ** 7),
+      creatorGraduationPayout: new BN(5 * 10 ** 6),
+      creatorFeeShareBps: 2000
+    })], [user])
+    const simulate = async () => {
+      const ix = await program.methods
+        .simulateGraduation()
+        .accounts({ dexConfigurationAccount: curveConfig, pool: poolPda })
+        .instruction()
+      const sig = await sendInstructions([ix], [user])
+      return (await getEvents(sig)).find((e) => e.name === "GraduationSimulation").data
+    }
+
+    // Stop just short of the graduation price
+    const { solRemaining } = await quoteGraduation(mint)
+    await sendInstructions([await buyIx(mint, user.publicKey, solRemaining.subn(10 ** 5))], [user])
+    const pool = await program.account.liquidityPool.fetch(poolPda)
+    expect(pool.completed).to.equal(false)
+    const simulation = await simulate()
+    expect(simulation.completed).to.equal(true)
+    // Nothing moved
+    expect((await program.account.liquidityPool.fetch(poolPda)).reserveSol.toString()).to.equal(pool.reserveSol.toString())
+
+    // The buy that graduates pays the simulated payout, the reserves differ from the simulation only by that buy
+    const sig = await sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 6))], [user])
+    const events = await getEvents(sig)
+    const completed = events.find((e) => e.name === "PoolCompleted").data
+    const swap = events.find((e) => e.name === "SwapEvent").data
+    expect(completed.creatorPayout.toString()).to.equal(simulation.creatorPayout.toString())
+    expect(completed.creatorPayout.toNumber()).to.equal(5 * 10 ** 6)
+    expect(completed.reserveSol.sub(simulation.reserveSol).toString()).to.equal(swap.amountIn.sub(swap.fee).toString())
+    expect(simulation.reserveToken.sub(completed.reserveToken).toString()).to.equal(
+      swap.reserveTokenBefore.sub(swap.reserveTokenAfter).toString()
+    )
+
+    // Graduated, there is nothing left to pay out
+    const after = await simulate()
+    expect(after.creatorPayout.toNumber()).to.equal(0)
+    expect(after.reserveSol.toString()).to.equal(completed.reserveSol.toString())
+
+    await sendInstructions([await updateConfigurationIx({
+      graduationPrice: new BN(0),
+      creatorGraduationPayout: new BN(0),
+      creatorFeeShareBps: 0
+    })], [user])
+  })
+
+  it("Migration dry run reports the first unmet precondition", async () => {
+    const canMigrate = async (mint: PublicKey, clock: PublicKey | null = null) => {
+      const { curveConfig, poolPda } = getPdas(mint)
+      const ix = await program.methods
+        .canMigrate()
+        .accounts({ dexConfigurationAccount: curveConfig, pool: poolPda, testClock: clock })
+        .instruction()
+      const sig = await sendInstructions([ix], [user])
+      const check = (await getEvents(sig)).find((e) => e.name === "MigrationCheck").data
+      expect(check.ready).to.equal(check.reason === 0)
+      return check.reason
+    }
+
+    // A pool never funded reports it has not graduated before its empty reserves
+    const empty = await createMint(connection, user, user.publicKey, null, tokenDecimal)
+    await sendInstructions([await createPoolIx(empty, user.publicKey)], [user])
+    expect(await canMigrate(empty)).to.equal(2)
+
+    const mint = await setupPool()
+    expect(await canMigrate(mint)).to.equal(2)
+
+    await sendInstructions([await updateConfigurationIx({ graduationPrice: new BN(10 ** 7) })], [user])
+    await sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 8))], [user])
+    await sendInstructions([await updateConfigurationIx({ graduationPrice: new BN(0) })], [user])
+    expect(await canMigrate(mint)).to.equal(3)
+
+    // The pool just graduated, its reserves wait out the migration time lock
+    await sendInstructions([await updateConfigurationIx({ migrationDestination: Keypair.generate().publicKey })], [user])
+    expect(await canMigrate(mint)).to.equal(4)
+
+    if (process.env.TESTING_FEATURE) {
+      // An auction graduating the pool at settlement holds the migration until every committer claimed
+      await sendInstructions([await updateConfigurationIx({ launchAuctionSecs: 10, graduationPrice: new BN(10 ** 7) })], [user])
+      const auctioned = await setupPool()
+      await sendInstructions([await updateConfigurationIx({ launchAuctionSecs: 0 })], [user])
+      await sendInstructions([await commitAuctionIx(auctioned, user.publicKey, 10 ** 8)], [user])
+      expect(await canMigrate(auctioned)).to.equal(2)
+      const { auctionEndTs } = await program.account.liquidityPool.fetch(getPdas(auctioned).poolPda)
+      await setTestClock(auctionEndTs)
+      await sendInstructions([await settleAuctionIx(auctioned, testClock)], [user])
+      await sendInstructions([await updateConfigurationIx({ graduationPrice: new BN(0) })], [user])
+      expect(await canMigrate(auctioned, testClock)).to.equal(6)
+      await sendInstructions([await claimAuctionIx(auctioned, user.publicKey)], [user])
+
+      // The time lock runs on the test clock
+      const { graduatedTs } = await program.account.liquidityPool.fetch(getPdas(auctioned).poolPda)
+      expect(graduatedTs.toString()).to.equal(auctionEndTs.toString())
+      expect(await canMigrate(auctioned, testClock)).to.equal(4)
+      await setTestClock(graduatedTs.addn(86_400 - 1))
+      expect(await canMigrate(auctioned, testClock)).to.equal(4)
+      await setTestClock(graduatedTs.addn(86_400))
+      expect(await canMigrate(auctioned, testClock)).to.equal(0)
+    }
+    await sendInstructions([await updateConfigurationIx({ migrationDestination: PublicKey.default })], [user])
+  })
+
+  it("swap_to_price buys the spot price up to the target", async () => {
+    const mint = await setupPool()
+    const { poolPda } = getPdas(mint)
+    await sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 7))], [user])
+
+    // Spot price in lamports per million tokens, 2 * position / 1280 SOL
+    const spotPrice = async () => {
+      const pool = await program.account.liquidityPool.fetch(poolPda)
+      const position = pool.totalSupply.sub(pool.reserveToken).toNumber() / 10 ** 15
+        + Math.sqrt(1280 * pool.virtualSolReserve.toNumber() / 10 ** 9)
+      return position * 2 / 1280 * 10 ** 9
+    }
+    const swapToPriceIx = async (targetPrice: number, maxSolIn = new BN(10 ** 10)) => program.methods
+      .swapToPrice(new BN(targetPrice), maxSolIn)
+      .accounts(await buyAccounts(mint, user.publicKey))
+      .instruction()
+
+    // The buy is bounded by the most the caller will pay, whatever the target
+    const target = Math.floor((await spotPrice()) * 3)
+    await expectCustomError(sendInstructions([await swapToPriceIx(target, new BN(1))], [user]), "MaxSolInExceeded")
+    const before = await program.account.liquidityPool.fetch(poolPda)
+    await sendInstructions([await swapToPriceIx(target)], [user])
+    const after = await program.account.liquidityPool.fetch(poolPda)
+    expect(after.reserveSol.sub(before.reserveSol).toNumber()).to.be.lessThan(10 ** 10)
+    expect(await spotPrice()).to.be.closeTo(target, target * 1e-6)
+
+    // A large trade pays the reduced fee, the SOL needed follows the fee the buy is actually charged
+    await sendInstructions([await updateConfigurationIx({ largeTradeThreshold: new BN(10 ** 6), largeTradeFeeBps: 30 })], [user])
+    const largeTarget = Math.floor((await spotPrice()) * 1.5)
+    const sig = await sendInstructions([await swapToPriceIx(largeTarget)], [user])
+    const swap = (await getEvents(sig)).find((e) => e.name === "SwapEvent").data
+    expect(swap.fee.muln(10_000).div(swap.amountIn).toNumber()).to.be.closeTo(30, 1)
+    expect(await spotPrice()).to.be.closeTo(largeTarget, largeTarget * 1e-6)
+    await sendInstructions([await updateConfigurationIx({ largeTradeThreshold: new BN(0) })], [user])
+
+    // Pushing the price down takes a sell
+    await expectCustomError(
+      sendInstructions([await swapToPriceIx(Math.floor(target / 2))], [user]),
+      "InvalidTargetPrice"
+    )
+  })
+
+  it("Curve prices below one SOL are exact against PRICE_SCALE", async () => {
+    const mint = await setupPool()
+    const { poolPda } = getPdas(mint)
+
+    // 0.0001 SOL per million tokens is 100_000 scaled: 2 * x / 1280 = 0.0001 at x = 0.064 million
+    // tokens, which cost 0.064^2 / 1280 SOL = 3200 lamports before the 1% fee
+    await sendInstructions([await updateConfigurationIx({ graduationPrice: new BN(100_000) })], [user])
+    const quote = await quoteGraduation(mint)
+    await sendInstructions([await updateConfigurationIx({ graduationPrice: new BN(0) })], [user])
+    // 3232 lamports is the least whose 1% fee, rounded down to 32, leaves the 3200
+    expect(quote.solRemaining.toNumber()).to.equal(3232)
+    expect(quote.tokenRemaining.toNumber() / 10 ** 9).to.be.closeTo(64_000, 1e-6)
+
+    // 990_000 lamports after the fee buy sqrt(1280 * 0.00099) = 1.1257 million tokens
+    const sig = await sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 6))], [user])
+    const swap = (await getEvents(sig)).find((e) => e.name === "SwapEvent").data
+    expect(swap.fee.toNumber()).to.equal(10_000)
+    expect(swap.amountOut.toNumber() / 10 ** 15).to.be.closeTo(Math.sqrt(1280 * 0.00099), 1e-9)
+    const pool = await program.account.liquidityPool.fetch(poolPda)
+    expect(pool.totalSupply.sub(pool.reserveToken).toString()).to.equal(swap.amountOut.toString())
+  })
+
+  it("Auto-compounding grows the SOL reserve by the swap fees", async () => {
+    const swapRounds = async (mint: PublicKey) => {
+      const { poolPda } = getPdas(mint)
+      const before = await program.account.liquidityPool.fetch(poolPda)
+      let fees = new BN(0)
+      for (let i = 0; i < 3; i++) {
+        const sig = await sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 7))], [user])
+        const swap = (await getEvents(sig)).find((e) => e.name === "SwapEvent").data
+        // The reported input is what the buyer paid, whether or not the fee went into the reserve
+        expect(swap.amountIn.toNumber()).to.equal(10 ** 7)
+        fees = fees.add(swap.fee)
+      }
+      const after = await program.account.liquidityPool.fetch(poolPda)
+      return {
+        reserveGrowth: after.reserveSol.sub(before.reserveSol),
+        accrued: after.feeSolAccrued.sub(before.feeSolAccrued),
+        fees,
+      }
+    }
+
+    const plain = await swapRounds(await setupPool())
+    await sendInstructions([await updateConfigurationIx({ autoCompound: true })], [user])
+    const compounded = await swapRounds(await setupPool())
+    await sendInstructions([await updateConfigurationIx({ autoCompound: false })], [user])
+
+    // Same swaps, the fees land in the reserve instead of the LP accrual
+    expect(plain.accrued.toString()).to.equal(plain.fees.toString())
+    expect(compounded.accrued.toNumber()).to.equal(0)
+    expect(compounded.reserveGrowth.toString()).to.equal(plain.reserveGrowth.add(plain.fees).toString())
+  })
+
+  it("Launch auction fills every commitment at one clearing price", async () => {
+    await sendInstructions([await updateConfigurationIx({ launchAuctionSecs: 10 })], [user])
+    const mint = await setupPool()
+    await sendInstructions([await updateConfigurationIx({ launchAuctionSecs: 0 })], [user])
+    const { poolPda } = getPdas(mint)
+    const other = Keypair.generate()
+    await sendInstructions([
+      SystemProgram.transfer({ fromPubkey: user.publicKey, toPubkey: other.publicKey, lamports: 10 ** 9 })
+    ], [user])
+    const commitIx = (owner: PublicKey, lamports: number, clock: PublicKey | null = null) =>
+      commitAuctionIx(mint, owner, lamports, clock)
+    const settleIx = (clock: PublicKey | null = null) => settleAuctionIx(mint, clock)
+    const claimIx = (owner: PublicKey) => claimAuctionIx(mint, owner)
+
+    const commitments = [[user, 10 ** 8], [other, 2 * 10 ** 8], [user, 10 ** 8]] as const
+    for (const [owner, lamports] of commitments) {
+      await sendInstructions([await commitIx(owner.publicKey, lamports)], [owner])
+    }
+    // The curve stays closed and nothing settles while commitments are taken
+    await expectCustomError(sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 7))], [user]), "AuctionActive")
+    await expectCustomError(sendInstructions([await settleIx()], [user]), "AuctionNotEnded")
+    if (!process.env.TESTING_FEATURE) {
+      return
+    }
+
+    // The auction ends on the test clock
+    const { auctionEndTs } = await program.account.liquidityPool.fetch(poolPda)
+    await setTestClock(auctionEndTs)
+    await expectCustomError(sendInstructions([await commitIx(other.publicKey, 10 ** 8, testClock)], [other]), "AuctionClosed")
+    const sig = await sendInstructions([await settleIx(testClock)], [other])
+    const settled = (await getEvents(sig)).find((e) => e.name === "AuctionSettled").data
+    expect(settled.auctionSol.toNumber()).to.equal(4 * 10 ** 8)
+
+    // Both committers put in the same amount and get the same tokens, the price is uniform
+    const claimed = async (owner: Keypair) => {
+      const claimSig = await sendInstructions([await claimIx(owner.publicKey)], [owner])
+      return (await getEvents(claimSig)).find((e) => e.name === "AuctionClaimed").data
+    }
+    const first = await claimed(user)
+    const second = await claimed(other)
+    expect(first.committed.toNumber()).to.equal(2 * 10 ** 8)
+    expect(first.tokens.toString()).to.equal(second.tokens.toString())
+    expect(first.tokens.add(second.tokens).toNumber()).to.be.closeTo(settled.tokens.toNumber(), 1)
+    expect(await connection.getAccountInfo(getCommitment(mint, other.publicKey))).to.equal(null)
+
+    // Settled, the curve opens at the price the auction moved it to
+    await sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 7))], [user])
+  })
+
+  it("An auction the curve cannot fill refunds its committers", async () => {
+    await sendInstructions([await updateConfigurationIx({ launchAuctionSecs: 5 })], [user])
+    const mint = await setupPool()
+    await sendInstructions([await updateConfigurationIx({ launchAuctionSecs: 0 })], [user])
+    const { curveConfig, poolPda, poolSolVault } = getPdas(mint)
+
+    // Commitments pass the same pause as a buy
+    const pauseAccounts = { dexConfigurationAccount: curveConfig, testClock: null, authority: user.publicKey }
+    await sendInstructions([await program.methods.setPaused(true, 1).accounts(pauseAccounts).instruction()], [user])
+    await expectCustomError(sendInstructions([await commitAuctionIx(mint, user.publicKey, 10 ** 7)], [user]), "ProgramPaused")
+    await sendInstructions([await program.methods.setPaused(false, 1).accounts(pauseAccounts).instruction()], [user])
+
+    // A fee taking the whole commitment leaves the fill nothing to buy with
+    await sendInstructions([await commitAuctionIx(mint, user.publicKey, 10 ** 7)], [user])
+    if (!process.env.TESTING_FEATURE) {
+      return
+    }
+    const { auctionEndTs } = await program.account.liquidityPool.fetch(poolPda)
+    await setTestClock(auctionEndTs)
+    await sendInstructions([await updateConfigurationIx({ feeSchedule: [{ startTs: auctionEndTs.subn(1), feeBps: 10_000 }] })], [user])
+    const sig = await sendInstructions([await settleAuctionIx(mint, testClock)], [user])
+    await sendInstructions([await updateConfigurationIx({ feeSchedule: [] })], [user])
+    expect((await getEvents(sig)).find((e) => e.name === "AuctionSettled").data.tokens.toNumber()).to.equal(0)
+
+    await expectCustomError(sendInstructions([await claimAuctionIx(mint, user.publicKey)], [user]), "AuctionNotFilled")
+    await expectCustomError(sendInstructions([await removeLiquidityIx(mint, user.publicKey)], [user]), "AuctionUnclaimed")
+
+    const vaultBefore = await connection.getBalance(poolSolVault)
+    await sendInstructions([await refundAuctionIx(mint, user.publicKey)], [user])
+    expect(vaultBefore - (await connection.getBalance(poolSolVault))).to.equal(10 ** 7)
+    expect(await connection.getAccountInfo(getCommitment(mint, user.publicKey))).to.equal(null)
+    expect((await program.account.liquidityPool.fetch(poolPda)).auctionSolClaimed.toNumber()).to.equal(10 ** 7)
+  })
+
+  it("Only whitelisted users buy during the whitelist phase", async () => {
+    const mint = await setupPool()
+    const { curveConfig, poolPda } = getPdas(mint)
+    const outsider = Keypair.generate()
+    await sendInstructions([
+      SystemProgram.transfer({ fromPubkey: user.publicKey, toPubkey: outsider.publicKey, lamports: 10 ** 9 })
+    ], [user])
+    const phaseEnd = Math.floor(Date.now() / 1000) + 6
+    await sendInstructions([
+      await program.methods
+        .setWhitelistPhase(new BN(phaseEnd))
+        .accounts({ dexConfigurationAccount: curveConfig, pool: poolPda, authority: user.publicKey })
+        .instruction(),
+      await program.methods
+        .addToWhitelist(user.publicKey)
+        .accounts({
+          dexConfigurationAccount: curveConfig,
+          pool: poolPda,
+          whitelistEntry: getWhitelistEntry(mint, user.publicKey),
+          authority: user.publicKey,
+          systemProgram: SystemProgram.programId
+        })
+        .instruction()
+    ], [user])
+
+    await sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 7))], [user])
+    await expectCustomError(
+      sendInstructions([await buyIx(mint, outsider.publicKey, new BN(10 ** 7))], [outsider]),
+      "NotWhitelisted"
+    )
+
+    // simulate_swap applies the same phase, the entry only counts for the user it was made for
+    const simulate = async (wallet: PublicKey) => {
+      const ix = await program.methods
+        .simulateSwap(0, new BN(10 ** 7))
+        .accounts({
+          dexConfigurationAccount: curveConfig,
+          pool: poolPda,
+          userState: null,
+          user: wallet,
+          whitelistEntry: getWhitelistEntry(mint, user.publicKey),
+          testClock: null
+        })
+        .instruction()
+      const sig = await sendInstructions([ix], [user])
+      return (await getEvents(sig)).find((e) => e.name === "SwapSimulation").data
+    }
+    expect((await simulate(user.publicKey)).wouldSucceed).to.equal(true)
+    const outsiderQuote = await simulate(outsider.publicKey)
+    expect(outsiderQuote.wouldSucceed).to.equal(false)
+    expect(outsiderQuote.errorCode).to.equal(program.idl.errors.find((e) => e.name === "NotWhitelisted").code)
+
+    // Once the phase ends on the test clock anyone can buy
+    if (!process.env.TESTING_FEATURE) {
+      return
+    }
+    await setTestClock(phaseEnd)
+    await sendInstructions([await buyIx(mint, outsider.publicKey, new BN(10 ** 7), null, null, testClock)], [outsider])
+    expect((await program.account.userState.fetch(getUserState(mint, outsider.publicKey))).buyVolume.toNumber()).to.be.greaterThan(0)
+  })
+
+  it("Creators are limited to max_pools_per_creator pools", async () => {
+    const [creator, other] = [Keypair.generate(), Keypair.generate()]
+    await sendInstructions([creator, other].map(({ publicKey }) =>
+      SystemProgram.transfer({ fromPubkey: user.publicKey, toPubkey: publicKey, lamports: 10 ** 8 })
+    ), [user])
+    const createPool = async (creator: Keypair) => {
+      const mint = await createMint(connection, user, user.publicKey, null, tokenDecimal)
+      return sendInstructions([await createPoolIx(mint, creator.publicKey)], [creator])
+    }
+
+    await sendInstructions([await updateConfigurationIx({ maxPoolsPerCreator: 2 })], [user])
+    await createPool(creator)
+    await createPool(creator)
+    await expectCustomError(createPool(creator), "CreatorPoolLimitReached")
+
+    // The count is kept per creator
+    await createPool(other)
+    expect((await program.account.creatorPools.fetch(getCreatorPools(creator.publicKey))).pools).to.equal(2)
+    expect((await program.account.creatorPools.fetch(getCreatorPools(other.publicKey))).pools).to.equal(1)
+    await sendInstructions([await updateConfigurationIx({ maxPoolsPerCreator: 0 })], [user])
+  })
+
+  it("create_pools_batch creates several pools in one transaction", async () => {
+    const creator = Keypair.generate()
+    await sendInstructions([
+      SystemProgram.transfer({ fromPubkey: user.publicKey, toPubkey: creator.publicKey, lamports: 10 ** 9 })
+    ], [user])
+    const createPoolsBatchIx = async (count: number) => {
+      const mints = []
+      for (let i = 0; i < count; i++) {
+        mints.push(await createMint(connection, user, user.publicKey, null, tokenDecimal))
+      }
+      const ix = await program.methods
+        .createPoolsBatch(mints.map((_, i) => ({ launchSellFeeBps: 0, launchFeeWindowSecs: 0, metadataUri: `batch-${i}` })))
+        .accounts({
+          dexConfigurationAccount: getPdas(mints[0]).curveConfig,
+          creatorPools: getCreatorPools(creator.publicKey),
+          protocolTreasury: getProtocolTreasury(),
+          payer: creator.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_PROGRAM_ID,
+          systemProgram: SystemProgram.programId
+        })
+        .remainingAccounts(mints.flatMap((mint) => {
+          const { poolPda, poolToken, poolSolVault } = getPdas(mint)
+          return [
+            { pubkey: mint, isSigner: false, isWritable: false },
+            { pubkey: poolPda, isSigner: false, isWritable: true },
+            { pubkey: poolToken, isSigner: false, isWritable: true },
+            { pubkey: poolSolVault, isSigner: false, isWritable: true },
+          ]
+        }))
+        .instruction()
+      return { ix, mints }
+    }
+
+    const { ix, mints } = await createPoolsBatchIx(3)
+    // Lamports sent to a pool address ahead of time do not block its creation
+    await sendInstructions([
+      SystemProgram.transfer({ fromPubkey: user.publicKey, toPubkey: getPdas(mints[1]).poolPda, lamports: 10 ** 6 })
+    ], [user])
+    await sendInstructions([ix], [creator])
+    for (const [i, mint] of mints.entries()) {
+      const { poolPda, poolToken } = getPdas(mint)
+      const pool = await program.account.liquidityPool.fetch(poolPda)
+      expect(pool.token.toBase58()).to.equal(mint.toBase58())
+      expect(pool.creator.toBase58()).to.equal(creator.publicKey.toBase58())
+      expect(pool.metadataUri).to.equal(`batch-${i}`)
+      expect((await getAccount(connection, poolToken)).owner.toBase58()).to.equal(poolPda.toBase58())
+    }
+    expect((await program.account.creatorPools.fetch(getCreatorPools(creator.publicKey))).pools).to.equal(3)
+
+    const tooMany = await createPoolsBatchIx(5)
+    await expectCustomError(sendInstructions([tooMany.ix], [creator]), "TooManyPools")
+  })
+
+  it("Configuration counts every pool created", async () => {
+    const { curveConfig } = getPdas(PublicKey.default)
+    const before = (await program.account.curveConfiguration.fetch(curveConfig)).poolCount
+    for (let i = 0; i < 3; i++) {
+      const mint = await createMint(connection, user, user.publicKey, null, tokenDecimal)
+      await sendInstructions([await createPoolIx(mint, user.publicKey)], [user])
+    }
+    const after = (await program.account.curveConfiguration.fetch(curveConfig)).poolCount
+    expect(after.sub(before).toNumber()).to.equal(3)
+  })
+
+  it("simulate_swap reports the guard a swap would fail", async () => {
+    const mint = await setupPool()
+    const { curveConfig, poolPda } = getPdas(mint)
+    const errorCode = (name: string) => program.idl.errors.find((e) => e.name === name).code
+    const simulate = async (lamports: number) => {
+      const ix = await program.methods
+        .simulateSwap(0, new BN(lamports))
+        .accounts({
+          dexConfigurationAccount: curveConfig,
+          pool: poolPda,
+          userState: null,
+          user: null,
+          whitelistEntry: null,
+          testClock: null
+        })
+        .instruction()
+      const sig = await sendInstructions([ix], [user])
+      return (await getEvents(sig)).find((e) => e.name === "SwapSimulation").data
+    }
+
+    const quote = await simulate(10 ** 7)
+    expect(quote.wouldSucceed).to.equal(true)
+    expect(quote.errorCode).to.equal(0)
+    expect(quote.amountOut.toNumber()).to.be.greaterThan(0)
+
+    // A swap under the configured minimum is quoted as blocked by that guard
+    await sendInstructions([await updateConfigurationIx({ minSwapAmount: new BN(10 ** 8) })], [user])
+    const blocked = await simulate(10 ** 7)
+    await expectCustomError(
+      sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 7))], [user]),
+      "AmountBelowMinimum"
+    )
+    await sendInstructions([await updateConfigurationIx({ minSwapAmount: new BN(0) })], [user])
+    expect(blocked.wouldSucceed).to.equal(false)
+    expect(blocked.errorCode).to.equal(errorCode("AmountBelowMinimum"))
+    expect(blocked.amountOut.toNumber()).to.equal(0)
+  })
+
+  it("Buyers claim the airdrop in proportion to their buy volume", async () => {
+    const mint = await setupPool()
+    const { curveConfig, poolPda } = getPdas(mint)
+    const buyer = Keypair.generate()
+    await sendInstructions([
+      SystemProgram.transfer({ fromPubkey: user.publicKey, toPubkey: buyer.publicKey, lamports: 10 ** 8 })
+    ], [user])
+
+    // The second buy, three times the first, graduates the curve
+    await sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 7))], [user])
+    await sendInstructions([await updateConfigurationIx({ graduationPrice: new BN(1) })], [user])
+    await sendInstructions([await buyIx(mint, buyer.publicKey, new BN(3 * 10 ** 7))], [buyer])
+    await sendInstructions([await updateConfigurationIx({ graduationPrice: new BN(0) })], [user])
+    expect((await program.account.liquidityPool.fetch(poolPda)).completed).to.equal(true)
+
+    const airdropMint = await createMint(connection, user, user.publicKey, null, 6)
+    const vault = await getOrCreateAssociatedTokenAccount(connection, user, airdropMint, poolPda, true)
+    await mintTo(connection, user, airdropMint, vault.address, user.publicKey, 4_000_000)
+    const setAirdropMintIx = (airdropMint: PublicKey) => program.methods
+      .setAirdropMint()
+      .accounts({ dexConfigurationAccount: curveConfig, pool: poolPda, airdropMint, authority: user.publicKey })
+      .instruction()
+    await sendInstructions([await setAirdropMintIx(airdropMint)], [user])
+    // The mint is set once, the buyers' claims cannot be moved to another token
+    await expectCustomError(
+      sendInstructions([await setAirdropMintIx(await createMint(connection, user, user.publicKey, null, 6))], [user]),
+      "AirdropMintSet"
+    )
+
+    const claim = async (claimer: Keypair) => {
+      const ix = await program.methods
+        .claimAirdrop()
+        .accounts({
+          pool: poolPda,
+          airdropMint,
+          airdropVault: vault.address,
+          userState: getUserState(mint, claimer.publicKey),
+          userAirdropAccount: getAssociatedTokenAddressSync(airdropMint, claimer.publicKey),
+          user: claimer.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_PROGRAM_ID,
+          systemProgram: SystemProgram.programId
+        })
+        .instruction()
+      const sig = await sendInstructions([ix], [claimer])
+      return (await getEvents(sig)).find((e) => e.name === "AirdropClaimed").data.amount.toNumber()
+    }
+
+    expect(await claim(user)).to.equal(1_000_000)
+    expect(await claim(buyer)).to.equal(3_000_000)
+    // A second claim has nothing left to pay
+    expect(await claim(user)).to.equal(0)
+    expect(Number((await getAccount(connection, vault.address)).amount)).to.equal(0)
+  })
+
+  it("Sells net out the buy volume", async () => {
+    const mint = await setupPool()
+    const { poolPda } = getPdas(mint)
+    const userState = getUserState(mint, user.publicKey)
+    await sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 8))], [user])
+    const bought = await program.account.userState.fetch(userState)
+    const poolBefore = await program.account.liquidityPool.fetch(poolPda)
+    expect(bought.buyVolume.toNumber()).to.equal(10 ** 8)
+    expect(poolBefore.totalBuyVolume.toNumber()).to.equal(10 ** 8)
+
+    await waitForNextSlot()
+    const tokens = new BN((await getAccount(connection, getAssociatedTokenAddressSync(mint, user.publicKey))).amount.toString())
+    const sig = await sendInstructions([await sellIx(mint, user.publicKey, tokens.divn(2))], [user])
+    const swap = (await getEvents(sig)).find((e) => e.name === "SwapEvent").data
+    const netted = swap.amountOut.add(swap.fee)
+
+    expect((await program.account.userState.fetch(userState)).buyVolume.toString())
+      .to.equal(bought.buyVolume.sub(netted).toString())
+    expect((await program.account.liquidityPool.fetch(poolPda)).totalBuyVolume.toString())
+      .to.equal(poolBefore.totalBuyVolume.sub(netted).toString())
+  })
+
+  it("Recovering stuck SOL only sends the excess over the tracked balances", async () => {
+    const mint = await setupPool()
+    const { curveConfig, poolPda, poolSolVault } = getPdas(mint)
+    await sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 8))], [user])
+    const recipient = Keypair.generate().publicKey
+    const stuck = 5 * 10 ** 7
+    await sendInstructions([
+      SystemProgram.transfer({ fromPubkey: user.publicKey, toPubkey: poolSolVault, lamports: stuck })
+    ], [user])
+    const recoverIx = async (recipient: PublicKey) => program.methods
+      .recoverStuckSol()
+      .accounts({
+        dexConfigurationAccount: curveConfig,
+        pool: poolPda,
+        poolSolVault,
+        recipient,
+        authority: user.publicKey,
+        systemProgram: SystemProgram.programId
+      })
+      .instruction()
+
+    await sendInstructions([await recoverIx(recipient)], [user])
+    const pool = await program.account.liquidityPool.fetch(poolPda)
+    expect(await connection.getBalance(recipient)).to.equal(stuck)
+    expect(await connection.getBalance(poolSolVault)).to.equal(
+      pool.reserveSol.add(pool.feeSolAccrued).add(pool.creatorFeeAccrued).toNumber()
+    )
+
+    // Nothing left to recover, the reserves stay where they are
+    const second = Keypair.generate().publicKey
+    await sendInstructions([await recoverIx(second)], [user])
+    expect(await connection.getBalance(second)).to.equal(0)
+  })
+
+  it("Creator receives the graduation payout from the raised SOL", async () => {
+    // user2 creates the pool so its balance only moves by the payout
+    const mint = await createMint(connection, user, user.publicKey, null, tokenDecimal)
+    const creatorAta = (await getOrCreateAssociatedTokenAccount(connection, user, mint, user2.publicKey)).address
+    await mintTo(connection, user, mint, creatorAta, user.publicKey, BigInt(amount.toString()))
+    await sendInstructions([await createPoolIx(mint, user2.publicKey)], [user2])
+    await sendInstructions([await addLiquidityIx(mint, user2.publicKey)], [user2])
+    const { poolPda } = getPdas(mint)
+
+    await sendInstructions([await updateConfigurationIx({
+      graduationPrice: new BN(10 ** 7),
+      creatorGraduationPayout: new BN(2 * 10 ** 7)
+    })], [user])
+    const creatorBefore = await connection.getBalance(user2.publicKey)
+    const sig = await sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 8))], [user])
+    await sendInstructions([await updateConfigurationIx({ graduationPrice: new BN(0), creatorGraduationPayout: new BN(0) })], [user])
+
+    const pool = await program.account.liquidityPool.fetch(poolPda)
+    const completed = (await getEvents(sig)).find((e) => e.name === "PoolCompleted").data
+    expect(pool.completed).to.equal(true)
+    expect(completed.creatorPayout.toNumber()).to.equal(2 * 10 ** 7)
+    expect(await connection.getBalance(user2.publicKey)).to.equal(creatorBefore + 2 * 10 ** 7)
+    expect(completed.reserveSol.toString()).to.equal(pool.reserveSol.toString())
+  })
+
+  it("LP APR quote annualizes the fees of the current window", async () => {
+    const mint = await setupPool()
+    const { poolPda } = getPdas(mint)
+    const quote = async (clock: PublicKey | null) => {
+      const ix = await program.methods.quoteLpApr().accounts({ pool: poolPda, testClock: clock }).instruction()
+      const sig = await sendInstructions([ix], [user])
+      return (await getEvents(sig)).find((e) => e.name === "LpAprQuote").data
+    }
+    const aprBps = (fees: BN, windowSecs: BN, reserveSol: BN) =>
+      fees.muln(365 * 86_400).muln(10_000).div(windowSecs.mul(reserveSol)).toString()
+
+    await sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 8))], [user])
+    await sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 8))], [user])
+    const pool = await program.account.liquidityPool.fetch(poolPda)
+    expect(pool.feesAccruedWindow.toNumber()).to.be.greaterThan(0)
+
+    // Seconds into the window, the quote annualizes over the hour floor rather than the few seconds elapsed
+    const live = await quote(null)
+    expect(live.feesAccruedWindow.toString()).to.equal(pool.feesAccruedWindow.toString())
+    expect(live.windowSecs.toNumber()).to.equal(60 * 60)
+    expect(live.aprBps.toString()).to.equal(aprBps(live.feesAccruedWindow, live.windowSecs, live.reserveSol))
+
+    if (!process.env.TESTING_FEATURE) {
+      return
+    }
+    // A day into the window, the fees earned so far are 1/365 of the yearly return
+    await setTestClock(pool.windowStartTs.addn(86_400))
+    const warped = await quote(testClock)
+    expect(warped.windowSecs.toNumber()).to.equal(86_400)
+    expect(warped.aprBps.toString()).to.equal(aprBps(pool.feesAccruedWindow, new BN(86_400), pool.reserveSol))
+  })
+
+  it("Spread quote prices buys above sells by about the fees", async () => {
+    const mint = await setupPool()
+    const { curveConfig, poolPda } = getPdas(mint)
+    await sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 9))], [user])
+
+    // 10k tokens around 35M bought, the curve itself barely moves over that size
+    const ix = await program.methods
+      .quoteSpread(new BN(10).pow(new BN(13)))
+      .accounts({ dexConfigurationAccount: curveConfig, pool: poolPda })
+      .instruction()
+    const sig = await sendInstructions([ix], [user])
+    const quote = (await getEvents(sig)).find((e) => e.name === "SpreadQuote").data
+    const buyPrice = quote.buyPrice.toNumber()
+    const sellPrice = quote.sellPrice.toNumber()
+
+    expect(buyPrice).to.be.greaterThan(sellPrice)
+    // 1% on each side
+    expect((buyPrice - sellPrice) / ((buyPrice + sellPrice) / 2) * 10_000).to.be.closeTo(200, 5)
+  })
+
+  it("Liquidity is frozen once the curve completes", async () => {
+    const mint = await setupPool()
+    await sendInstructions([await updateConfigurationIx({ graduationPrice: new BN(10 ** 7) })], [user])
+    await sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 8))], [user])
+    await sendInstructions([await updateConfigurationIx({ graduationPrice: new BN(0) })], [user])
+
+    await expectCustomError(
+      sendInstructions([await addLiquidityIx(mint, user.publicKey)], [user]),
+      "CurveComplete"
+    )
+    await expectCustomError(
+      sendInstructions([await removeLiquidityIx(mint, user.publicKey)], [user]),
+      "CurveComplete"
+    )
+  })
+
+  it("Liquidity providers claim fees in proportion to their shares", async () => {
+    // The first provider funds 100k tokens, buying them all out leaves room for a second provider
+    const mint = await setupPool(0, 0, new BN(10).pow(new BN(14)))
+    const { poolPda } = getPdas(mint)
+    const claimed = async (provider: Keypair) => {
+      const sig = await sendInstructions([await claimLpFeesIx(mint, provider.publicKey)], [provider])
+      const event = (await getEvents(sig)).find((e) => e.name === "LpFeesClaimed")
+      return event ? (event.data.amount as BN).toNumber() : 0
+    }
+
+    await sendInstructions([await buyIx(mint, user.publicKey, new BN(5 * 10 ** 8))], [user])
+    expect(await claimed(user)).to.be.greaterThan(0)
+
+    const userAta = getAssociatedTokenAddressSync(mint, user.publicKey)
+    const user2Ata = (await getOrCreateAssociatedTokenAccount(connection, user, mint, user2.publicKey)).address
+    const bought = BigInt((await connection.getTokenAccountBalance(userAta)).value.amount)
+    await transfer(connection, user, userAta, user2Ata, user, bought)
+    await mintTo(connection, user, mint, user2Ata, user.publicKey, BigInt(3) * BigInt(10 ** 14))
+    await sendInstructions([await addLiquidityIx(mint, user2.publicKey)], [user2])
+
+    // Fees earned before joining are not owed to the second provider
+    expect(await claimed(user)).to.equal(0)
+    expect(await claimed(user2)).to.equal(0)
+
+    const before = await program.account.liquidityPool.fetch(poolPda)
+    await sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 8))], [user])
+    const after = await program.account.liquidityPool.fetch(poolPda)
+    const fee = after.feeSolAccrued.sub(before.feeSolAccrued).toNumber()
+
+    // Shares are 100k tokens against 400k tokens
+    expect(await claimed(user)).to.be.closeTo(fee / 5, 1)
+    expect(await claimed(user2)).to.be.closeTo(fee * 4 / 5, 1)
+  })
+
+  it("Sell in the same slot as a buy is blocked", async () => {
+    const mint = await setupPool()
+    await sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 8))], [user])
+    const tokens = new BN((await connection.getTokenAccountBalance(getAssociatedTokenAddressSync(mint, user.publicKey))).value.amount)
+
+    // One transaction always lands in a single slot
+    await expectCustomError(
+      sendInstructions([
+        await buyIx(mint, user.publicKey, new BN(10 ** 7)),
+        await sellIx(mint, user.publicKey, tokens)
+      ], [user]),
+      "SameSlotTradeBlocked"
+    )
+    await waitForNextSlot()
+    await sendInstructions([await sellIx(mint, user.publicKey, tokens)], [user])
+  })
+
+  it("Only the authority can run a fee-free admin swap", async () => {
+    const mint = await setupPool()
+    const { poolPda } = getPdas(mint)
+    const before = await program.account.liquidityPool.fetch(poolPda)
+
+    const sig = await sendInstructions([await adminSwapIx(mint, user.publicKey, new BN(10 ** 8), 0)], [user])
+    const event = (await getEvents(sig)).find((e) => e.name === "AdminSwapEvent").data
+    const after = await program.account.liquidityPool.fetch(poolPda)
+    expect(after.feeSolAccrued.toString()).to.equal(before.feeSolAccrued.toString())
+    expect(after.reserveSol.sub(before.reserveSol).toNumber()).to.equal(10 ** 8)
+    expect(event.amountOut.toString()).to.equal(before.reserveToken.sub(after.reserveToken).toString())
+
+    await expectCustomError(
+      sendInstructions([await adminSwapIx(mint, user2.publicKey, new BN(10 ** 7), 0)], [user2]),
+      "NotAuthority"
+    )
+  })
+
+  it("Buyback spends protocol treasury SOL on the curve and can burn what it buys", async () => {
+    const mint = await setupPool()
+    const { curveConfig, poolPda, poolSolVault } = getPdas(mint)
+    const treasury = getProtocolTreasury()
+    await sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 9))], [user])
+    await sendInstructions([
+      SystemProgram.transfer({ fromPubkey: user.publicKey, toPubkey: treasury, lamports: 2 * 10 ** 8 })
+    ], [user])
+    const spotPrice = async () => {
+      const sig = await sendInstructions([await program.methods.quoteMarketCap().accounts({ pool: poolPda }).instruction()], [user])
+      return (await getEvents(sig)).find((e) => e.name === "MarketCapQuote").data.spotPrice as BN
+    }
+
+    const before = await program.account.liquidityPool.fetch(poolPda)
+    const vaultBefore = await connection.getBalance(poolSolVault)
+    const treasuryBefore = await connection.getBalance(treasury)
+    const priceBefore = await spotPrice()
+    const half = new BN(10 ** 8)
+
+    // Kept tokens become protocol tokens, the LP fees stay untouched
+    await sendInstructions([await buybackIx(mint, user.publicKey, half, false)], [user])
+    const kept = await program.account.liquidityPool.fetch(poolPda)
+    const bought = before.reserveToken.sub(kept.reserveToken)
+    expect(kept.feeSolAccrued.toString()).to.equal(before.feeSolAccrued.toString())
+    expect(kept.reserveSol.sub(before.reserveSol).toString()).to.equal(half.toString())
+    expect(kept.feeTokenAccrued.sub(before.feeTokenAccrued).toString()).to.equal(bought.toString())
+    expect(await connection.getBalance(poolSolVault)).to.equal(vaultBefore + half.toNumber())
+    expect(treasuryBefore - (await connection.getBalance(treasury))).to.equal(half.toNumber())
+    const priceKept = await spotPrice()
+    expect(priceKept.gt(priceBefore)).to.equal(true)
+
+    // The treasury keeps its rent-exempt minimum
+    const rest = new BN((await connection.getBalance(treasury)) - (await connection.getMinimumBalanceForRentExemption(0)))
+    await expectCustomError(
+      sendInstructions([await buybackIx(mint, user.publicKey, rest.addn(1), true)], [user]),
+      "InsufficientTreasury"
+    )
+
+    // Paused, a buyback is refused like any buy
+    const pauseAccounts = { dexConfigurationAccount: curveConfig, testClock: null, authority: user.publicKey }
+    await sendInstructions([await program.methods.setPaused(true, 1).accounts(pauseAccounts).instruction()], [user])
+    await expectCustomError(sendInstructions([await buybackIx(mint, user.publicKey, rest, true)], [user]), "ProgramPaused")
+    await sendInstructions([await program.methods.setPaused(false, 1).accounts(pauseAccounts).instruction()], [user])
+
+    const supplyBefore = new BN((await connection.getTokenSupply(mint)).value.amount)
+    const sig = await sendInstructions([await buybackIx(mint, user.publicKey, rest, true)], [user])
+    const burned = await program.account.liquidityPool.fetch(poolPda)
+    const event = (await getEvents(sig)).find((e) => e.name === "BuybackEvent").data
+    const supplyAfter = new BN((await connection.getTokenSupply(mint)).value.amount)
+    expect(burned.feeSolAccrued.toString()).to.equal(before.feeSolAccrued.toString())
+    expect(burned.feeTokenAccrued.toString()).to.equal(kept.feeTokenAccrued.toString())
+    expect(supplyBefore.sub(supplyAfter).toString()).to.equal(event.amountOut.toString())
+    expect(event.burned).to.equal(true)
+    expect(event.spotPriceAfter.gt(priceKept)).to.equal(true)
+
+    await expectCustomError(
+      sendInstructions([await buybackIx(mint, user2.publicKey, new BN(1), false)], [user2]),
+      "NotAuthority"
+    )
+  })
+
+  it("Selling more than the curve sold fails with InsufficientReserves", async () => {
+    const mint = await setupPool()
+    const userAta = getAssociatedTokenAddressSync(mint, user.publicKey)
+    await sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 7))], [user])
+    const bought = new BN((await connection.getTokenAccountBalance(userAta)).value.amount)
+
+    // Tokens minted outside the curve have no SOL behind them in the pool
+    await mintTo(connection, user, mint, userAta, user.publicKey, BigInt(bought.toString()))
+    await expectCustomError(
+      sendInstructions([await sellIx(mint, user.publicKey, bought.muln(2))], [user]),
+      "InsufficientReserves"
+    )
+  })
+
+  it("Creator allocation vests linearly after the cliff", async () => {
+    const mint = await setupPool()
+    const total = new BN(10).pow(new BN(15))
+    await mintTo(connection, user, mint, getAssociatedTokenAddressSync(mint, user.publicKey), user.publicKey, BigInt(total.toString()))
+    await sendInstructions([await createVestingIx(mint, total, 3, 9)], [user])
+    const { startTs } = await program.account.creatorVesting.fetch(getVesting(mint))
+
+    const claim = async (clock: PublicKey | null = null) => {
+      const sig = await sendInstructions([await claimVestedIx(mint, clock)], [user])
+      return (await getEvents(sig)).find((e) => e.name === "VestedClaimed").data
+    }
+    const vestedAt = (timestamp: BN) => total.mul(timestamp.sub(startTs)).divn(9)
+
+    // Before the cliff nothing is released
+    const early = await claim()
+    expect(early.timestamp.sub(startTs).toNumber()).to.be.lessThan(3)
+    expect(early.amount.toNumber()).to.equal(0)
+
+    if (!process.env.TESTING_FEATURE) {
+      return
+    }
+    // Past the cliff on the test clock the vested share is released, the rest at the end
+    await setTestClock(startTs.addn(4))
+    const mid = await claim(testClock)
+    expect(mid.timestamp.sub(startTs).toNumber()).to.equal(4)
+    expect(mid.claimed.toString()).to.equal(vestedAt(mid.timestamp).toString())
+
+    await setTestClock(startTs.addn(9))
+    const end = await claim(testClock)
+    expect(end.claimed.toString()).to.equal(total.toString())
+    expect(end.amount.toString()).to.equal(total.sub(mid.claimed).toString())
+  })
+
+  it("Shares are issued per whole token whatever the mint decimals", async () => {
+    const sharesFor = async (mint: PublicKey) => {
+      const [liquidityProvider] = PublicKey.findProgramAddressSync(
+        [Buffer.from(LIQUIDITY_SEED), getPdas(mint).poolPda.toBuffer(), user.publicKey.toBuffer()],
+        program.programId
+      )
+      return (await program.account.liquidityProvider.fetch(liquidityProvider)).shares
+    }
+
+    // One billion whole tokens and the same SOL in both pools
+    const nineDecimals = await setupPool()
+    const sixDecimals = await createMint(connection, user, user.publicKey, null, 6)
+    const ata = (await getOrCreateAssociatedTokenAccount(connection, user, sixDecimals, user.publicKey)).address
+    await mintTo(connection, user, sixDecimals, ata, user.publicKey, BigInt(10 ** 15))
+    await sendInstructions([await createPoolIx(sixDecimals, user.publicKey)], [user])
+    await sendInstructions([await addLiquidityIx(sixDecimals, user.publicKey)], [user])
+
+    const shares = await sharesFor(sixDecimals)
+    expect(shares.toString()).to.equal((await sharesFor(nineDecimals)).toString())
+    expect(shares.toString()).to.equal(new BN(10).pow(new BN(18)).toString())
+  })
+
+  it("First deposit at the float limit issues exact shares or fails cleanly", async () => {
+    const maxSupply = BigInt("18446744073709551615")
+
+    // The whole u64 range deposited at nine decimals still maps to finite curve math
+    const nineDecimals = await setupPool(0, 0, new BN(maxSupply.toString()))
+    const pool = await program.account.liquidityPool.fetch(getPdas(nineDecimals).poolPda)
+    expect(pool.reserveToken.toString()).to.equal(maxSupply.toString())
+    expect(pool.totalShares.toString()).to.equal(maxSupply.toString())
+
+    // Without decimals the same deposit needs more shares than fit, the deposit is refused
+    const noDecimals = await createMint(connection, user, user.publicKey, null, 0)
+    const ata = (await getOrCreateAssociatedTokenAccount(connection, user, noDecimals, user.publicKey)).address
+    await mintTo(connection, user, noDecimals, ata, user.publicKey, maxSupply)
+    await sendInstructions([await createPoolIx(noDecimals, user.publicKey)], [user])
+    await expectCustomError(
+      sendInstructions([await addLiquidityIx(noDecimals, user.publicKey)], [user]),
+      "FailedToAllocateShares"
+    )
+  })
+
+  it("Accounts whose lamports move are writable", async () => {
+    // Instruction -> accounts paying or receiving SOL, a missing `mut` only fails at runtime
+    const lamportMoves: Record<string, string[]> = {
+      initialize: ["dexConfigurationAccount", "admin"],
+      createPool: ["pool", "creatorPools", "protocolTreasury", "poolSolVault", "payer"],
+      createPoolsBatch: ["creatorPools", "protocolTreasury", "payer"],
+      createVesting: ["creatorVesting", "creator"],
+      addLiquidity: ["poolSolVault", "user"],
+      removeLiquidity: ["poolSolVault", "protocolTreasury", "user"],
+      redeemProtocolSeed: ["poolSolVault", "protocolTreasury"],
+      buy: ["poolSolVault", "creator", "referrer", "user"],
+      sell: ["poolSolVault", "referrer", "user"],
+      buyback: ["protocolTreasury", "poolSolVault", "creator"],
+      adminSwap: ["poolSolVault", "authority"],
+      claimLpFees: ["poolSolVault", "user"],
+      claimCreatorFees: ["poolSolVault", "creator"],
+      claimAirdrop: ["userAirdropAccount", "user"],
+      commitAuction: ["poolSolVault", "commitment", "user"],
+      settleAuction: ["poolSolVault", "creator"],
+      claimAuction: ["commitment", "user"],
+      refundAuction: ["poolSolVault", "commitment", "user"],
+      splitProvider: ["recipientProviderAccount", "recipient"],
+      closeLiquidityProvider: ["poolSolVault", "liquidityProviderAccount", "user"],
+    }
+    for (const [name, accounts] of Object.entries(lamportMoves)) {
+      const instruction = program.idl.instructions.find((ix) => ix.name === name)
+      for (const account of accounts) {
+        const meta = instruction.accounts.find((a) => a.name === account) as anchor.IdlAccount
+        expect(meta.isMut, `${name}.${account}`).to.equal(true)
+      }
+    }
+  })
+
+  it("Pool stores the metadata URI given at creation", async () => {
+    const uri = "https://example.com/pools/metadata.json"
+    const mint = await createMint(connection, user, user.publicKey, null, tokenDecimal)
+    await sendInstructions([await createPoolIx(mint, user.publicKey, 0, 0, uri)], [user])
+    expect((await program.account.liquidityPool.fetch(getPdas(mint).poolPda)).metadataUri).to.equal(uri)
+
+    const tooLong = await createMint(connection, user, user.publicKey, null, tokenDecimal)
+    await expectCustomError(
+      sendInstructions([await createPoolIx(tooLong, user.publicKey, 0, 0, "x".repeat(129))], [user]),
+      "MetadataUriTooLong"
+    )
+  })
+
+  // Run with TESTING_FEATURE=1 against a program built with `--features testing`
+  it("reset_pool only resets pools in testing builds", async () => {
+    const mint = await setupPool()
+    const { curveConfig, poolPda } = getPdas(mint)
+    await sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 7))], [user])
+    const resetIx = await program.methods
+      .resetPool()
+      .accounts({ dexConfigurationAccount: curveConfig, pool: poolPda, authority: user.publicKey })
+      .instruction()
+
+    if (process.env.TESTING_FEATURE) {
+      await sendInstructions([resetIx], [user])
+      const pool = await program.account.liquidityPool.fetch(poolPda)
+      expect(pool.reserveToken.toNumber()).to.equal(0)
+      expect(pool.reserveSol.toNumber()).to.equal(0)
+      expect(pool.totalSupply.toNumber()).to.equal(0)
+      expect(pool.completed).to.equal(false)
+    } else {
+      await expectCustomError(sendInstructions([resetIx], [user]), "TestingOnly")
+      expect((await program.account.liquidityPool.fetch(poolPda)).reserveSol.toNumber()).to.be.greaterThan(0)
+    }
+  })
+
+  it("A sell pays the exact curve output rounded down", async () => {
+    const mint = await setupPool()
+    const { poolPda } = getPdas(mint)
+    await sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 8))], [user])
+
+    // Same float math as `sell_amount_out`, to find a sell whose exact output has a fraction above one half
+    const pool = await program.account.liquidityPool.fetch(poolPda)
+    const unit = 1_000_000 * 10 ** pool.tokenDecimals
+    const virtualAmount = Math.sqrt(1280 * pool.virtualSolReserve.toNumber() / 1_000_000_000)
+    const sold = pool.totalSupply.toNumber() - pool.reserveToken.toNumber()
+    const position = (tokens: number) => tokens / unit + virtualAmount
+    const exactOut = (amount: number) =>
+      (position(sold) ** 2 - position(sold - amount) ** 2) / 1280 * 1_000_000_000
+    let amount = 10 ** 6
+    while (exactOut(amount) % 1 < 0.6 || exactOut(amount) % 1 > 0.9) amount += 1
+
+    await waitForNextSlot()
+    const sig = await sendInstructions([sellIx(mint, user.publicKey, new BN(amount))], [user])
+    const swap = (await getEvents(sig)).find((e) => e.name === "SwapEvent").data
+    expect(swap.amountOut.add(swap.fee).toNumber()).to.equal(Math.floor(exactOut(amount)))
+  })
+
+  it("Account sizes match their layouts", async () => {
+    const mint = await setupPool()
+    const { curveConfig, poolPda } = getPdas(mint)
+    const [liquidityProvider] = PublicKey.findProgramAddressSync(
+      [Buffer.from(LIQUIDITY_SEED), poolPda.toBuffer(), user.publicKey.toBuffer()],
+      program.programId
+    )
+    await sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 7))], [user])
+    await mintTo(connection, user, mint, getAssociatedTokenAddressSync(mint, user.publicKey), user.publicKey, BigInt(10 ** 9))
+    await sendInstructions([await createVestingIx(mint, new BN(10 ** 9), 0, 1)], [user])
+
+    const allocated = async (address: PublicKey) => (await connection.getAccountInfo(address)).data.length
+    // The fee schedule and fee recipients are allocated for MAX_FEE_TIERS and MAX_FEE_RECIPIENTS entries
+    expect(await allocated(curveConfig)).to.equal(
+      accountSize(program.idl, "CurveConfiguration", { feeSchedule: 4, feeRecipients: 4 })
+    )
+    // The metadata URI is allocated for MAX_METADATA_URI_LEN bytes
+    expect(await allocated(poolPda)).to.equal(accountSize(program.idl, "LiquidityPool", { metadataUri: 128 }))
+    expect(await allocated(liquidityProvider)).to.equal(accountSize(program.idl, "LiquidityProvider"))
+    expect(await allocated(getUserState(mint, user.publicKey))).to.equal(accountSize(program.idl, "UserState"))
+    expect(await allocated(getVesting(mint))).to.equal(accountSize(program.idl, "CreatorVesting"))
+    expect(await allocated(getCreatorPools(user.publicKey))).to.equal(accountSize(program.idl, "CreatorPools"))
+  })
+
+  it("get_config emits every stored configuration field", async () => {
+    const { curveConfig } = getPdas(PublicKey.default)
+    await sendInstructions([await updateConfigurationIx({ maxLps: 7, rewardPerSwap: new BN(42) })], [user])
+    const sig = await sendInstructions([
+      await program.methods.getConfig().accounts({ dexConfigurationAccount: curveConfig }).instruction()
+    ], [user])
+    const event = (await getEvents(sig)).find((e) => e.name === "ConfigEvent").data
+    const { bump, ...stored } = await program.account.curveConfiguration.fetch(curveConfig)
+
+    expect(Object.keys(event).sort()).to.deep.equal(Object.keys(stored).sort())
+    for (const key of Object.keys(stored)) {
+      expect(JSON.stringify(event[key]), key).to.equal(JSON.stringify(stored[key]))
+    }
+    expect(event.maxLps).to.equal(7)
+    await sendInstructions([await updateConfigurationIx({ maxLps: 0, rewardPerSwap: new BN(0) })], [user])
+  })
+
+  it("Configuration account is allocated without spare bytes", async () => {
+    const { curveConfig } = getPdas(PublicKey.default)
+    // The stored configuration Borsh-encoded with a full fee schedule and fee recipient list
+    const stored = await program.account.curveConfiguration.fetch(curveConfig)
+    const full = await program.coder.accounts.encode("CurveConfiguration", {
+      ...stored,
+      feeSchedule: Array.from({ length: 4 }, () => ({ startTs: new BN(0), feeBps: 0 })),
+      feeRecipients: Array.from({ length: 4 }, () => ({ recipient: PublicKey.default, weightBps: 0 })),
+    })
+    expect((await connection.getAccountInfo(curveConfig)).data.length).to.equal(full.length)
+  })
+
+  it("Post-swap hook is called with the swap amounts", async () => {
+    const mint = await createMint(connection, user, user.publicKey, null, tokenDecimal)
+    const ata = (await getOrCreateAssociatedTokenAccount(connection, user, mint, user.publicKey)).address
+    await mintTo(connection, user, mint, ata, user.publicKey, BigInt(amount.toString()))
+    await sendInstructions([await createPoolIx(mint, user.publicKey, 0, 0)], [user])
+    const { poolPda } = getPdas(mint)
+    const [hookRecord] = PublicKey.findProgramAddressSync([Buffer.from("hook_record")], hookProgram.programId)
+    const setHookIx = (hook: PublicKey | null) =>
+      program.methods.setPostSwapProgram(hook).accounts({ pool: poolPda, creator: user.publicKey }).instruction()
+
+    // The hook is set before trading opens and stays as it is from then on
+    await sendInstructions([await setHookIx(hookProgram.programId)], [user])
+    await sendInstructions([await addLiquidityIx(mint, user.publicKey)], [user])
+    await expectCustomError(sendInstructions([await setHookIx(null)], [user]), "PostSwapProgramLocked")
+
+    const callsBefore = (await hookProgram.account.hookRecord.fetchNullable(hookRecord))?.calls ?? new BN(0)
+    const ix = await buyIx(mint, user.publicKey, new BN(10 ** 7))
+    ix.keys.push(
+      { pubkey: hookProgram.programId, isSigner: false, isWritable: false },
+      { pubkey: hookRecord, isSigner: false, isWritable: true },
+      { pubkey: user.publicKey, isSigner: true, isWritable: true },
+      { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+    )
+    const sig = await sendInstructions([ix], [user])
+    const swap = (await getEvents(sig)).find((e) => e.name === "SwapEvent").data
+
+    const record = await hookProgram.account.hookRecord.fetch(hookRecord)
+    expect(record.calls.toNumber()).to.equal(callsBefore.toNumber() + 1)
+    expect(record.pool.toBase58()).to.equal(poolPda.toBase58())
+    expect(record.user.toBase58()).to.equal(user.publicKey.toBase58())
+    expect(record.direction).to.equal(0)
+    expect(record.amountIn.toString()).to.equal(swap.amountIn.toString())
+    expect(record.amountOut.toString()).to.equal(swap.amountOut.toString())
+    expect((await program.account.liquidityPool.fetch(poolPda)).locked).to.equal(false)
+
+    // Without the hook program in the remaining accounts the swap is refused
+    await expectCustomError(
+      sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 7))], [user]),
+      "InvalidPostSwapProgram"
+    )
+  })
+
+  it("Removals moving the spot price past the LP tolerance are rejected", async () => {
+    const mint = await setupPool()
+    const { poolPda } = getPdas(mint)
+    await sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 8))], [user])
+    await sendInstructions([await updateConfigurationIx({ maxLpPriceImpactBps: 100 })], [user])
+
+    // Removing takes every reserve, the spot price falls back to the start of the curve
+    await expectCustomError(
+      sendInstructions([await removeLiquidityIx(mint, user.publicKey)], [user]),
+      "LpPriceImpactTooHigh"
+    )
+    expect((await program.account.liquidityPool.fetch(poolPda)).reserveSol.toNumber()).to.be.greaterThan(0)
+
+    await sendInstructions([await updateConfigurationIx({ maxLpPriceImpactBps: 0 })], [user])
+    await sendInstructions([await removeLiquidityIx(mint, user.publicKey)], [user])
+  })
+
+  it("Withdraw fee is kept by the pool on remove_liquidity", async () => {
+    const mint = await setupPool()
+    const { poolPda, poolToken } = getPdas(mint)
+    const userAta = getAssociatedTokenAddressSync(mint, user.publicKey)
+    const poolBalance = new BN((await connection.getTokenAccountBalance(poolToken)).value.amount)
+
+    await sendInstructions([await updateConfigurationIx({ withdrawFeeBps: 100 })], [user])
+    await sendInstructions([await removeLiquidityIx(mint, user.publicKey)], [user])
+    await sendInstructions([await updateConfigurationIx({ withdrawFeeBps: 0 })], [user])
+
+    const fee = poolBalance.divn(100)
+    const pool = await program.account.liquidityPool.fetch(poolPda)
+    expect(pool.feeTokenAccrued.toString()).to.equal(fee.toString())
+    expect((await connection.getTokenAccountBalance(userAta)).value.amount).to.equal(poolBalance.sub(fee).toString())
+    expect((await connection.getTokenAccountBalance(poolToken)).value.amount).to.equal(fee.toString())
+  })
+
+  it("Removing liquidity leaves the fee balances and burns the creator's shares", async () => {
+    const mint = await setupPool()
+    const { poolPda, poolSolVault } = getPdas(mint)
+    await sendInstructions([await updateConfigurationIx({ creatorFeeShareBps: 2_000 })], [user])
+    await sendInstructions([await buyIx(mint, user2.publicKey, new BN(10 ** 8))], [user2])
+    await sendInstructions([await updateConfigurationIx({ creatorFeeShareBps: 0 })], [user])
+
+    const before = await program.account.liquidityPool.fetch(poolPda)
+    expect(before.creatorFeeAccrued.toNumber()).to.be.greaterThan(0)
+    await sendInstructions([await removeLiquidityIx(mint, user.publicKey)], [user])
+
+    // The creator fees stay claimable, its own LP fees went out with the reserves
+    const pool = await program.account.liquidityPool.fetch(poolPda)
+    expect(pool.creatorFeeAccrued.toString()).to.equal(before.creatorFeeAccrued.toString())
+    expect(pool.totalShares.toNumber()).to.equal(0)
+    expect(pool.accFeePerShare.toNumber()).to.equal(0)
+    const rent = await connection.getMinimumBalanceForRentExemption(0)
+    expect(await connection.getBalance(poolSolVault)).to.equal(
+      pool.feeSolAccrued.add(pool.creatorFeeAccrued).toNumber() + rent
+    )
+    const [liquidityProviderAccount] = PublicKey.findProgramAddressSync(
+      [Buffer.from(LIQUIDITY_SEED), poolPda.toBuffer(), user.publicKey.toBuffer()],
+      program.programId
+    )
+    expect((await program.account.liquidityProvider.fetch(liquidityProviderAccount)).shares.toNumber()).to.equal(0)
+  })
+
+  it("Authority withdraws accrued token fees and nothing more", async () => {
+    // Token fees accrue through the withdraw fee on remove_liquidity, swaps only take SOL fees
+    const mint = await setupPool()
+    const { poolPda, poolToken } = getPdas(mint)
+    await sendInstructions([await updateConfigurationIx({ withdrawFeeBps: 100 })], [user])
+    await sendInstructions([await removeLiquidityIx(mint, user.publicKey)], [user])
+    await sendInstructions([await updateConfigurationIx({ withdrawFeeBps: 0 })], [user])
+
+    const accrued = (await program.account.liquidityPool.fetch(poolPda)).feeTokenAccrued
+    const recipient = (await getOrCreateAssociatedTokenAccount(connection, user, mint, user2.publicKey)).address
+    await expectCustomError(
+      sendInstructions([await withdrawProtocolTokensIx(mint, recipient, accrued.addn(1))], [user]),
+      "ExceedsAccruedFees"
+    )
+    await sendInstructions([await withdrawProtocolTokensIx(mint, recipient, accrued)], [user])
+
+    expect((await connection.getTokenAccountBalance(recipient)).value.amount).to.equal(accrued.toString())
+    expect((await connection.getTokenAccountBalance(poolToken)).value.amount).to.equal("0")
+    expect((await program.account.liquidityPool.fetch(poolPda)).feeTokenAccrued.toNumber()).to.equal(0)
+  })
+
+  it("Remove liquidity", async () => {
+    try {
+      const [curveConfig] = PublicKey.findProgramAddressSync(
+        [Buffer.from(curveSeed)],
+        program.programId
+      )
+      const [poolPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from(POOL_SEED_PREFIX), mint1.toBuffer()],
+        program.programId
+      )
+
+      const poolToken = await getAssociatedTokenAddress(
+        mint1, poolPda, true
+      )
+      const userAta1 = await getAssociatedTokenAddress(
+        mint1, user.publicKey
+      )
+      const [poolSolVault, bump] = PublicKey.findProgramAddressSync(
+        [Buffer.from(SOL_VAULT_PREFIX), mint1.toBuffer()],
+        program.programId
+      )
+
+      const tx = new Transaction()
+        .add(
+          ComputeBudgetProgram.setComputeUnitLimit({ units: 200_000 }),
+          ComputeBudgetProgram.setComputeUnitPrice({ microLamports: 200_000 }),
+          await program.methods
+            .removeLiquidity(bump)
+            .accounts({
+              dexConfigurationAccount: curveConfig,
+              pool: poolPda,
+              liquidityProviderAccount: PublicKey.findProgramAddressSync(
+                [Buffer.from(LIQUIDITY_SEED), poolPda.toBuffer(), user.publicKey.toBuffer()],
+                program.programId
+              )[0],
+              tokenMint: mint1,
+              poolTokenAccount: poolToken,
+              userTokenAccount: userAta1,
+              poolSolVault,
+              protocolTreasury: PublicKey.findProgramAddressSync([Buffer.from("protocol_treasury")], program.programId)[0],
+              user: user.publicKey,
+              tokenProgram: TOKEN_PROGRAM_ID,
+              associatedTokenProgram: ASSOCIATED_PROGRAM_ID,
+              rent: SYSVAR_RENT_PUBKEY,
+              systemProgram: SystemProgram.programId
+            })
+            .instruction()
+        )
+      tx.feePayer = user.publicKey
+      tx.recentBlockhash = (await connection.getLatestBlockhash()).blockhash
+      console.log(await connection.simulateTransaction(tx))
+      const sig = await sendAndConfirmTransaction(connection, tx, [user], { skipPreflight: true })
+      console.log("Successfully added liquidity : ", `https://solscan.io/tx/${sig}?cluster=devnet`)
+    } catch (error) {
+      console.log("Error in removing liquidity", error)
+    }
+  })
+
+  it("Remove liquidity drains the pool to zero", async () => {
+    const [poolPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from(POOL_SEED_PREFIX), mint1.toBuffer()],
+      program.programId
+    )
+    const poolToken = await getAssociatedTokenAddress(
+      mint1, poolPda, true
+    )
+    const [poolSolVault] = PublicKey.findProgramAddressSync(
+      [Buffer.from(SOL_VAULT_PREFIX), mint1.toBuffer()],
+      program.programId
+    )
+
+    const pool = await program.account.liquidityPool.fetch(poolPda)
+    expect(pool.reserveToken.toNumber()).to.equal(0)
+    expect(pool.reserveSol.toNumber()).to.equal(0)
+    expect(pool.totalSupply.toNumber()).to.equal(0)
+    expect((await connection.getTokenAccountBalance(poolToken)).value.amount).to.equal("0")
+    expect(await connection.getBalance(poolSolVault)).to.equal(0)
+  })
+
+  // Runs at the end: a shortened unpause delay keeps the old one for a while
+  it("Unpausing waits out the configured delay", async () => {
+    // The delay runs on the test clock, without it the program would be left paused
+    if (!process.env.TESTING_FEATURE) {
+      return
+    }
+    const mint = await setupPool()
+    const { curveConfig } = getPdas(mint)
+    const accounts = { dexConfigurationAccount: curveConfig, testClock, authority: user.publicKey }
+    const setPausedIx = (paused: boolean) => program.methods.setPaused(paused, 1).accounts(accounts).instruction()
+    const applyUnpauseIx = () => program.methods.applyUnpause().accounts(accounts).instruction()
+    await setTestClock(await connection.getBlockTime(await connection.getSlot()))
+
+    await sendInstructions([await updateConfigurationIx({ unpauseDelaySecs: 5 })], [user])
+    await sendInstructions([await setPausedIx(true)], [user])
+    await expectCustomError(
+      sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 7))], [user]),
+      "ProgramPaused"
+    )
+
+    // The unpause is only scheduled, swaps stay halted until it is applied
+    await sendInstructions([await setPausedIx(false)], [user])
+    const { paused, unpauseReadyTs } = await program.account.curveConfiguration.fetch(curveConfig)
+    expect(paused).to.equal(true)
+    expect(unpauseReadyTs.toNumber()).to.be.greaterThan(0)
+    await expectCustomError(sendInstructions([await applyUnpauseIx()], [user]), "UnpauseNotReady")
+
+    await setTestClock(unpauseReadyTs)
+    await sendInstructions([await applyUnpauseIx()], [user])
+    expect((await program.account.curveConfiguration.fetch(curveConfig)).paused).to.equal(false)
+    await sendInstructions([await buyIx(mint, user.publicKey, new BN(10 ** 7))], [user])
+
+    // Shortening the delay only takes effect once the current delay ran out
+    await sendInstructions([await updateConfigurationIx({ unpauseDelaySecs: 0 })], [user])
+    const config = await program.account.curveConfiguration.fetch(curveConfig)
+    expect(config.unpauseDelaySecs).to.equal(5)
+    expect(config.pendingUnpauseDelaySecs).to.equal(0)
+    expect(config.unpauseDelayReadyTs.toNumber()).to.be.greaterThan(0)
+  })
+
+  // Runs last: a looser fee change cap only applies a day after it is set, so the cap stays on from here
+  it("Fee changes are capped per period", async () => {
+    const { curveConfig } = getPdas(PublicKey.default)
+    const config = () => program.account.curveConfiguration.fetch(curveConfig)
+    const feeBps = async () => Math.round((await config()).fees * 100)
+    const startFeeBps = await feeBps()
+    await sendInstructions([await updateConfigurationIx({ maxFeeChangeBps: 100, feeChangePeriodSecs: 86_400 })], [user])
+
+    // More than 1% at once is refused, a step within it goes through
+    await expectCustomError(
+      sendInstructions([await updateConfigurationIx({ feeBps: startFeeBps + 200 })], [user]),
+      "FeeChangeTooFast"
+    )
+    await sendInstructions([await updateConfigurationIx({ feeBps: startFeeBps + 50 })], [user])
+    expect(await feeBps()).to.equal(startFeeBps + 50)
+
+    // A second change in the same day is refused however small, on any fee knob
+    await expectCustomError(
+      sendInstructions([await updateConfigurationIx({ feeBps: startFeeBps })], [user]),
+      "FeeChangeTooFast"
+    )
+    await expectCustomError(
+      sendInstructions([await updateConfigurationIx({ dumpTaxMaxBps: (await config()).dumpTaxMaxBps + 1 })], [user]),
+      "FeeChangeTooFast"
+    )
+
+    // Lifting the cap waits out the time lock, the fee stays capped meanwhile
+    await sendInstructions([await updateConfigurationIx({ maxFeeChangeBps: 0 })], [user])
+    const pending = await config()
+    expect(pending.maxFeeChangeBps).to.equal(100)
+    expect(pending.pendingMaxFeeChangeBps).to.equal(0)
+    expect(pending.feeCapReadyTs.toNumber()).to.be.greaterThan(0)
+    await expectCustomError(
+      sendInstructions([await updateConfigurationIx({ feeBps: startFeeBps })], [user]),
+      "FeeChangeTooFast"
+    )
+    expect(await feeBps()).to.equal(startFeeBps + 50)
+  })
+});